@@ -0,0 +1,170 @@
+//! Benchmarks of the scheduler and the dependency graph over synthetic blocks of growing
+//! size, so a change that makes either scale worse shows up as a drop in throughput.
+//!
+//! Run with `cargo bench --bench scheduler`.
+
+use alloy_primitives::{Address, B256, U256};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use incredible_squaring_blueprint_eigenlayer::deps::{
+    AccessKey, AccessListItem, DependencyGraph, TxAccessList,
+};
+use incredible_squaring_blueprint_eigenlayer::scheduler::{schedule_parallel, TxAccess, TxId};
+
+const BLOCK_SIZES: [usize; 3] = [100, 500, 1000];
+
+/// Share of transactions writing one of a few hot storage slots, e.g. a popular pool's reserves
+const CONFLICT_DENSITIES: [(&str, f64); 3] = [("none", 0.0), ("low", 0.1), ("high", 0.5)];
+
+/// Hot slots the conflicting transactions write to
+const HOT_SLOTS: u64 = 8;
+
+/// Accounts every transaction reads a few of, which never conflict on their own
+const SHARED_ACCOUNTS: u64 = 64;
+
+/// Small deterministic xorshift generator, so every run benchmarks the same blocks
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        // The top 53 bits as a float in [0, 1)
+        let unit = (self.next() >> 11) as f64 / (1u64 << 53) as f64;
+        unit < probability
+    }
+}
+
+fn account(index: u64) -> Address {
+    let mut bytes = [0u8; 20];
+    bytes[12..].copy_from_slice(&index.to_be_bytes());
+    Address::from(bytes)
+}
+
+/// Generates the access lists of a block of `num_txs` transactions.
+///
+/// Every transaction writes an account of its own and reads two shared accounts. With
+/// probability `conflict_density` it also writes one of [`HOT_SLOTS`] slots of a single
+/// contract, conflicting with every other transaction writing that slot.
+fn synthetic_block(num_txs: usize, conflict_density: f64) -> Vec<TxAccessList> {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15 ^ num_txs as u64);
+    let hot_contract = account(u64::MAX);
+    (0..num_txs as u64)
+        .map(|i| {
+            let reads = (0..2)
+                .map(|_| AccessListItem {
+                    address: account((1 << 32) | rng.below(SHARED_ACCOUNTS)),
+                    storage_keys: Vec::new(),
+                })
+                .collect();
+            let mut writes = vec![AccessListItem {
+                address: account(i),
+                storage_keys: Vec::new(),
+            }];
+            if rng.chance(conflict_density) {
+                writes.push(AccessListItem {
+                    address: hot_contract,
+                    storage_keys: vec![B256::from(U256::from(rng.below(HOT_SLOTS)))],
+                });
+            }
+            TxAccessList { reads, writes }
+        })
+        .collect()
+}
+
+/// Converts the access lists into the scheduler's input, with ids in block order
+fn tx_accesses(block: &[TxAccessList]) -> Vec<TxAccess> {
+    let keys = |items: &[AccessListItem]| -> Vec<AccessKey> {
+        items
+            .iter()
+            .flat_map(|item| {
+                let slots: Vec<AccessKey> = item
+                    .storage_keys
+                    .iter()
+                    .map(|slot| (item.address, *slot).into())
+                    .collect();
+                if slots.is_empty() {
+                    vec![item.address.into()]
+                } else {
+                    slots
+                }
+            })
+            .collect()
+    };
+    block
+        .iter()
+        .enumerate()
+        .map(|(id, tx)| {
+            TxAccess::new(id as TxId)
+                .with_reads(keys(&tx.reads))
+                .with_writes(keys(&tx.writes))
+        })
+        .collect()
+}
+
+fn bench_schedule_parallel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("schedule_parallel");
+    for (density_name, density) in CONFLICT_DENSITIES {
+        for num_txs in BLOCK_SIZES {
+            let txs = tx_accesses(&synthetic_block(num_txs, density));
+            group.throughput(Throughput::Elements(num_txs as u64));
+            group.bench_with_input(BenchmarkId::new(density_name, num_txs), &txs, |b, txs| {
+                b.iter(|| schedule_parallel(black_box(txs)))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_dependency_graph(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dependency_graph");
+    for (density_name, density) in CONFLICT_DENSITIES {
+        for num_txs in BLOCK_SIZES {
+            let block = synthetic_block(num_txs, density);
+            group.throughput(Throughput::Elements(num_txs as u64));
+            group.bench_with_input(
+                BenchmarkId::new(density_name, num_txs),
+                &block,
+                |b, block| {
+                    b.iter(|| {
+                        DependencyGraph::from_transactions(black_box(block)).topological_layers()
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+/// Indexed against pairwise construction of the dependency graph, on the densest blocks
+fn bench_dependency_graph_construction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dependency_graph_construction");
+    let (_, density) = CONFLICT_DENSITIES[CONFLICT_DENSITIES.len() - 1];
+    for num_txs in BLOCK_SIZES {
+        let block = synthetic_block(num_txs, density);
+        group.throughput(Throughput::Elements(num_txs as u64));
+        group.bench_with_input(BenchmarkId::new("indexed", num_txs), &block, |b, block| {
+            b.iter(|| DependencyGraph::from_transactions(black_box(block)))
+        });
+        group.bench_with_input(BenchmarkId::new("pairwise", num_txs), &block, |b, block| {
+            b.iter(|| DependencyGraph::from_transactions_pairwise(black_box(block)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_schedule_parallel,
+    bench_dependency_graph,
+    bench_dependency_graph_construction
+);
+criterion_main!(benches);
@@ -26,6 +26,8 @@ contract IncredibleSquaringTaskManager is
     uint32 public immutable TASK_RESPONSE_WINDOW_BLOCK;
     uint32 public constant TASK_CHALLENGE_WINDOW_BLOCK = 100;
     uint256 internal constant _THRESHOLD_DENOMINATOR = 100;
+    bytes32 public constant TASK_RESPONSE_DOMAIN_TYPEHASH =
+        keccak256("ParallelExecTaskResponse(uint256 chainId,address taskManager)");
 
     /* STORAGE */
     uint32 public latestTaskNum;
@@ -107,8 +109,8 @@ contract IncredibleSquaringTaskManager is
         );
 
         /* CHECKING SIGNATURES & WHETHER THRESHOLD IS MET OR NOT */
-        // calculate message which operators signed
-        bytes32 message = keccak256(abi.encode(taskResponse));
+        // calculate message which operators signed, bound to this task manager on this chain
+        bytes32 message = taskResponseSigningHash(taskResponse);
 
         // check the BLS signature
         (
@@ -151,6 +153,32 @@ contract IncredibleSquaringTaskManager is
         return latestTaskNum;
     }
 
+    // NOTE: binds operator signatures to this task manager on this chain, so they cannot be
+    // replayed against another deployment accepting the same task responses.
+    function domainSeparator() public view returns (bytes32) {
+        return
+            keccak256(
+                abi.encode(
+                    TASK_RESPONSE_DOMAIN_TYPEHASH,
+                    block.chainid,
+                    address(this)
+                )
+            );
+    }
+
+    function taskResponseSigningHash(
+        TaskResponse calldata taskResponse
+    ) public view returns (bytes32) {
+        return
+            keccak256(
+                abi.encodePacked(
+                    "\x19\x01",
+                    domainSeparator(),
+                    keccak256(abi.encode(taskResponse))
+                )
+            );
+    }
+
     // NOTE: this function enables a challenger to raise and resolve a challenge.
     function raiseAndResolveChallenge(
         Task calldata task,
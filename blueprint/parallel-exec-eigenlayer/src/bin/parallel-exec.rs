@@ -0,0 +1,12 @@
+use clap::Parser;
+use incredible_squaring_blueprint_eigenlayer::cli::{run, Cli};
+use incredible_squaring_blueprint_eigenlayer::config::Config;
+
+#[tokio::main]
+async fn main() -> color_eyre::Result<()> {
+    let cli = Cli::parse();
+    // Reads the same settings as the operator, problems are reported by `doctor`
+    let config = Config::load_unvalidated()?;
+    run(cli, &config, &mut std::io::stdout()).await?;
+    Ok(())
+}
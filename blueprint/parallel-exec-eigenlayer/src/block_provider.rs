@@ -0,0 +1,207 @@
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+
+use crate::api_client::{read_blocks_file, ApiClient, ApiClientError, Block};
+
+/// Serves the blocks tasks are computed from, so the helper API, local files and canned
+/// blocks in tests are interchangeable.
+///
+/// An [`ApiClient`] fetches the blocks of a task window through its provider when one is set,
+/// see [`ApiClient::with_block_provider`], and still verifies and hashes them itself.
+#[async_trait]
+pub trait BlockProvider: Debug + Send + Sync {
+    /// Returns the blocks numbered within `range` in ascending order. Blocks the provider does
+    /// not have are left out, an empty range is an [`ApiClientError::InvalidRange`].
+    async fn fetch(&self, range: RangeInclusive<u64>) -> Result<Vec<Block>, ApiClientError>;
+}
+
+#[async_trait]
+impl BlockProvider for ApiClient {
+    async fn fetch(&self, range: RangeInclusive<u64>) -> Result<Vec<Block>, ApiClientError> {
+        self.get_blocks_in_range(*range.start(), *range.end()).await
+    }
+}
+
+/// Reads blocks from a helper API response saved as JSON, e.g. an
+/// [`ApiRecording`](crate::contexts::api_recordings::ApiRecording)
+#[derive(Debug, Clone)]
+pub struct FileBlockProvider {
+    path: PathBuf,
+}
+
+impl FileBlockProvider {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl BlockProvider for FileBlockProvider {
+    async fn fetch(&self, range: RangeInclusive<u64>) -> Result<Vec<Block>, ApiClientError> {
+        check_range(&range)?;
+        let response = read_blocks_file(&self.path, Some((*range.start(), *range.end()))).await?;
+        Ok(response.data)
+    }
+}
+
+/// Serves blocks held in memory and records every range asked for, for tests
+#[derive(Debug, Clone, Default)]
+pub struct MockBlockProvider {
+    blocks: Vec<Block>,
+    requests: Arc<Mutex<Vec<RangeInclusive<u64>>>>,
+}
+
+impl MockBlockProvider {
+    pub fn new(blocks: Vec<Block>) -> Self {
+        Self {
+            blocks,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the ranges fetched so far, shared between clones
+    pub fn requests(&self) -> Vec<RangeInclusive<u64>> {
+        self.requests.lock().clone()
+    }
+}
+
+#[async_trait]
+impl BlockProvider for MockBlockProvider {
+    async fn fetch(&self, range: RangeInclusive<u64>) -> Result<Vec<Block>, ApiClientError> {
+        check_range(&range)?;
+        self.requests.lock().push(range.clone());
+
+        let mut blocks = Vec::new();
+        for block in &self.blocks {
+            if range.contains(&block.number_u64()?) {
+                blocks.push(block.clone());
+            }
+        }
+        Ok(blocks)
+    }
+}
+
+fn check_range(range: &RangeInclusive<u64>) -> Result<(), ApiClientError> {
+    if range.is_empty() {
+        return Err(ApiClientError::InvalidRange {
+            from: *range.start(),
+            to: *range.end(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::{hash_blocks, ApiResponse, BlockSource};
+
+    /// Three chained blocks numbered 16 to 18
+    fn chain() -> Vec<Block> {
+        let hashes = [
+            "0x2946018d361c6825c64c35c1421f3d80b16c3262dd01234a65dbf6bdb4422121",
+            "0x0a885e8a3a5cb03e0540e7ef65734c46189f17a65596009570a227d3abb6d466",
+            "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+        ];
+        let mut parent_hash = format!("0x{}", "00".repeat(32));
+        hashes
+            .iter()
+            .enumerate()
+            .map(|(i, hash)| {
+                let block = serde_json::from_value(serde_json::json!({
+                    "hash": hash,
+                    "number": format!("{:#x}", 16 + i),
+                    "timestamp": "0x67c2a1b0",
+                    "transactions_root": format!("0x{}", "00".repeat(32)),
+                    "parent_hash": parent_hash,
+                }))
+                .unwrap();
+                parent_hash = hash.to_string();
+                block
+            })
+            .collect()
+    }
+
+    /// The assertions every provider has to pass, serving the blocks of [`chain`]
+    async fn assert_serves_chain(provider: &dyn BlockProvider) {
+        let numbers = |blocks: Vec<Block>| -> Vec<u64> {
+            blocks
+                .iter()
+                .map(|block| block.number_u64().unwrap())
+                .collect()
+        };
+
+        assert_eq!(
+            numbers(provider.fetch(16..=18).await.unwrap()),
+            vec![16, 17, 18]
+        );
+        assert_eq!(numbers(provider.fetch(17..=17).await.unwrap()), vec![17]);
+        // Only the blocks the provider has are returned
+        assert_eq!(numbers(provider.fetch(18..=30).await.unwrap()), vec![18]);
+        assert!(provider.fetch(20..=30).await.unwrap().is_empty());
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = provider.fetch(18..=16).await;
+        assert!(matches!(
+            empty,
+            Err(ApiClientError::InvalidRange { from: 18, to: 16 })
+        ));
+    }
+
+    fn blocks_file(dir: &tempfile::TempDir) -> PathBuf {
+        let path = dir.path().join("blocks.json");
+        let response = ApiResponse {
+            status: "success".to_string(),
+            message: "ok".to_string(),
+            data: chain(),
+        };
+        std::fs::write(&path, serde_json::to_vec(&response).unwrap()).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_api_client_provider() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let client = ApiClient::new().with_block_source(BlockSource::File(blocks_file(&dir)));
+        assert_serves_chain(&client).await;
+    }
+
+    #[tokio::test]
+    async fn test_file_provider() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert_serves_chain(&FileBlockProvider::new(blocks_file(&dir))).await;
+
+        let missing = FileBlockProvider::new(dir.path().join("missing.json"));
+        assert!(matches!(
+            missing.fetch(16..=18).await,
+            Err(ApiClientError::File { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mock_provider() {
+        let provider = MockBlockProvider::new(chain());
+        assert_serves_chain(&provider).await;
+        assert_eq!(provider.requests()[..2], [16..=18, 17..=17]);
+    }
+
+    #[tokio::test]
+    async fn test_api_client_hashes_blocks_of_its_provider() {
+        let provider = MockBlockProvider::new(chain());
+        let client = ApiClient::new().with_block_provider(Arc::new(provider.clone()));
+
+        let calculation = client.get_calculation_at_detailed(18).await.unwrap();
+        assert_eq!(calculation.blocks.len(), 3);
+        assert_eq!(
+            calculation.hash,
+            hash_blocks(&chain(), Default::default()).unwrap()
+        );
+        assert_eq!(provider.requests(), vec![9..=18]);
+        // Nothing was requested from the helper API
+        assert_eq!(client.metrics().requests(), 0);
+    }
+}
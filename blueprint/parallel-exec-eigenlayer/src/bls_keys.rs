@@ -0,0 +1,146 @@
+use blueprint_sdk::crypto::bn254::{ArkBlsBn254, ArkBlsBn254Public};
+use blueprint_sdk::keystore::backends::Backend;
+use blueprint_sdk::keystore::Keystore;
+use blueprint_sdk::macros::ext::keystore::backends::bn254::Bn254Backend;
+use eigensdk::crypto_bls::BlsKeyPair;
+use thiserror::Error;
+
+use crate::jobs::compute_x_square::TaskStatus;
+
+#[derive(Debug, Error)]
+pub enum KeystoreError {
+    #[error("No local BLS BN254 key found: {0}")]
+    NoLocalKey(String),
+    #[error("Failed to expose BLS BN254 secret: {0}")]
+    SecretExposure(String),
+    #[error("No BLS BN254 secret stored for the local public key")]
+    MissingSecret,
+    #[error("Invalid BLS key pair: {0}")]
+    InvalidKeyPair(String),
+}
+
+impl KeystoreError {
+    /// Returns the task status reported when a task fails with this error
+    pub fn status(&self) -> TaskStatus {
+        match self {
+            KeystoreError::InvalidKeyPair(_) => TaskStatus::SigningFailed,
+            _ => TaskStatus::NoKey,
+        }
+    }
+}
+
+/// The keystore operations needed to load the operator's BLS key
+pub trait BlsKeystore {
+    type Public;
+
+    /// Returns the first locally stored BLS BN254 public key
+    fn first_local_bls(&self) -> Result<Self::Public, String>;
+
+    /// Returns the secret belonging to `public` as a decimal field element
+    fn expose_bls_secret(&self, public: &Self::Public) -> Result<Option<String>, String>;
+}
+
+impl BlsKeystore for Keystore {
+    type Public = ArkBlsBn254Public;
+
+    fn first_local_bls(&self) -> Result<Self::Public, String> {
+        self.first_local::<ArkBlsBn254>().map_err(|e| e.to_string())
+    }
+
+    fn expose_bls_secret(&self, public: &Self::Public) -> Result<Option<String>, String> {
+        self.expose_bls_bn254_secret(public)
+            .map(|secret| secret.map(|secret| secret.0.to_string()))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// A BLS secret handed to the operator directly instead of through the keystore, as a decimal
+/// field element
+pub struct BlsSecret(pub String);
+
+impl BlsKeystore for BlsSecret {
+    type Public = ();
+
+    fn first_local_bls(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn expose_bls_secret(&self, _public: &()) -> Result<Option<String>, String> {
+        Ok(Some(self.0.trim().to_string()))
+    }
+}
+
+/// Loads the operator's BLS key pair, reporting which step failed
+pub fn load_bls_key_pair<K: BlsKeystore>(keystore: &K) -> Result<BlsKeyPair, KeystoreError> {
+    let public = keystore
+        .first_local_bls()
+        .map_err(KeystoreError::NoLocalKey)?;
+    let secret = keystore
+        .expose_bls_secret(&public)
+        .map_err(KeystoreError::SecretExposure)?
+        .ok_or(KeystoreError::MissingSecret)?;
+    BlsKeyPair::new(secret).map_err(|e| KeystoreError::InvalidKeyPair(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::compute_x_square::operator_id_from_key;
+    use crate::test_support::StubKeystore;
+
+    #[test]
+    fn test_no_local_key() {
+        let keystore = StubKeystore {
+            public: Err("keystore is empty".to_string()),
+            secret: Ok(Some("1".to_string())),
+        };
+
+        let err = load_bls_key_pair(&keystore).unwrap_err();
+        assert!(matches!(&err, KeystoreError::NoLocalKey(msg) if msg == "keystore is empty"));
+        assert_eq!(err.status(), TaskStatus::NoKey);
+    }
+
+    #[test]
+    fn test_secret_exposure_fails() {
+        let keystore = StubKeystore {
+            public: Ok(()),
+            secret: Err("permission denied".to_string()),
+        };
+
+        let err = load_bls_key_pair(&keystore).unwrap_err();
+        assert!(matches!(&err, KeystoreError::SecretExposure(msg) if msg == "permission denied"));
+        assert_eq!(err.status(), TaskStatus::NoKey);
+    }
+
+    #[test]
+    fn test_missing_secret() {
+        let keystore = StubKeystore {
+            public: Ok(()),
+            secret: Ok(None),
+        };
+
+        let err = load_bls_key_pair(&keystore).unwrap_err();
+        assert!(matches!(err, KeystoreError::MissingSecret));
+        assert_eq!(err.status(), TaskStatus::NoKey);
+    }
+
+    #[test]
+    fn test_loads_key_pair() {
+        let keystore = StubKeystore {
+            public: Ok(()),
+            secret: Ok(Some("12345".to_string())),
+        };
+
+        let key_pair = load_bls_key_pair(&keystore).unwrap();
+        assert_eq!(
+            operator_id_from_key(key_pair),
+            operator_id_from_key(BlsKeyPair::new("12345".to_string()).unwrap())
+        );
+
+        let key_pair = load_bls_key_pair(&BlsSecret(" 12345\n".to_string())).unwrap();
+        assert_eq!(
+            operator_id_from_key(key_pair),
+            operator_id_from_key(BlsKeyPair::new("12345".to_string()).unwrap())
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Failed fetches in a row after which the helper API circuit breaker opens
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+/// How long an open circuit breaker fails fetches before probing the helper API again
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// When a [`CircuitBreaker`] opens and for how long
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the breaker
+    pub failure_threshold: u32,
+    /// Time the breaker stays open before letting a probe through
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_COOLDOWN,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+        }
+    }
+}
+
+/// State of a [`CircuitBreaker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go through, counting the failures in a row
+    Closed { consecutive_failures: u32 },
+    /// Requests fail fast until the cooldown ends
+    Open { until: Instant },
+    /// The cooldown ended and a single probe is in flight since `since`, every other request
+    /// fails fast. A probe whose outcome is never recorded, e.g. because its request was
+    /// cancelled, is replaced by a new one after another cooldown.
+    HalfOpen { since: Instant },
+}
+
+/// What a [`CircuitBreaker`] decided about a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    /// The request may be sent
+    Allowed,
+    /// The request is the probe of a half-open breaker, its outcome closes or reopens it
+    Probe,
+    /// The request must fail without being sent, the breaker lets a probe through after
+    /// `retry_in`
+    Rejected { retry_in: Duration },
+}
+
+/// What recording an outcome did to a [`CircuitBreaker`], so callers can log transitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    None,
+    Opened,
+    Closed,
+}
+
+/// Circuit breaker shared between clones, failing requests fast while an upstream keeps
+/// failing instead of sending every one of them into the outage.
+///
+/// After [`CircuitBreakerConfig::failure_threshold`] failures in a row the breaker opens and
+/// rejects requests for the cooldown. It then half-opens, letting one probe through: a
+/// success closes it again, a failure reopens it for another cooldown.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Arc<Mutex<CircuitState>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: Arc::new(Mutex::new(CircuitState::Closed {
+                consecutive_failures: 0,
+            })),
+        }
+    }
+
+    pub fn config(&self) -> CircuitBreakerConfig {
+        self.config
+    }
+
+    pub fn state(&self) -> CircuitState {
+        *self.state.lock()
+    }
+
+    /// Decides whether a request may be sent at `now`
+    pub fn admit(&self, now: Instant) -> Admission {
+        let mut state = self.state.lock();
+        match *state {
+            CircuitState::Closed { .. } => Admission::Allowed,
+            CircuitState::Open { until } if now < until => Admission::Rejected {
+                retry_in: until - now,
+            },
+            CircuitState::Open { .. } => {
+                *state = CircuitState::HalfOpen { since: now };
+                Admission::Probe
+            }
+            // The probe decides shortly, until then nothing else is sent
+            CircuitState::HalfOpen { since } if now < since + self.config.cooldown => {
+                Admission::Rejected {
+                    retry_in: since + self.config.cooldown - now,
+                }
+            }
+            // The probe was abandoned without an outcome, let another one through
+            CircuitState::HalfOpen { .. } => {
+                *state = CircuitState::HalfOpen { since: now };
+                Admission::Probe
+            }
+        }
+    }
+
+    /// Records a request that reached a healthy upstream
+    pub fn record_success(&self) -> Transition {
+        let mut state = self.state.lock();
+        let transition = match *state {
+            CircuitState::Closed { .. } => Transition::None,
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => Transition::Closed,
+        };
+        *state = CircuitState::Closed {
+            consecutive_failures: 0,
+        };
+        transition
+    }
+
+    /// Records a request that failed at `now` because of the upstream
+    pub fn record_failure(&self, now: Instant) -> Transition {
+        let mut state = self.state.lock();
+        let open = CircuitState::Open {
+            until: now + self.config.cooldown,
+        };
+        match *state {
+            CircuitState::Closed {
+                consecutive_failures,
+            } => {
+                let consecutive_failures = consecutive_failures + 1;
+                if consecutive_failures >= self.config.failure_threshold {
+                    *state = open;
+                    Transition::Opened
+                } else {
+                    *state = CircuitState::Closed {
+                        consecutive_failures,
+                    };
+                    Transition::None
+                }
+            }
+            CircuitState::HalfOpen { .. } => {
+                *state = open;
+                Transition::Opened
+            }
+            // A request admitted before the breaker opened failed as well
+            CircuitState::Open { .. } => Transition::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig::new(3, Duration::from_secs(10)));
+        let now = Instant::now();
+
+        assert_eq!(breaker.record_failure(now), Transition::None);
+        assert_eq!(breaker.record_failure(now), Transition::None);
+        // A success in between starts the count over
+        assert_eq!(breaker.record_success(), Transition::None);
+        assert_eq!(breaker.record_failure(now), Transition::None);
+        assert_eq!(breaker.record_failure(now), Transition::None);
+        assert_eq!(breaker.admit(now), Admission::Allowed);
+        assert_eq!(breaker.record_failure(now), Transition::Opened);
+
+        assert_eq!(
+            breaker.admit(now + Duration::from_secs(4)),
+            Admission::Rejected {
+                retry_in: Duration::from_secs(6)
+            }
+        );
+    }
+
+    #[test]
+    fn test_breaker_half_opens_after_the_cooldown() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig::new(1, Duration::from_secs(10)));
+        let now = Instant::now();
+        breaker.record_failure(now);
+
+        // One probe goes through once the cooldown is over, the rest wait for its outcome
+        let after_cooldown = now + Duration::from_secs(10);
+        assert_eq!(breaker.admit(after_cooldown), Admission::Probe);
+        assert_eq!(
+            breaker.state(),
+            CircuitState::HalfOpen {
+                since: after_cooldown
+            }
+        );
+        assert!(matches!(
+            breaker.admit(after_cooldown),
+            Admission::Rejected { .. }
+        ));
+
+        // A failed probe reopens the breaker for another cooldown
+        assert_eq!(breaker.record_failure(after_cooldown), Transition::Opened);
+        assert_eq!(
+            breaker.state(),
+            CircuitState::Open {
+                until: after_cooldown + Duration::from_secs(10)
+            }
+        );
+
+        // A successful one closes it
+        let recovered = after_cooldown + Duration::from_secs(10);
+        assert_eq!(breaker.admit(recovered), Admission::Probe);
+        assert_eq!(breaker.record_success(), Transition::Closed);
+        assert_eq!(breaker.admit(recovered), Admission::Allowed);
+        assert_eq!(
+            breaker.state(),
+            CircuitState::Closed {
+                consecutive_failures: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_abandoned_probe_is_replaced_after_the_cooldown() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig::new(1, Duration::from_secs(10)));
+        let now = Instant::now();
+        breaker.record_failure(now);
+        let probe_sent = now + Duration::from_secs(10);
+        assert_eq!(breaker.admit(probe_sent), Admission::Probe);
+
+        // The probe never reports back, e.g. because its task was cancelled at its deadline
+        assert_eq!(
+            breaker.admit(probe_sent + Duration::from_secs(4)),
+            Admission::Rejected {
+                retry_in: Duration::from_secs(6)
+            }
+        );
+        let replaced = probe_sent + Duration::from_secs(10);
+        assert_eq!(breaker.admit(replaced), Admission::Probe);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen { since: replaced });
+        assert!(matches!(
+            breaker.admit(replaced),
+            Admission::Rejected { .. }
+        ));
+        assert_eq!(breaker.record_success(), Transition::Closed);
+    }
+
+    #[test]
+    fn test_clones_share_the_breaker() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig::new(2, Duration::from_secs(10)));
+        let clone = breaker.clone();
+        let now = Instant::now();
+
+        breaker.record_failure(now);
+        assert_eq!(clone.record_failure(now), Transition::Opened);
+        assert!(matches!(breaker.admit(now), Admission::Rejected { .. }));
+    }
+}
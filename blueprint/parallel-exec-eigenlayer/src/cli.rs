@@ -0,0 +1,561 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use alloy_primitives::B256;
+use blueprint_sdk::keystore::{Keystore, KeystoreConfig};
+use clap::{Parser, Subcommand};
+use thiserror::Error;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use crate::api_client::{ApiClient, ApiClientError, Block};
+use crate::bls_keys::{load_bls_key_pair, BlsKeystore, BlsSecret};
+use crate::config::Config;
+use crate::constants::ConfigError;
+use crate::contexts::api_recordings::{ApiRecordingError, ApiRecordings};
+use crate::contexts::client::AggregatorClient;
+use crate::jobs::compute_x_square::{
+    connect_remote_signer, operator_id_from_key, schedule_for_task,
+};
+use crate::signer::{
+    ecdsa_signer_from_key, load_ecdsa_signer, BlsTaskSigner, SignatureScheme, SignerBackend,
+    SigningDomain, TaskSigner,
+};
+use crate::IIncredibleSquaringTaskManager::TaskResponse;
+
+/// Keystore the operator's keys are read from when `--keystore` is not given
+pub const DEFAULT_KEYSTORE_PATH: &str = "./target/keystore";
+
+/// How long the aggregator may take to accept a connection before it counts as unreachable
+const AGGREGATOR_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error(transparent)]
+    Api(#[from] ApiClientError),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("{failed} of {total} checks failed")]
+    ChecksFailed { failed: usize, total: usize },
+    #[error(transparent)]
+    Recording(#[from] ApiRecordingError),
+    #[error("No API response was recorded for task {0}")]
+    NotRecorded(u32),
+    #[error("Replaying task {0} did not reproduce the recorded hashes")]
+    ReplayMismatch(u32),
+}
+
+/// Debugging tools for the parallel execution operator, run without starting the blueprint
+#[derive(Debug, Parser)]
+#[command(name = "parallel-exec", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Fetches the latest blocks from the helper API once and prints the calculation hash
+    /// along with the numbers of the blocks it was computed from
+    FetchHash {
+        /// Helper API to query instead of the configured one
+        #[arg(long)]
+        url: Option<String>,
+    },
+    /// Checks that the configured signer backend signs, the helper API serves blocks and the
+    /// aggregator is reachable, printing a pass/fail checklist
+    Doctor {
+        /// Keystore holding the operator's key, when signing with the keystore backend
+        #[arg(long, default_value = DEFAULT_KEYSTORE_PATH)]
+        keystore: PathBuf,
+        /// Helper API to query instead of the configured one
+        #[arg(long)]
+        url: Option<String>,
+        /// Aggregator to connect to instead of the configured one
+        #[arg(long)]
+        aggregator: Option<String>,
+    },
+    /// Hashes and schedules the API response recorded for a task again, checking that it
+    /// reproduces the hashes the task was answered with
+    Replay {
+        /// Directory the responses were recorded to, as set in `API_RECORDING_DIR`
+        #[arg(long)]
+        dir: PathBuf,
+        /// Index of the task to replay
+        task_index: u32,
+    },
+}
+
+/// Outcome of one `doctor` check, with what was found or what went wrong
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub result: Result<String, String>,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Runs `cli` with the helper API, aggregator and hash settings of `config`, writing its
+/// output to `out`
+pub async fn run(cli: Cli, config: &Config, out: &mut impl Write) -> Result<(), CliError> {
+    match cli.command {
+        Command::FetchHash { url } => {
+            let mut api_client = config.api.api_client()?;
+            if let Some(url) = url {
+                api_client = api_client.with_base_url(url);
+            }
+            fetch_hash(&api_client, out).await
+        }
+        Command::Doctor {
+            keystore,
+            url,
+            aggregator,
+        } => {
+            let api_client = config.api.api_client().map(|api_client| match url {
+                Some(url) => api_client.with_base_url(url),
+                None => api_client,
+            });
+            let aggregator = match aggregator {
+                Some(aggregator) => Ok(aggregator),
+                None => config
+                    .aggregator
+                    .address()
+                    .map(|address| address.to_string())
+                    .map_err(|e| e.to_string()),
+            };
+
+            let checks = vec![
+                Check {
+                    name: "config",
+                    result: config
+                        .validate()
+                        .map(|_| "all settings are valid".to_string())
+                        .map_err(|e| e.to_string()),
+                },
+                Check {
+                    name: "signer",
+                    result: check_configured_signer(config, &keystore).await,
+                },
+                Check {
+                    name: "helper api",
+                    result: match api_client {
+                        Ok(api_client) => check_api(&api_client).await,
+                        Err(e) => Err(e.to_string()),
+                    },
+                },
+                Check {
+                    name: "aggregator",
+                    result: match aggregator {
+                        Ok(aggregator) => check_aggregator(&aggregator).await,
+                        Err(e) => Err(e),
+                    },
+                },
+            ];
+            write_checklist(&checks, out)
+        }
+        Command::Replay { dir, task_index } => replay(
+            &ApiRecordings::open(dir)?,
+            task_index,
+            &config.api.api_client()?,
+            out,
+        ),
+    }
+}
+
+fn open_keystore(path: &Path) -> Result<Keystore, String> {
+    Keystore::new(KeystoreConfig::new().fs_root(path))
+        .map_err(|e| format!("Failed to open keystore at {}: {}", path.display(), e))
+}
+
+/// Loads the signer of the configured backend and scheme like the operator does and checks
+/// that it signs, reading keystore keys from `keystore`
+pub async fn check_configured_signer(config: &Config, keystore: &Path) -> Result<String, String> {
+    let settings = &config.signer;
+    let signer: Box<dyn TaskSigner> = match (settings.backend, config.signature_scheme) {
+        (SignerBackend::Keystore, SignatureScheme::Bls) => {
+            return check_keystore(&open_keystore(keystore)?).await;
+        }
+        (SignerBackend::Keystore, SignatureScheme::Ecdsa) => {
+            Box::new(load_ecdsa_signer(&open_keystore(keystore)?).map_err(|e| e.to_string())?)
+        }
+        (SignerBackend::Env, SignatureScheme::Bls) => {
+            let secret = settings
+                .bls_secret
+                .as_ref()
+                .ok_or("OPERATOR_BLS_SECRET is not set")?;
+            return check_keystore(&BlsSecret(secret.expose().to_string())).await;
+        }
+        (SignerBackend::Env, SignatureScheme::Ecdsa) => {
+            let key = settings
+                .ecdsa_key
+                .as_ref()
+                .ok_or("OPERATOR_ECDSA_KEY is not set")?;
+            Box::new(ecdsa_signer_from_key(key.expose()).map_err(|e| e.to_string())?)
+        }
+        (SignerBackend::Remote, scheme) => Box::new(
+            connect_remote_signer(settings, scheme)
+                .await
+                .map_err(|e| e.to_string())?,
+        ),
+    };
+    check_signer(signer.as_ref()).await
+}
+
+/// Loads the BLS key like the job does and checks that it signs a dummy task response
+pub async fn check_keystore<K: BlsKeystore>(keystore: &K) -> Result<String, String> {
+    let key_pair = load_bls_key_pair(keystore).map_err(|e| e.to_string())?;
+    let operator_id = operator_id_from_key(key_pair.clone());
+    check_signer(&BlsTaskSigner::new(key_pair, operator_id)).await
+}
+
+/// Checks that `signer` signs a dummy task response that verifies against its key
+async fn check_signer(signer: &dyn TaskSigner) -> Result<String, String> {
+    let dummy = TaskResponse {
+        referenceTaskIndex: 0,
+        resultHash: B256::ZERO,
+    };
+    let domain = SigningDomain::default();
+    let payload = signer
+        .sign_task_response(dummy, &domain)
+        .await
+        .map_err(|e| e.to_string())?;
+    let scheme = signer.scheme().to_string().to_uppercase();
+    if !signer.verify(&payload, &domain) {
+        return Err(format!(
+            "Signature of the {} key does not verify against its public key",
+            scheme
+        ));
+    }
+    Ok(format!(
+        "{} key of operator {} signs",
+        scheme,
+        payload.operator()
+    ))
+}
+
+/// Fetches the latest blocks and schedules the last one, like a task would
+pub async fn check_api(api_client: &ApiClient) -> Result<String, String> {
+    let calculation = api_client
+        .get_calculation_detailed()
+        .await
+        .map_err(|e| format!("{}: {}", api_client.base_url(), e))?;
+    let (_, stats) = calculation
+        .blocks
+        .last()
+        .ok_or(ApiClientError::EmptyResponse)
+        .and_then(Block::number_u64)
+        .and_then(|newest| schedule_for_task(&calculation, newest))
+        .map_err(|e| format!("{}: {}", api_client.base_url(), e))?;
+    let numbers: Vec<_> = calculation.blocks.iter().map(block_number).collect();
+    Ok(format!(
+        "{} served blocks {} ({} transactions in the latest)",
+        api_client.base_url(),
+        numbers.join(", "),
+        stats.num_txs
+    ))
+}
+
+/// Checks that the aggregator address is valid and accepts connections
+pub async fn check_aggregator(address: &str) -> Result<String, String> {
+    let client = AggregatorClient::new(address).map_err(|e| e.to_string())?;
+    let url = client.url();
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or_default();
+    match timeout(AGGREGATOR_CONNECT_TIMEOUT, TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => Ok(format!("{} accepts connections", url)),
+        Ok(Err(e)) => Err(format!("{} is unreachable: {}", url, e)),
+        Err(_) => Err(format!(
+            "{} did not accept a connection within {:?}",
+            url, AGGREGATOR_CONNECT_TIMEOUT
+        )),
+    }
+}
+
+/// Prints one line per check and fails if any check did
+fn write_checklist(checks: &[Check], out: &mut impl Write) -> Result<(), CliError> {
+    for check in checks {
+        let (status, detail) = match &check.result {
+            Ok(detail) => ("PASS", detail),
+            Err(detail) => ("FAIL", detail),
+        };
+        writeln!(out, "[{}] {:<10} {}", status, check.name, detail)?;
+    }
+
+    let failed = checks.iter().filter(|check| !check.passed()).count();
+    if failed > 0 {
+        return Err(CliError::ChecksFailed {
+            failed,
+            total: checks.len(),
+        });
+    }
+    Ok(())
+}
+
+async fn fetch_hash(api_client: &ApiClient, out: &mut impl Write) -> Result<(), CliError> {
+    let calculation = api_client.get_calculation_detailed().await?;
+    let numbers: Vec<_> = calculation.blocks.iter().map(block_number).collect();
+
+    writeln!(out, "api:    {}", api_client.base_url())?;
+    writeln!(out, "hash:   {}", calculation.hash)?;
+    writeln!(out, "blocks: {}", numbers.join(", "))?;
+    Ok(())
+}
+
+/// Replays the recording of `task_index` with the hash settings of `api_client`, printing the
+/// recorded hashes next to the replayed ones
+fn replay(
+    recordings: &ApiRecordings,
+    task_index: u32,
+    api_client: &ApiClient,
+    out: &mut impl Write,
+) -> Result<(), CliError> {
+    let recording = recordings
+        .read(task_index)?
+        .ok_or(CliError::NotRecorded(task_index))?;
+    let replay = recording.replay(api_client)?;
+    let numbers: Vec<_> = recording.response.data.iter().map(block_number).collect();
+
+    writeln!(out, "task:     {}", task_index)?;
+    writeln!(out, "blocks:   {}", numbers.join(", "))?;
+    writeln!(
+        out,
+        "recorded: {} {}",
+        recording.calculation_hash, recording.result_hash
+    )?;
+    writeln!(
+        out,
+        "replayed: {} {}",
+        replay.calculation_hash, replay.result_hash
+    )?;
+    if !replay.matches(&recording) {
+        return Err(CliError::ReplayMismatch(task_index));
+    }
+    Ok(())
+}
+
+/// Formats the block number in decimal, falling back to what the API returned
+fn block_number(block: &Block) -> String {
+    block
+        .number_u64()
+        .map_or_else(|_| block.number.clone(), |number| number.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::hash_blocks;
+    use crate::config::Secret;
+    use crate::contexts::api_recordings::ApiRecording;
+    use crate::test_support::{MockServer, StubKeystore};
+    use tokio::net::TcpListener;
+
+    const BLOCKS: &str = r#"{
+        "status": "success",
+        "message": "ok",
+        "data": [
+            {
+                "hash": "0x0a885e8a3a5cb03e0540e7ef65734c46189f17a65596009570a227d3abb6d466",
+                "number": "0x10",
+                "timestamp": "0x67c2a1b0",
+                "transactions_root": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                "parent_hash": "0x2946018d361c6825c64c35c1421f3d80b16c3262dd01234a65dbf6bdb4422121"
+            }
+        ]
+    }"#;
+
+    /// Helper API answering every request with `status` and `body`
+    async fn mock_api(status: u16, body: &str) -> String {
+        MockServer::start(vec![(status, body.to_string())])
+            .await
+            .url()
+    }
+
+    #[tokio::test]
+    async fn test_fetch_hash_prints_hash_and_blocks() {
+        let url = mock_api(200, BLOCKS).await;
+        let cli = Cli::parse_from(["parallel-exec", "fetch-hash", "--url", &url]);
+
+        let mut out = Vec::new();
+        run(cli, &Config::default(), &mut out).await.unwrap();
+
+        let blocks: serde_json::Value = serde_json::from_str(BLOCKS).unwrap();
+        let blocks: Vec<Block> = serde_json::from_value(blocks["data"].clone()).unwrap();
+        let expected = hash_blocks(&blocks, Default::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("api:    {}\nhash:   {}\nblocks: 16\n", url, expected)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_keystore_check() {
+        let detail = check_keystore(&BlsSecret("12345".to_string()))
+            .await
+            .unwrap();
+        let key_pair = eigensdk::crypto_bls::BlsKeyPair::new("12345".to_string()).unwrap();
+        assert!(detail.contains(&operator_id_from_key(key_pair).to_string()));
+
+        let err = check_keystore(&StubKeystore::empty()).await.unwrap_err();
+        assert!(err.contains("keystore is empty"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_signer_check_follows_the_backend() {
+        let keystore = Path::new("/nonexistent/keystore");
+        let mut config = Config::default();
+        config.signer.backend = SignerBackend::Env;
+        let err = check_configured_signer(&config, keystore)
+            .await
+            .unwrap_err();
+        assert!(err.contains("OPERATOR_BLS_SECRET"), "{}", err);
+
+        // Keys held in the environment are checked without a keystore
+        config.signer.bls_secret = Some(Secret::new("12345"));
+        let detail = check_configured_signer(&config, keystore).await.unwrap();
+        let key_pair = eigensdk::crypto_bls::BlsKeyPair::new("12345".to_string()).unwrap();
+        assert!(detail.contains(&operator_id_from_key(key_pair).to_string()));
+
+        config.signature_scheme = SignatureScheme::Ecdsa;
+        let key = alloy_signer_local::PrivateKeySigner::random();
+        config.signer.ecdsa_key = Some(Secret::new(&alloy_primitives::hex::encode(key.to_bytes())));
+        let detail = check_configured_signer(&config, keystore).await.unwrap();
+        assert_eq!(
+            detail,
+            format!("ECDSA key of operator {} signs", key.address())
+        );
+
+        // A remote signer is reached like the operator would
+        config.signer.backend = SignerBackend::Remote;
+        config.signer.remote_url = Some("http://127.0.0.1:1".to_string());
+        assert!(check_configured_signer(&config, keystore).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_api_check() {
+        let url = mock_api(200, BLOCKS).await;
+        let detail = check_api(&ApiClient::new().with_base_url(url.clone()))
+            .await
+            .unwrap();
+        assert_eq!(
+            detail,
+            format!("{} served blocks 16 (0 transactions in the latest)", url)
+        );
+
+        let url = mock_api(404, "Not Found").await;
+        let err = check_api(&ApiClient::new().with_base_url(url.clone()))
+            .await
+            .unwrap_err();
+        assert!(err.starts_with(&url), "{}", err);
+
+        let url = mock_api(200, r#"{"status": "success", "message": "ok", "data": []}"#).await;
+        assert!(check_api(&ApiClient::new().with_base_url(url))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_aggregator_check() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        assert!(check_aggregator(&address).await.is_ok());
+
+        drop(listener);
+        assert!(check_aggregator(&address).await.is_err());
+        assert!(check_aggregator("no-port").await.is_err());
+    }
+
+    #[test]
+    fn test_checklist_fails_on_any_failed_check() {
+        let checks = vec![
+            Check {
+                name: "keystore",
+                result: Ok("BLS key signs".to_string()),
+            },
+            Check {
+                name: "aggregator",
+                result: Err("unreachable".to_string()),
+            },
+        ];
+
+        let mut out = Vec::new();
+        let err = write_checklist(&checks, &mut out).unwrap_err();
+        assert!(matches!(
+            err,
+            CliError::ChecksFailed {
+                failed: 1,
+                total: 2
+            }
+        ));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[PASS] keystore   BLS key signs\n[FAIL] aggregator unreachable\n"
+        );
+
+        let mut out = Vec::new();
+        write_checklist(&checks[..1], &mut out).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_hash_reports_api_errors() {
+        let url = mock_api(404, "Not Found").await;
+        let cli = Cli::parse_from(["parallel-exec", "fetch-hash", "--url", &url]);
+
+        let mut out = Vec::new();
+        let err = run(cli, &Config::default(), &mut out).await.unwrap_err();
+        assert!(matches!(
+            err,
+            CliError::Api(ApiClientError::Status { status: 404, .. })
+        ));
+        assert!(out.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_checks_the_recorded_hashes() {
+        let url = mock_api(200, BLOCKS).await;
+        let calculation = ApiClient::new()
+            .with_base_url(url)
+            .get_calculation_detailed()
+            .await
+            .unwrap();
+        let (schedule, _) = schedule_for_task(&calculation, 0x10).unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let recordings = ApiRecordings::open(dir.path()).unwrap();
+        let mut recording = ApiRecording::new(3, &calculation, schedule.hash());
+        recordings.write(&recording).unwrap();
+
+        let dir_arg = dir.path().to_str().unwrap();
+        let cli = Cli::parse_from(["parallel-exec", "replay", "--dir", dir_arg, "3"]);
+        let mut out = Vec::new();
+        run(cli, &Config::default(), &mut out).await.unwrap();
+        let hashes = format!("{} {}", calculation.hash, schedule.hash());
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "task:     3\nblocks:   16\nrecorded: {}\nreplayed: {}\n",
+                hashes, hashes
+            )
+        );
+
+        // A task answered with another hash than its blocks give is reported
+        recording.result_hash = B256::repeat_byte(1);
+        recordings.write(&recording).unwrap();
+        let cli = Cli::parse_from(["parallel-exec", "replay", "--dir", dir_arg, "3"]);
+        let err = run(cli, &Config::default(), &mut Vec::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CliError::ReplayMismatch(3)));
+
+        let cli = Cli::parse_from(["parallel-exec", "replay", "--dir", dir_arg, "4"]);
+        let err = run(cli, &Config::default(), &mut Vec::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CliError::NotRecorded(4)));
+    }
+}
@@ -1,6 +1,10 @@
 use alloy_primitives::{address, Address, U256};
+use alloy_signer_local::PrivateKeySigner;
 use lazy_static::lazy_static;
 use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+use thiserror::Error;
 
 // Environment variables with default values
 lazy_static! {
@@ -9,16 +13,170 @@ lazy_static! {
         .unwrap_or_else(|_| "http://localhost:8545".to_string());
     pub static ref EIGENLAYER_WS_ENDPOINT: String =
         env::var("EIGENLAYER_WS_ENDPOINT").unwrap_or_else(|_| "ws://localhost:8546".to_string());
-    pub static ref PRIVATE_KEY: String = env::var("PRIVATE_KEY").unwrap_or_else(|_| {
-        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80".to_string()
-    });
-    pub static ref AGGREGATOR_PRIVATE_KEY: String = env::var("PRIVATE_KEY").unwrap_or_else(|_| {
-        "2a871d0798f97d79848a013d4936a73bf4cc922c825d33c1cf7073dff6d409c6".to_string()
-    });
-    pub static ref TASK_MANAGER_ADDRESS: Address = env::var("TASK_MANAGER_ADDRESS")
-        .map(|addr| addr.parse().expect("Invalid TASK_MANAGER_ADDRESS"))
-        .unwrap_or_else(|_| address!("0000000000000000000000000000000000000000"));
 }
 
 pub const OPERATOR_ADDRESS: Address = address!("f39fd6e51aad88f6f4ce6ab8827279cfffb92266");
 pub const OPERATOR_METADATA_URL: &str = "https://github.com/tangle-network/gadget";
+pub const DEFAULT_API_BASE_URL: &str = "https://parallel-exec-helper.onrender.com";
+pub const RESPONSE_QUEUE_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+pub const RESPONSE_QUEUE_DRAIN_INTERVAL: Duration = Duration::from_secs(15);
+pub const PROCESSED_TASKS_WINDOW: usize = 1024;
+/// Finished tasks stay in the quorum tracker, so their status can be queried, for this long
+/// after their deadline
+pub const QUORUM_STATUS_RETENTION: Duration = Duration::from_secs(60 * 60);
+/// Tasks for the same block range created within this window share one helper API fetch
+pub const API_COALESCE_WINDOW: Duration = Duration::from_millis(100);
+/// Blocks kept in the live view of the chain, see `BLOCK_POLL_INTERVAL_MS`
+pub const LIVE_BLOCKS_CAPACITY: usize = 256;
+/// Task manager deployment targeted when `TASK_MANAGER_ADDRESS` is unset. Builds without a
+/// default deployment leave this at the zero address, so the address must be configured
+pub const DEFAULT_TASK_MANAGER_ADDRESS: Address = Address::ZERO;
+pub const DEFAULT_AGGREGATOR_HOST: &str = "127.0.0.1";
+pub const DEFAULT_AGGREGATOR_PORT: u16 = 8081;
+/// Aggregator key of the local devnet, used when `PRIVATE_KEY` is unset
+pub const DEFAULT_AGGREGATOR_PRIVATE_KEY: &str =
+    "2a871d0798f97d79848a013d4936a73bf4cc922c825d33c1cf7073dff6d409c6";
+
+/// Startup configuration the operator cannot run without
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConfigError {
+    #[error(
+        "PRIVATE_KEY does not hold a valid aggregator key ({0}). Set it to the aggregator's \
+         secp256k1 private key as 64 hex characters, optionally prefixed with 0x"
+    )]
+    InvalidAggregatorKey(String),
+    #[error(
+        "TASK_MANAGER_ADDRESS is not set and there is no default deployment. Set it to the address of the deployed \
+         IncredibleSquaringTaskManager, as 0x followed by 40 hex characters"
+    )]
+    MissingTaskManagerAddress,
+    #[error(
+        "TASK_MANAGER_ADDRESS {value:?} is not a valid address ({reason}). Set it to the address \
+         of the deployed IncredibleSquaringTaskManager, as 0x followed by 40 hex characters"
+    )]
+    InvalidTaskManagerAddress { value: String, reason: String },
+    #[error("Failed to read config file {}: {reason}", .path.display())]
+    ReadFile { path: PathBuf, reason: String },
+    #[error("Invalid config file {}: {reason}", .path.display())]
+    ParseFile { path: PathBuf, reason: String },
+    #[error("{name} {value:?} is invalid: {reason}")]
+    InvalidEnv {
+        name: &'static str,
+        value: String,
+        reason: String,
+    },
+    #[error("{setting}: {reason}")]
+    Invalid {
+        setting: &'static str,
+        reason: String,
+    },
+}
+
+/// Parses the aggregator's private key, without echoing the secret in the error
+pub fn parse_aggregator_private_key(key: &str) -> Result<PrivateKeySigner, ConfigError> {
+    key.trim()
+        .parse()
+        .map_err(|e: alloy_signer_local::LocalSignerError| {
+            ConfigError::InvalidAggregatorKey(e.to_string())
+        })
+}
+
+/// Parses the task manager address, falling back to `default` when it is unset. The zero
+/// address is never a deployment, neither configured nor as the default
+pub fn parse_task_manager_address(
+    address: Option<&str>,
+    default: Address,
+) -> Result<Address, ConfigError> {
+    let Some(address) = address.map(str::trim).filter(|address| !address.is_empty()) else {
+        if default.is_zero() {
+            return Err(ConfigError::MissingTaskManagerAddress);
+        }
+        return Ok(default);
+    };
+    let invalid = |reason: String| ConfigError::InvalidTaskManagerAddress {
+        value: address.to_string(),
+        reason,
+    };
+    let parsed: Address = address
+        .parse()
+        .map_err(|e: alloy_primitives::hex::FromHexError| invalid(e.to_string()))?;
+    if parsed.is_zero() {
+        return Err(invalid("the zero address".to_string()));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bad_aggregator_key_yields_descriptive_error() {
+        let key = "2a871d0798f97d79848a013d4936a73bf4cc922c825d33c1cf7073dff6d409c6";
+        let signer = parse_aggregator_private_key(key).unwrap();
+        assert_eq!(
+            parse_aggregator_private_key(&format!("0x{}\n", key))
+                .unwrap()
+                .address(),
+            signer.address()
+        );
+
+        for bad_key in [
+            "",
+            "not-a-key",
+            &key[..62],
+            "2a871d0798f97d79848a013d4936a73bf4cc922c825d33c1cf7073dff6d409zz",
+        ] {
+            let err = parse_aggregator_private_key(bad_key).unwrap_err();
+            assert!(matches!(err, ConfigError::InvalidAggregatorKey(_)));
+            let message = err.to_string();
+            assert!(message.contains("PRIVATE_KEY"), "{}", message);
+            assert!(message.contains("64 hex characters"), "{}", message);
+            // The key is a secret, even a malformed one must not end up in logs
+            if !bad_key.is_empty() {
+                assert!(!message.contains(bad_key), "{}", message);
+            }
+        }
+    }
+
+    #[test]
+    fn test_task_manager_address_override_and_default() {
+        let deployment = Address::repeat_byte(0x42);
+        let other = "0x4343434343434343434343434343434343434343";
+
+        // The configured address overrides the default deployment
+        assert_eq!(
+            parse_task_manager_address(Some(&format!(" {} ", other)), deployment),
+            Ok(Address::repeat_byte(0x43))
+        );
+        assert_eq!(
+            parse_task_manager_address(Some(other), Address::ZERO),
+            Ok(Address::repeat_byte(0x43))
+        );
+        for unset in [None, Some(""), Some("  ")] {
+            assert_eq!(
+                parse_task_manager_address(unset, deployment),
+                Ok(deployment)
+            );
+            assert_eq!(
+                parse_task_manager_address(unset, Address::ZERO),
+                Err(ConfigError::MissingTaskManagerAddress)
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_task_manager_address_is_rejected() {
+        let deployment = Address::repeat_byte(0x42);
+        for invalid in ["0x1234", "not-an-address", &Address::ZERO.to_string()] {
+            let err = parse_task_manager_address(Some(invalid), deployment).unwrap_err();
+            assert!(matches!(
+                &err,
+                ConfigError::InvalidTaskManagerAddress { value, .. } if value == invalid
+            ));
+            assert!(err
+                .to_string()
+                .contains(&format!("TASK_MANAGER_ADDRESS {:?}", invalid)));
+        }
+    }
+}
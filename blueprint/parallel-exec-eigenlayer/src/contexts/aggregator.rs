@@ -1,3 +1,12 @@
+use crate::constants::QUORUM_STATUS_RETENTION;
+use crate::contexts::health::{self, HealthStatus};
+use crate::contexts::quorum_status::{QuorumState, QuorumStatus, QuorumTracker};
+use crate::contexts::response_codec::{JsonCodec, ResponseCodec};
+use crate::contexts::submission::{Fees, GasConfig, NonceTracker};
+use crate::contexts::task_config::{TaskConfig, TaskParams};
+#[cfg(feature = "metrics")]
+use crate::metrics::Metrics;
+use crate::signer::{SigningDomain, TaskResponseEncoding};
 use crate::IBLSSignatureChecker::NonSignerStakesAndSignature;
 use crate::IIncredibleSquaringTaskManager::Task;
 use crate::IIncredibleSquaringTaskManager::TaskResponse;
@@ -5,25 +14,34 @@ use crate::BN254::G1Point;
 use crate::BN254::G2Point;
 use crate::{contexts::client::SignedTaskResponse, Error, IncredibleSquaringTaskManager};
 use alloy_network::{Ethereum, NetworkWallet};
-use alloy_primitives::{keccak256, Address};
-use alloy_sol_types::SolType;
+use alloy_primitives::{Address, B256, U128, U64};
+use alloy_provider::Provider;
+use alloy_rpc_types::TransactionReceipt;
 use jsonrpc_core::{IoHandler, Params, Value};
 use jsonrpc_http_server::{AccessControlAllowOrigin, DomainsValidation, ServerBuilder};
-use std::{collections::VecDeque, net::SocketAddr, sync::Arc, time::Duration};
-use tokio::sync::{oneshot, Mutex, Notify};
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{broadcast, oneshot, Mutex, Notify};
 use tokio::task::JoinHandle;
 use tokio::time::interval;
 
 use alloy_network::EthereumWallet;
+use ark_bn254::{G1Affine, G2Affine};
 use blueprint_sdk::config::GadgetConfiguration;
 use blueprint_sdk::contexts::eigenlayer::EigenlayerContext;
-use blueprint_sdk::logging::{debug, error, info};
+use blueprint_sdk::logging::{debug, error, info, warn};
 use blueprint_sdk::macros::contexts::{EigenlayerContext, KeystoreContext};
 use blueprint_sdk::runners::core::error::RunnerError;
 use blueprint_sdk::runners::core::runner::BackgroundService;
 use eigensdk::client_avsregistry::reader::AvsRegistryChainReader;
 use eigensdk::common::get_provider;
-use eigensdk::crypto_bls::{convert_to_g1_point, convert_to_g2_point, BlsG1Point, BlsG2Point};
+use eigensdk::crypto_bls::{
+    convert_to_g1_point, convert_to_g2_point, verify_message, BlsG1Point, BlsG2Point,
+};
 use eigensdk::services_avsregistry::chaincaller::AvsRegistryServiceChainCaller;
 use eigensdk::services_blsaggregation::{
     bls_agg::BlsAggregatorService, bls_aggregation_service_response::BlsAggregationServiceResponse,
@@ -32,23 +50,63 @@ use eigensdk::services_operatorsinfo::operatorsinfo_inmemory::OperatorInfoServic
 use eigensdk::types::avs::{TaskIndex, TaskResponseDigest};
 use std::collections::HashMap;
 
+/// Default upper bound for [`AggregatorContext::shutdown`] to wait for in-flight work
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Upper bound for each RPC call made by a health check
+const HEALTH_RPC_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Confirmations buffered for each subscriber, a subscriber lagging further behind misses the
+/// oldest ones
+const CONFIRMATION_CHANNEL_CAPACITY: usize = 64;
+
+/// Interval at which a pending submission is checked for a receipt
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 pub type BlsAggServiceInMemory = BlsAggregatorService<
     AvsRegistryServiceChainCaller<AvsRegistryChainReader, OperatorInfoServiceInMemory>,
 >;
 
+/// A task response the task manager accepted, as seen in its `TaskResponded` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskConfirmation {
+    pub task_index: TaskIndex,
+    pub result_hash: B256,
+    /// Transaction that submitted the aggregated response
+    pub tx_hash: B256,
+    pub block_number: Option<u64>,
+}
+
 #[derive(Clone, EigenlayerContext, KeystoreContext)]
 pub struct AggregatorContext {
     pub port_address: String,
     pub task_manager_address: Address,
+    /// Chain the task manager is deployed on, which operators bind their signatures to
+    pub chain_id: u64,
     pub tasks: Arc<Mutex<HashMap<TaskIndex, Task>>>,
     pub tasks_responses: Arc<Mutex<HashMap<TaskIndex, HashMap<TaskResponseDigest, TaskResponse>>>>,
     pub bls_aggregation_service: Option<Arc<Mutex<BlsAggServiceInMemory>>>,
     pub http_rpc_url: String,
     pub wallet: EthereumWallet,
     pub response_cache: Arc<Mutex<VecDeque<SignedTaskResponse>>>,
+    /// Signatures and stake collected for every initialized task
+    pub quorum_tracker: QuorumTracker,
     #[config]
     pub sdk_config: GadgetConfiguration,
     shutdown: Arc<(Notify, Mutex<bool>)>,
+    /// Set once the server and the response processing have both stopped
+    stopped: Arc<(Notify, Mutex<bool>)>,
+    shutdown_timeout: Duration,
+    health_address: Option<SocketAddr>,
+    task_config: TaskConfig,
+    task_response_encoding: TaskResponseEncoding,
+    confirmations: broadcast::Sender<TaskConfirmation>,
+    response_codec: Arc<dyn ResponseCodec>,
+    gas_config: GasConfig,
+    /// Nonces of the aggregator's submissions, shared between concurrent ones
+    nonces: Arc<parking_lot::Mutex<NonceTracker>>,
+    #[cfg(feature = "metrics")]
+    metrics: Metrics,
 }
 
 impl AggregatorContext {
@@ -58,33 +116,249 @@ impl AggregatorContext {
         wallet: EthereumWallet,
         sdk_config: GadgetConfiguration,
     ) -> Result<Self, Error> {
-        let mut aggregator_context = AggregatorContext {
+        let mut aggregator_context =
+            Self::without_bls_service(port_address, task_manager_address, wallet, sdk_config);
+        aggregator_context.chain_id = get_provider(&aggregator_context.http_rpc_url)
+            .get_chain_id()
+            .await
+            .map_err(|e| Error::Chain(e.to_string()))?;
+
+        // Initialize the bls registry service
+        let bls_service = aggregator_context
+            .eigenlayer_client()
+            .await
+            .map_err(|e| Error::Context(e.to_string()))?
+            .bls_aggregation_service_in_memory()
+            .await
+            .map_err(|e| Error::Context(e.to_string()))?;
+        aggregator_context.bls_aggregation_service = Some(Arc::new(Mutex::new(bls_service)));
+
+        Ok(aggregator_context)
+    }
+
+    fn without_bls_service(
+        port_address: String,
+        task_manager_address: Address,
+        wallet: EthereumWallet,
+        sdk_config: GadgetConfiguration,
+    ) -> Self {
+        AggregatorContext {
             port_address,
             task_manager_address,
+            chain_id: 0,
             tasks: Arc::new(Mutex::new(HashMap::new())),
             tasks_responses: Arc::new(Mutex::new(HashMap::new())),
             bls_aggregation_service: None,
             http_rpc_url: sdk_config.http_rpc_endpoint.clone(),
             wallet,
             response_cache: Arc::new(Mutex::new(VecDeque::new())),
+            quorum_tracker: QuorumTracker::new(),
             sdk_config,
             shutdown: Arc::new((Notify::new(), Mutex::new(false))),
-        };
+            stopped: Arc::new((Notify::new(), Mutex::new(true))),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            health_address: None,
+            task_config: TaskConfig::default(),
+            task_response_encoding: TaskResponseEncoding::default(),
+            confirmations: broadcast::channel(CONFIRMATION_CHANNEL_CAPACITY).0,
+            response_codec: Arc::new(JsonCodec),
+            gas_config: GasConfig::default(),
+            nonces: Arc::new(parking_lot::Mutex::new(NonceTracker::new())),
+            #[cfg(feature = "metrics")]
+            metrics: Metrics::new(),
+        }
+    }
 
-        // Initialize the bls registry service
-        let bls_service = aggregator_context
-            .eigenlayer_client()
+    /// Returns the domain operators sign task responses in for this task manager
+    pub fn signing_domain(&self) -> SigningDomain {
+        SigningDomain::new(self.chain_id, self.task_manager_address)
+            .with_encoding(self.task_response_encoding)
+    }
+
+    /// Hashes task responses with `encoding`, which must match what operators sign
+    pub fn with_task_response_encoding(mut self, encoding: TaskResponseEncoding) -> Self {
+        self.task_response_encoding = encoding;
+        self
+    }
+
+    /// Decodes signed task responses with `codec`, which operators have to encode them with
+    pub fn with_response_codec(mut self, codec: Arc<dyn ResponseCodec>) -> Self {
+        self.response_codec = codec;
+        self
+    }
+
+    /// Prices and replaces the aggregator's submissions with `gas_config`, which should have
+    /// been validated at startup
+    pub fn with_gas_config(mut self, gas_config: GasConfig) -> Self {
+        self.gas_config = gas_config;
+        self
+    }
+
+    /// Computes the fees of a new submission from the chain's current gas price and base fee
+    async fn current_fees(&self) -> Result<Fees, Error> {
+        let provider = get_provider(&self.http_rpc_url);
+        let gas_price = provider
+            .get_gas_price()
             .await
-            .map_err(|e| Error::Context(e.to_string()))?
-            .bls_aggregation_service_in_memory()
+            .map_err(|e| Error::Chain(e.to_string()))?;
+        let block: Option<serde_json::Value> = provider
+            .raw_request("eth_getBlockByNumber".into(), ("latest", false))
             .await
-            .map_err(|e| Error::Context(e.to_string()))?;
-        aggregator_context.bls_aggregation_service = Some(Arc::new(Mutex::new(bls_service)));
+            .map_err(|e| Error::Chain(e.to_string()))?;
+        // Chains without EIP-1559 have no base fee
+        let base_fee = block
+            .and_then(|block| serde_json::from_value::<U128>(block["baseFeePerGas"].clone()).ok())
+            .map(|base_fee| base_fee.to::<u128>());
+        self.gas_config
+            .fees(base_fee, gas_price)
+            .map_err(|e| Error::Chain(e.to_string()))
+    }
 
-        Ok(aggregator_context)
+    /// Serves `GET /healthz` on `address` while the aggregator runs, off by default
+    pub fn with_health_address(mut self, address: SocketAddr) -> Self {
+        self.health_address = Some(address);
+        self
+    }
+
+    /// Initializes new tasks with `task_config`, which should have been validated at startup
+    pub fn with_task_config(mut self, task_config: TaskConfig) -> Self {
+        self.task_config = task_config;
+        self
+    }
+
+    pub fn task_config(&self) -> &TaskConfig {
+        &self.task_config
+    }
+
+    /// Returns the parameters `task` is initialized with in the BLS aggregation service
+    pub fn task_params(&self, task: &Task) -> TaskParams {
+        self.task_config.params_for(task)
+    }
+
+    /// Returns how many signatures and how much stake the task has collected against its
+    /// thresholds, or `None` if the task was not initialized
+    pub fn quorum_status(&self, task_index: TaskIndex) -> Option<QuorumStatus> {
+        self.quorum_tracker.status(task_index)
+    }
+
+    /// Fails the tasks whose window closed by `now` before reaching their quorum and logs each
+    /// one, returning their statuses. Tasks finished longer than the retention ago are dropped
+    pub fn check_quorum_deadlines(&self, now: Instant) -> Vec<QuorumStatus> {
+        let failed = self.quorum_tracker.expire(now);
+        for status in &failed {
+            warn!("Quorum not met before the task expired, {}", status);
+        }
+        self.quorum_tracker.prune(now, QUORUM_STATUS_RETENTION);
+        failed
+    }
+
+    /// Returns a receiver of every task response the task manager accepts from now on, once
+    /// the transaction submitting it was included
+    pub fn subscribe_confirmations(&self) -> broadcast::Receiver<TaskConfirmation> {
+        self.confirmations.subscribe()
+    }
+
+    /// Fetches the receipt of a submission and notifies subscribers of the task responses it
+    /// confirmed, e.g. to confirm a submission made before a restart
+    pub async fn confirm_submission(&self, tx_hash: B256) -> Result<Vec<TaskConfirmation>, Error> {
+        let receipt = get_provider(&self.http_rpc_url)
+            .get_transaction_receipt(tx_hash)
+            .await
+            .map_err(|e| Error::Chain(e.to_string()))?
+            .ok_or_else(|| Error::Chain(format!("No receipt for transaction {}", tx_hash)))?;
+        Ok(self.notify_confirmations(&receipt))
+    }
+
+    /// Sends a confirmation for every `TaskResponded` event the task manager emitted in the
+    /// transaction of `receipt`
+    fn notify_confirmations(&self, receipt: &TransactionReceipt) -> Vec<TaskConfirmation> {
+        let confirmations: Vec<TaskConfirmation> = receipt
+            .inner
+            .logs()
+            .iter()
+            .filter(|log| log.address() == self.task_manager_address)
+            .filter_map(|log| {
+                log.log_decode::<IncredibleSquaringTaskManager::TaskResponded>()
+                    .ok()
+            })
+            .map(|log| TaskConfirmation {
+                task_index: log.inner.data.taskResponse.referenceTaskIndex,
+                result_hash: log.inner.data.taskResponse.resultHash,
+                tx_hash: receipt.transaction_hash,
+                block_number: receipt.block_number,
+            })
+            .collect();
+
+        if confirmations.is_empty() {
+            warn!(
+                "Transaction {} confirmed no task response (status: {})",
+                receipt.transaction_hash,
+                receipt.status()
+            );
+        }
+        for confirmation in &confirmations {
+            info!(
+                "Task {} response confirmed in transaction {} at block {:?}",
+                confirmation.task_index, confirmation.tx_hash, confirmation.block_number
+            );
+            // Nobody listening is fine
+            let _ = self.confirmations.send(*confirmation);
+        }
+        confirmations
+    }
+
+    /// Records the aggregator's counters in `metrics`, e.g. to share them with the operator
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Checks whether the aggregator is running, its RPC endpoint answers and the task
+    /// manager is deployed at the configured address
+    pub async fn health(&self) -> HealthStatus {
+        let up = !*self.stopped.1.lock().await && !self.is_shutting_down().await;
+
+        let provider = get_provider(&self.http_rpc_url);
+        let rpc_connected =
+            tokio::time::timeout(HEALTH_RPC_TIMEOUT, async { provider.get_chain_id().await })
+                .await
+                .is_ok_and(|chain_id| chain_id.is_ok());
+
+        let task_manager_deployed = rpc_connected
+            && self.task_manager_address != Address::ZERO
+            && tokio::time::timeout(HEALTH_RPC_TIMEOUT, async {
+                provider.get_code_at(self.task_manager_address).await
+            })
+            .await
+            .is_ok_and(|code| code.is_ok_and(|code| !code.is_empty()));
+
+        HealthStatus::new(up, rpc_connected, task_manager_deployed)
+    }
+
+    /// Sets how long [`AggregatorContext::shutdown`] waits for in-flight work to finish
+    pub fn with_shutdown_timeout(mut self, shutdown_timeout: Duration) -> Self {
+        self.shutdown_timeout = shutdown_timeout;
+        self
     }
 
     pub async fn start(self) -> JoinHandle<()> {
+        let stopped = self.stopped.clone();
+        *stopped.1.lock().await = false;
+
+        if let Some(address) = self.health_address {
+            let context = self.clone();
+            let shutdown = self.shutdown.clone();
+            let check = move || {
+                let context = context.clone();
+                async move { context.health().await }
+            };
+            let until_shutdown = async move { wait_for_shutdown(&shutdown).await };
+            if let Err(e) = health::bind(address, check, until_shutdown).await {
+                error!("Failed to start health endpoint on {}: {}", address, e);
+            }
+        }
+
         let aggregator = Arc::new(Mutex::new(self));
 
         tokio::spawn(async move {
@@ -95,8 +369,12 @@ impl AggregatorContext {
             let process_handle =
                 tokio::spawn(Self::process_cached_responses(Arc::clone(&aggregator)));
 
-            // Wait for both tasks to complete
-            let (server_result, process_result) = tokio::join!(server_handle, process_handle);
+            let aggregate_handle =
+                tokio::spawn(Self::process_aggregated_responses(Arc::clone(&aggregator)));
+
+            // Wait for every task to complete
+            let (server_result, process_result, aggregate_result) =
+                tokio::join!(server_handle, process_handle, aggregate_handle);
 
             if let Err(e) = server_result {
                 error!("Server task failed: {}", e);
@@ -104,26 +382,67 @@ impl AggregatorContext {
             if let Err(e) = process_result {
                 error!("Process cached responses task failed: {}", e);
             }
+            if let Err(e) = aggregate_result {
+                error!("Process aggregated responses task failed: {}", e);
+            }
 
             info!("Aggregator shutdown complete");
+            let (notify, is_stopped) = &*stopped;
+            *is_stopped.lock().await = true;
+            notify.notify_waiters();
         })
     }
 
-    pub async fn shutdown(&self) {
+    /// Stops the aggregator.
+    ///
+    /// New signed task responses are rejected right away, the RPC server is closed and the
+    /// response currently being aggregated is allowed to finish. Returns an error if that
+    /// takes longer than the shutdown timeout.
+    pub async fn shutdown(&self) -> Result<(), Error> {
         info!("Initiating aggregator shutdown");
 
         // Set internal shutdown flag
         let (notify, is_shutdown) = &*self.shutdown;
         *is_shutdown.lock().await = true;
         notify.notify_waiters();
+
+        let (stopped_notify, is_stopped) = &*self.stopped;
+        let wait_until_stopped = async {
+            loop {
+                // Register for the notification before checking, so it cannot be missed
+                let notified = stopped_notify.notified();
+                if *is_stopped.lock().await {
+                    break;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(self.shutdown_timeout, wait_until_stopped)
+            .await
+            .map_err(|_| {
+                Error::Runtime(format!(
+                    "Aggregator did not stop within {:?}",
+                    self.shutdown_timeout
+                ))
+            })?;
+
+        info!("Aggregator stopped");
+        Ok(())
+    }
+
+    /// Returns true once [`AggregatorContext::shutdown`] has been called
+    pub async fn is_shutting_down(&self) -> bool {
+        *self.shutdown.1.lock().await
     }
 
     async fn start_server(aggregator: Arc<Mutex<Self>>) -> Result<(), Error> {
         let mut io = IoHandler::new();
         io.add_method("process_signed_task_response", {
             let aggregator = Arc::clone(&aggregator);
+            let codec = Arc::clone(&aggregator.lock().await.response_codec);
             move |params: Params| {
                 let aggregator = Arc::clone(&aggregator);
+                let codec = Arc::clone(&codec);
                 async move {
                     // Parse the outer structure first
                     let outer_params: Value = params.parse()?;
@@ -133,14 +452,13 @@ impl AggregatorContext {
                         jsonrpc_core::Error::invalid_params("Missing 'params' field")
                     })?;
 
-                    // Now parse the inner params as SignedTaskResponse
-                    let signed_task_response: SignedTaskResponse =
-                        serde_json::from_value(inner_params.clone()).map_err(|e| {
-                            jsonrpc_core::Error::invalid_params(format!(
-                                "Invalid SignedTaskResponse: {}",
-                                e
-                            ))
-                        })?;
+                    // Now decode the inner params as SignedTaskResponse
+                    let signed_task_response = codec.decode(inner_params).map_err(|e| {
+                        jsonrpc_core::Error::invalid_params(format!(
+                            "Invalid SignedTaskResponse: {}",
+                            e
+                        ))
+                    })?;
 
                     aggregator
                         .lock()
@@ -153,6 +471,24 @@ impl AggregatorContext {
             }
         });
 
+        io.add_method("quorum_status", {
+            let quorum_tracker = aggregator.lock().await.quorum_tracker.clone();
+            move |params: Params| {
+                let quorum_tracker = quorum_tracker.clone();
+                async move {
+                    let (task_index,): (TaskIndex,) = params.parse()?;
+                    let status = quorum_tracker.status(task_index).ok_or_else(|| {
+                        jsonrpc_core::Error::invalid_params(format!(
+                            "Task {} is not initialized",
+                            task_index
+                        ))
+                    })?;
+                    serde_json::to_value(status)
+                        .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))
+                }
+            }
+        });
+
         let socket: SocketAddr = aggregator
             .lock()
             .await
@@ -224,15 +560,21 @@ impl AggregatorContext {
         &mut self,
         resp: SignedTaskResponse,
     ) -> Result<(), Error> {
+        if self.is_shutting_down().await {
+            return Err(Error::Runtime("Aggregator is shutting down".to_string()));
+        }
+
         let task_index = resp.task_response.referenceTaskIndex;
-        let task_response_digest = keccak256(TaskResponse::abi_encode(&resp.task_response));
+        let task_response_digest = self.signing_domain().signing_hash(&resp.task_response);
 
         info!(
-            "Caching signed task response for task index: {}, task response digest: {}",
-            task_index, task_response_digest
+            "Caching signed task response for task index: {}, quorum: {:?}, task response digest: {}",
+            task_index, resp.quorum_number, task_response_digest
         );
 
         self.response_cache.lock().await.push_back(resp);
+        #[cfg(feature = "metrics")]
+        self.metrics.signature_received();
 
         Ok(())
     }
@@ -241,13 +583,28 @@ impl AggregatorContext {
         let mut interval = interval(Duration::from_secs(6));
 
         // Get shutdown components
-        let shutdown = {
+        let (shutdown, quorum_tracker) = {
             let agg = aggregator.lock().await;
-            agg.shutdown.clone()
+            (agg.shutdown.clone(), agg.quorum_tracker.clone())
         };
 
         loop {
+            // Finalize every task at its deadline, without waiting for the next tick
+            let next_deadline = quorum_tracker.next_deadline();
+            let until_next_deadline = async move {
+                match next_deadline {
+                    Some(deadline) => {
+                        tokio::time::sleep_until(tokio::time::Instant::from_std(deadline)).await
+                    }
+                    None => std::future::pending().await,
+                }
+            };
+
             tokio::select! {
+                _ = until_next_deadline => {
+                    aggregator.lock().await.check_quorum_deadlines(Instant::now());
+                }
+                _ = quorum_tracker.deadline_changed() => {}
                 _ = interval.tick() => {
                     // Check shutdown status first
                     if *shutdown.1.lock().await {
@@ -255,6 +612,8 @@ impl AggregatorContext {
                         break;
                     }
 
+                    aggregator.lock().await.check_quorum_deadlines(Instant::now());
+
                     // Get responses to process while holding the lock briefly
                     let responses_to_process = {
                         let guard = aggregator.lock().await;
@@ -297,9 +656,10 @@ impl AggregatorContext {
             task_response,
             signature,
             operator_id,
+            ..
         } = resp.clone();
         let task_index = task_response.referenceTaskIndex;
-        let task_response_digest = keccak256(TaskResponse::abi_encode(&task_response));
+        let task_response_digest = self.signing_domain().signing_hash(&task_response);
 
         // Check if we have the task initialized first
         if !self.tasks.lock().await.contains_key(&task_index) {
@@ -311,17 +671,26 @@ impl AggregatorContext {
             return Ok(());
         }
 
+        // A task is finalized at its deadline, signatures arriving later are dropped so the
+        // aggregation service never completes it late
         if self
-            .tasks_responses
-            .lock()
-            .await
-            .entry(task_index)
-            .or_default()
-            .contains_key(&task_response_digest)
+            .quorum_tracker
+            .status(task_index)
+            .is_some_and(|status| status.state == QuorumState::Failed)
         {
+            warn!(
+                "Dropping signature of operator {} for task index {}: its quorum window closed",
+                operator_id, task_index
+            );
+            return Ok(());
+        }
+
+        // Operators send the same signature once for every quorum they belong to, but the
+        // aggregation service counts each operator's stake in all of its quorums at once
+        if self.quorum_tracker.has_signed(task_index, operator_id) {
             info!(
-                "Task response digest already processed for task index: {}",
-                task_index
+                "Signature of operator {} already processed for task index: {}",
+                operator_id, task_index
             );
             return Ok(());
         }
@@ -331,6 +700,14 @@ impl AggregatorContext {
             task_index, task_response_digest
         );
 
+        // The task response is known before the service can aggregate a signature over it
+        self.tasks_responses
+            .lock()
+            .await
+            .entry(task_index)
+            .or_default()
+            .entry(task_response_digest)
+            .or_insert_with(|| task_response.clone());
         self.bls_aggregation_service
             .as_ref()
             .ok_or_else(|| {
@@ -346,43 +723,114 @@ impl AggregatorContext {
             .await
             .map_err(|e| Error::Context(e.to_string()))?;
 
-        if let Some(tasks_responses) = self.tasks_responses.lock().await.get_mut(&task_index) {
-            tasks_responses.insert(task_response_digest, task_response.clone());
+        if let Some(status) =
+            self.quorum_tracker
+                .record_signature(task_index, operator_id, Instant::now())
+        {
+            debug!("Collected signature for {}", status);
         }
 
         debug!(
             "Successfully processed new signature for task index: {}",
             task_index
         );
+        Ok(())
+    }
 
-        if let Some(aggregated_response) = self
-            .bls_aggregation_service
-            .as_ref()
-            .ok_or_else(|| {
-                std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "BLS Aggregation Service not initialized",
-                )
-            })
-            .map_err(|e| Error::Context(e.to_string()))?
-            .lock()
-            .await
-            .aggregated_response_receiver
-            .lock()
-            .await
-            .recv()
-            .await
-        {
-            let response = aggregated_response.map_err(|e| Error::Context(e.to_string()))?;
-            self.send_aggregated_response_to_contract(response).await?;
+    /// Submits every aggregate the BLS aggregation service produces, until shutdown.
+    ///
+    /// Aggregates are received here rather than by the signature that completed them, so
+    /// neither waiting for them nor their submission holds the aggregator's lock.
+    async fn process_aggregated_responses(aggregator: Arc<Mutex<Self>>) {
+        let (service, shutdown) = {
+            let agg = aggregator.lock().await;
+            (agg.bls_aggregation_service.clone(), agg.shutdown.clone())
+        };
+        let Some(service) = service else {
+            return;
+        };
+        let receiver = Arc::clone(&service.lock().await.aggregated_response_receiver);
+
+        loop {
+            let aggregated_response = tokio::select! {
+                aggregated_response = async { receiver.lock().await.recv().await } => {
+                    aggregated_response
+                }
+                _ = wait_for_shutdown(&shutdown) => {
+                    info!("Process aggregated responses received shutdown signal");
+                    break;
+                }
+            };
+            let Some(aggregated_response) = aggregated_response else {
+                break;
+            };
+            let response = match aggregated_response {
+                Ok(response) => response,
+                Err(e) => {
+                    warn!("BLS aggregation did not produce an aggregate: {:?}", e);
+                    continue;
+                }
+            };
+            let context = aggregator.lock().await.clone();
+            if let Err(e) = context.submit_aggregated_response(response).await {
+                error!("Failed to submit aggregated response: {}", e);
+            }
         }
-        Ok(())
+    }
+
+    /// Submits the aggregate of a task that reached its quorum before its deadline, and drops
+    /// it if the task was already finalized as failed
+    async fn submit_aggregated_response(
+        &self,
+        response: BlsAggregationServiceResponse,
+    ) -> Result<(), Error> {
+        if !self.quorum_tracker.mark_reached(response.task_index) {
+            warn!(
+                "Dropping aggregated response for task index {}: it was finalized before its quorum was reached",
+                response.task_index
+            );
+            return Ok(());
+        }
+        let result = self.send_aggregated_response_to_contract(response).await;
+        #[cfg(feature = "metrics")]
+        match &result {
+            Ok(()) => self.metrics.aggregation_submitted(),
+            Err(_) => self.metrics.aggregation_failed(),
+        }
+        result
     }
 
     async fn send_aggregated_response_to_contract(
         &self,
         response: BlsAggregationServiceResponse,
     ) -> Result<(), Error> {
+        // Never pay gas for an aggregate the task manager would reject
+        {
+            let task_responses = self.tasks_responses.lock().await;
+            let task_response = task_responses
+                .get(&response.task_index)
+                .and_then(|responses| responses.get(&response.task_response_digest))
+                .ok_or_else(|| {
+                    Error::Context(format!(
+                        "Task response not found for task index {}",
+                        response.task_index
+                    ))
+                })?;
+            if let Err(e) = verify_aggregated_signature(
+                task_response,
+                &self.signing_domain(),
+                response.task_response_digest,
+                response.signers_apk_g2.g2(),
+                response.signers_agg_sig_g1.g1_point().g1(),
+            ) {
+                error!(
+                    "Rejecting aggregated response for task index {}: {}",
+                    response.task_index, e
+                );
+                return Err(e);
+            }
+        }
+
         let non_signer_stakes_and_signature = NonSignerStakesAndSignature {
             nonSignerPubkeys: response
                 .non_signers_pub_keys_g1
@@ -412,43 +860,209 @@ impl AggregatorContext {
             G2Point { X: pt.X, Y: pt.Y }
         }
 
-        let tasks = self.tasks.lock().await;
-        let task_responses = self.tasks_responses.lock().await;
-        let task = tasks.get(&response.task_index).expect("Task not found");
-        let task_response = task_responses
+        // Not held while the submission is pending, which can take several blocks
+        let task = self
+            .tasks
+            .lock()
+            .await
+            .get(&response.task_index)
+            .cloned()
+            .expect("Task not found");
+        let task_response = self
+            .tasks_responses
+            .lock()
+            .await
             .get(&response.task_index)
             .and_then(|responses| responses.get(&response.task_response_digest))
+            .cloned()
             .expect("Task response not found");
 
         let provider = get_provider(&self.http_rpc_url);
         let task_manager =
             IncredibleSquaringTaskManager::new(self.task_manager_address, provider.clone());
+        let from = NetworkWallet::<Ethereum>::default_signer_address(&self.wallet);
+        let call = task_manager
+            .respondToTask(task, task_response, non_signer_stakes_and_signature)
+            .from(from);
 
-        let _ = task_manager
-            .respondToTask(
-                task.clone(),
-                task_response.clone(),
-                non_signer_stakes_and_signature,
-            )
-            .from(NetworkWallet::<Ethereum>::default_signer_address(
-                &self.wallet,
-            ))
-            .send()
-            .await
-            .map_err(|e| Error::Chain(e.to_string()))?
-            .get_receipt()
+        let mut fees = self.current_fees().await?;
+        let chain_nonce: U64 = provider
+            .raw_request("eth_getTransactionCount".into(), (from, "pending"))
             .await
             .map_err(|e| Error::Chain(e.to_string()))?;
+        let nonce = self.nonces.lock().reserve(chain_nonce.to());
+
+        // Send with the reserved nonce, replacing the submission with higher fees whenever
+        // it is still pending after the replacement timeout
+        let send = |fees: Fees| {
+            let attempt = match fees {
+                Fees::Legacy { gas_price } => call.clone().nonce(nonce).gas_price(gas_price),
+                Fees::Eip1559 {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                } => call
+                    .clone()
+                    .nonce(nonce)
+                    .max_fee_per_gas(max_fee_per_gas)
+                    .max_priority_fee_per_gas(max_priority_fee_per_gas),
+            };
+            async move {
+                let pending = attempt
+                    .send()
+                    .await
+                    .map_err(|e| Error::Chain(e.to_string()))?;
+                Ok::<_, Error>(*pending.tx_hash())
+            }
+        };
+        let receipt = async {
+            let mut sent = vec![send(fees).await?];
+            let mut replacements = 0;
+            loop {
+                let replace_after = self
+                    .gas_config
+                    .replacement_timeout()
+                    .filter(|_| replacements < self.gas_config.max_replacements());
+                let wait = replace_after.unwrap_or(self.gas_config.receipt_timeout());
+                if let Some(receipt) =
+                    wait_for_receipt(&provider, &sent, Instant::now() + wait).await?
+                {
+                    break Ok::<_, Error>(receipt);
+                }
+                if replace_after.is_none() {
+                    break Err(Error::Chain(format!(
+                        "Submission for task {} with nonce {} was not included within {:?}",
+                        response.task_index, nonce, wait
+                    )));
+                }
+
+                let Some(replacement) = self.gas_config.replacement_fees(fees) else {
+                    warn!(
+                        "Submission for task {} is still pending, but the fee cap leaves no \
+                         room to replace it",
+                        response.task_index
+                    );
+                    replacements = self.gas_config.max_replacements();
+                    continue;
+                };
+                warn!(
+                    "Submission for task {} is still pending with nonce {}, replacing it with \
+                     a max fee of {} wei per gas",
+                    response.task_index,
+                    nonce,
+                    replacement.max_fee_per_gas()
+                );
+                fees = replacement;
+                replacements += 1;
+                match send(fees).await {
+                    Ok(tx_hash) => sent.push(tx_hash),
+                    // An earlier submission may have been included in the meantime
+                    Err(e) => warn!(
+                        "Replacement of the submission for task {} was rejected: {}",
+                        response.task_index, e
+                    ),
+                }
+            }
+        }
+        .await;
+        self.nonces.lock().finish(nonce);
+        let receipt = receipt?;
 
         info!(
             "Sent aggregated response to contract for task index: {}",
             response.task_index,
         );
+        self.notify_confirmations(&receipt);
 
         Ok(())
     }
 }
 
+/// Resolves once [`AggregatorContext::shutdown`] was called
+async fn wait_for_shutdown(shutdown: &(Notify, Mutex<bool>)) {
+    let (notify, is_shutdown) = shutdown;
+    loop {
+        // Register for the notification before checking, so it cannot be missed
+        let notified = notify.notified();
+        if *is_shutdown.lock().await {
+            break;
+        }
+        notified.await;
+    }
+}
+
+/// Polls for the receipt of any of the transactions `sent` with the same nonce, until `until`.
+/// Returns `None` if none was included in time.
+async fn wait_for_receipt<P: Provider>(
+    provider: &P,
+    sent: &[B256],
+    until: Instant,
+) -> Result<Option<TransactionReceipt>, Error> {
+    loop {
+        for tx_hash in sent {
+            let receipt = provider
+                .get_transaction_receipt(*tx_hash)
+                .await
+                .map_err(|e| Error::Chain(e.to_string()))?;
+            if receipt.is_some() {
+                return Ok(receipt);
+            }
+        }
+        if Instant::now() >= until {
+            return Ok(None);
+        }
+        tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+    }
+}
+
+/// Checks an aggregated response before it is submitted on-chain: `digest` must be the signing
+/// hash of `task_response` in `domain` and `sigma` must be a valid signature over it by the
+/// aggregate key `apk_g2`
+pub fn verify_aggregated_signature(
+    task_response: &TaskResponse,
+    domain: &SigningDomain,
+    digest: B256,
+    apk_g2: G2Affine,
+    sigma: G1Affine,
+) -> Result<(), Error> {
+    let expected = domain.signing_hash(task_response);
+    if digest != expected {
+        return Err(Error::Context(format!(
+            "Aggregated digest {} does not match task response digest {}",
+            digest, expected
+        )));
+    }
+    if !verify_message(apk_g2, &expected.0, sigma) {
+        return Err(Error::Context(format!(
+            "Aggregated BLS signature does not verify for task response digest {}",
+            expected
+        )));
+    }
+    Ok(())
+}
+
+/// Resolves once the process receives SIGTERM or Ctrl-C, used to trigger
+/// [`AggregatorContext::shutdown`]
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+                return;
+            }
+            Err(e) => error!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        error!("Failed to listen for Ctrl-C: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
+
 #[async_trait::async_trait]
 impl BackgroundService for AggregatorContext {
     async fn start(&self) -> Result<oneshot::Receiver<Result<(), RunnerError>>, RunnerError> {
@@ -475,3 +1089,540 @@ impl BackgroundService for AggregatorContext {
         Ok(result_rx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contexts::client::AggregatorClient;
+    use crate::contexts::quorum_status::QuorumStakes;
+    use crate::test_support::serve_json_rpc;
+    use crate::IIncredibleSquaringTaskManager::TaskResponseMetadata;
+    use alloy_primitives::B256;
+    use alloy_primitives::U256;
+    use alloy_signer_local::PrivateKeySigner;
+    use alloy_sol_types::SolEvent;
+    use ark_ec::{AffineRepr, CurveGroup};
+    use eigensdk::crypto_bls::{BlsKeyPair, OperatorId};
+
+    fn free_address() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    fn test_context(address: String, task_manager_address: Address) -> AggregatorContext {
+        AggregatorContext::without_bls_service(
+            address,
+            task_manager_address,
+            EthereumWallet::from(PrivateKeySigner::random()),
+            GadgetConfiguration::default(),
+        )
+    }
+
+    /// Minimal JSON-RPC node answering `eth_chainId` and `eth_getCode` with `code`
+    async fn mock_rpc(code: &'static str) -> String {
+        serve_json_rpc(move |method, _| match method {
+            "eth_chainId" => serde_json::json!("0x7a69"),
+            _ => serde_json::json!(code),
+        })
+        .await
+    }
+
+    async fn get_health(address: &str) -> Option<(u16, serde_json::Value)> {
+        let response = reqwest::get(format!("http://{}{}", address, health::HEALTH_PATH))
+            .await
+            .ok()?;
+        let status = response.status().as_u16();
+        let body = response.text().await.ok()?;
+        Some((status, serde_json::from_str(&body).ok()?))
+    }
+
+    /// Polls the health endpoint until it answers
+    async fn wait_for_health(address: &str) -> (u16, serde_json::Value) {
+        for _ in 0..50 {
+            if let Some(health) = get_health(address).await {
+                return health;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("health endpoint never came up");
+    }
+
+    fn signed_response(task_index: u32) -> SignedTaskResponse {
+        let key_pair = BlsKeyPair::new("1".to_string()).unwrap();
+        SignedTaskResponse {
+            task_response: TaskResponse {
+                referenceTaskIndex: task_index,
+                resultHash: B256::ZERO,
+            },
+            signature: key_pair.sign_message(B256::ZERO.as_slice()),
+            operator_id: OperatorId::ZERO,
+            quorum_number: None,
+        }
+    }
+
+    /// Aggregates the operators' public keys and their signatures over each digest
+    fn aggregate(signers: &[(&BlsKeyPair, B256)]) -> (G2Affine, G1Affine) {
+        let apk = signers
+            .iter()
+            .fold(G2Affine::zero().into_group(), |apk, (key_pair, _)| {
+                apk + key_pair.public_key_g2().g2()
+            });
+        let sigma = signers.iter().fold(
+            G1Affine::zero().into_group(),
+            |sigma, (key_pair, digest)| {
+                sigma + key_pair.sign_message(digest.as_slice()).g1_point().g1()
+            },
+        );
+        (apk.into_affine(), sigma.into_affine())
+    }
+
+    #[test]
+    fn test_verify_aggregated_signature_rejects_tampered_signature() {
+        let task_response = TaskResponse {
+            referenceTaskIndex: 3,
+            resultHash: B256::repeat_byte(0xab),
+        };
+        let domain = SigningDomain::new(31337, Address::repeat_byte(0x42));
+        let digest = domain.signing_hash(&task_response);
+        let key_pairs: Vec<_> = ["1", "2", "3"]
+            .iter()
+            .map(|secret| BlsKeyPair::new(secret.to_string()).unwrap())
+            .collect();
+
+        let honest: Vec<_> = key_pairs
+            .iter()
+            .map(|key_pair| (key_pair, digest))
+            .collect();
+        let (apk, sigma) = aggregate(&honest);
+        verify_aggregated_signature(&task_response, &domain, digest, apk, sigma).unwrap();
+
+        // One operator signed a different digest
+        let mut tampered = honest.clone();
+        tampered[1].1 = B256::repeat_byte(0xff);
+        let (apk, sigma) = aggregate(&tampered);
+        assert!(matches!(
+            verify_aggregated_signature(&task_response, &domain, digest, apk, sigma),
+            Err(Error::Context(_))
+        ));
+
+        // The aggregate is over another task response than the one to be submitted
+        let (apk, sigma) = aggregate(&honest);
+        let other = TaskResponse {
+            referenceTaskIndex: 4,
+            ..task_response.clone()
+        };
+        assert!(verify_aggregated_signature(&other, &domain, digest, apk, sigma).is_err());
+    }
+
+    #[test]
+    fn test_verify_aggregated_signature_rejects_other_domains() {
+        let task_response = TaskResponse {
+            referenceTaskIndex: 3,
+            resultHash: B256::repeat_byte(0xab),
+        };
+        let domain = SigningDomain::new(31337, Address::repeat_byte(0x42));
+        let key_pair = BlsKeyPair::new("1".to_string()).unwrap();
+
+        // Signatures collected for the same response on another task manager are not replayable
+        let replayed = SigningDomain::new(31337, Address::repeat_byte(0x43));
+        let replayed_digest = replayed.signing_hash(&task_response);
+        let (apk, sigma) = aggregate(&[(&key_pair, replayed_digest)]);
+        verify_aggregated_signature(&task_response, &replayed, replayed_digest, apk, sigma)
+            .unwrap();
+        assert!(
+            verify_aggregated_signature(&task_response, &domain, replayed_digest, apk, sigma)
+                .is_err()
+        );
+        let digest = domain.signing_hash(&task_response);
+        assert!(verify_aggregated_signature(&task_response, &domain, digest, apk, sigma).is_err());
+    }
+
+    #[test]
+    fn test_task_params_keep_the_task_quorums() {
+        let task = Task {
+            taskCreatedBlock: 100,
+            quorumNumbers: vec![0].into(),
+            quorumThresholdPercentage: 67,
+        };
+        let context = test_context(free_address(), Address::ZERO);
+        assert_eq!(context.task_params(&task).quorum_numbers, vec![0]);
+
+        let context = context.with_task_config(
+            TaskConfig::default()
+                .with_quorum_numbers(vec![1, 2])
+                .with_quorum_threshold_percentage(80)
+                .with_task_interval_blocks(5),
+        );
+        let params = context.task_params(&task);
+        assert_eq!(params.quorum_numbers, vec![0]);
+        assert_eq!(params.quorum_threshold_percentages, vec![67]);
+        assert_eq!(params.time_to_expiry, Duration::from_secs(60));
+        assert_eq!(context.task_config().mismatches(&task).len(), 2);
+    }
+
+    #[test]
+    fn test_signing_domain_uses_chain_and_task_manager() {
+        let mut context = test_context(free_address(), Address::repeat_byte(0x42));
+        context.chain_id = 17000;
+        assert_eq!(
+            context.signing_domain(),
+            SigningDomain::new(17000, Address::repeat_byte(0x42))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_returns_within_timeout() {
+        let address = free_address();
+        let context = test_context(address.clone(), Address::ZERO)
+            .with_shutdown_timeout(Duration::from_secs(5));
+        let handle = context.clone().start().await;
+
+        // Wait for the server to come up, then deliver a response
+        let client = AggregatorClient::new(&address)
+            .unwrap()
+            .with_max_attempts(1);
+        let mut delivered = false;
+        for _ in 0..50 {
+            if client
+                .send_signed_task_response(signed_response(1))
+                .await
+                .is_ok()
+            {
+                delivered = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(delivered);
+
+        tokio::time::timeout(Duration::from_secs(10), context.shutdown())
+            .await
+            .expect("shutdown hung")
+            .unwrap();
+        assert!(context.is_shutting_down().await);
+        handle.await.unwrap();
+
+        // Shutting down again returns immediately
+        context.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_rejects_new_responses() {
+        let mut context = test_context("127.0.0.1:0".to_string(), Address::ZERO);
+
+        // Never started, so there is nothing to wait for
+        context.shutdown().await.unwrap();
+        assert!(context
+            .process_signed_task_response(signed_response(1))
+            .await
+            .is_err());
+        assert!(context.response_cache.lock().await.is_empty());
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_received_signatures_are_counted() {
+        let metrics = Metrics::new();
+        let mut context =
+            test_context("127.0.0.1:0".to_string(), Address::ZERO).with_metrics(metrics.clone());
+
+        context
+            .process_signed_task_response(signed_response(1))
+            .await
+            .unwrap();
+        assert!(metrics
+            .render()
+            .lines()
+            .any(|line| line == "parallel_exec_signatures_received_total 1"));
+    }
+
+    #[tokio::test]
+    async fn test_each_operator_signature_is_aggregated_once_per_task() {
+        let mut context = test_context(free_address(), Address::ZERO);
+        let task = Task {
+            taskCreatedBlock: 100,
+            quorumNumbers: vec![0, 1].into(),
+            quorumThresholdPercentage: 67,
+        };
+        context.tasks.lock().await.insert(3, task.clone());
+        let params = context.task_params(&task);
+        context
+            .quorum_tracker
+            .start(3, &params, QuorumStakes::new(), Instant::now());
+        context
+            .quorum_tracker
+            .record_signature(3, OperatorId::ZERO, Instant::now());
+
+        // The copy the operator sent for its second quorum never reaches the aggregation
+        // service, which this context does not even have
+        let copy = SignedTaskResponse {
+            quorum_number: Some(1),
+            ..signed_response(3)
+        };
+        context.process_response(copy).await.unwrap();
+        assert_eq!(context.quorum_status(3).unwrap().signatures, 1);
+        assert!(context.tasks_responses.lock().await.is_empty());
+
+        // Another operator's signature is aggregated
+        let other = SignedTaskResponse {
+            operator_id: OperatorId::repeat_byte(1),
+            ..signed_response(3)
+        };
+        assert!(context.process_response(other).await.is_err());
+    }
+
+    #[test]
+    fn test_quorum_failure_is_reported_when_the_window_closes() {
+        let context = test_context(free_address(), Address::ZERO);
+        let stakes = QuorumStakes::new()
+            .with_operator(OperatorId::repeat_byte(1), 0, U256::from(40))
+            .with_operator(OperatorId::repeat_byte(2), 0, U256::from(60));
+        let params = TaskParams {
+            quorum_numbers: vec![0],
+            quorum_threshold_percentages: vec![67],
+            time_to_expiry: Duration::from_secs(60),
+            quorum_window: None,
+        };
+        let now = Instant::now();
+        context.quorum_tracker.start(3, &params, stakes, now);
+        assert!(context.quorum_status(4).is_none());
+
+        // Only the operator with 40% of the stake signs
+        context
+            .quorum_tracker
+            .record_signature(3, OperatorId::repeat_byte(1), now);
+        let status = context.quorum_status(3).unwrap();
+        assert_eq!(status.state, QuorumState::Collecting);
+        assert_eq!(status.signatures, 1);
+        assert!(context
+            .check_quorum_deadlines(now + Duration::from_secs(30))
+            .is_empty());
+
+        let failed = context.check_quorum_deadlines(now + Duration::from_secs(60));
+        assert_eq!(failed.len(), 1);
+        assert_eq!(context.quorum_status(3).unwrap().state, QuorumState::Failed);
+        assert_eq!(
+            context.quorum_status(3).unwrap().quorums[0].signed_percentage(),
+            Some(40)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quorum_window_finalizes_the_task_at_its_deadline() {
+        let window = Duration::from_millis(300);
+        let context = test_context(free_address(), Address::ZERO)
+            .with_task_config(TaskConfig::default().with_quorum_window(window));
+        let task = Task {
+            taskCreatedBlock: 100,
+            quorumNumbers: vec![0].into(),
+            quorumThresholdPercentage: 67,
+        };
+        let handle = context.clone().start().await;
+
+        // The task expires in 20 minutes and responses are processed every 6 seconds, neither
+        // of which may be waited for
+        let stakes = QuorumStakes::new()
+            .with_operator(OperatorId::repeat_byte(1), 0, U256::from(40))
+            .with_operator(OperatorId::repeat_byte(2), 0, U256::from(60));
+        context
+            .quorum_tracker
+            .start(3, &context.task_params(&task), stakes, Instant::now());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let first_signature = Instant::now();
+        context
+            .quorum_tracker
+            .record_signature(3, OperatorId::repeat_byte(1), first_signature);
+        assert_eq!(
+            context.quorum_tracker.deadline(3),
+            Some(first_signature + window)
+        );
+
+        let failed_at = tokio::time::timeout(Duration::from_secs(3), async {
+            loop {
+                if context.quorum_status(3).unwrap().state == QuorumState::Failed {
+                    break Instant::now();
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("task was not finalized at the end of its quorum window");
+        assert!(failed_at >= first_signature + window);
+
+        context.shutdown().await.unwrap();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_signatures_after_the_quorum_window_are_dropped() {
+        let window = Duration::from_secs(10);
+        let mut context = test_context(free_address(), Address::ZERO)
+            .with_task_config(TaskConfig::default().with_quorum_window(window));
+        let task = Task {
+            taskCreatedBlock: 100,
+            quorumNumbers: vec![0].into(),
+            quorumThresholdPercentage: 67,
+        };
+        context.tasks.lock().await.insert(3, task.clone());
+        let stakes = QuorumStakes::new()
+            .with_operator(OperatorId::repeat_byte(1), 0, U256::from(40))
+            .with_operator(OperatorId::ZERO, 0, U256::from(60));
+        let now = Instant::now();
+        context
+            .quorum_tracker
+            .start(3, &context.task_params(&task), stakes, now);
+        context
+            .quorum_tracker
+            .record_signature(3, OperatorId::repeat_byte(1), now);
+        assert_eq!(context.check_quorum_deadlines(now + window).len(), 1);
+
+        // The signature that would have reached the threshold is dropped instead of being
+        // handed to the aggregation service, which this context does not even have
+        context.process_response(signed_response(3)).await.unwrap();
+        let status = context.quorum_status(3).unwrap();
+        assert_eq!(status.state, QuorumState::Failed);
+        assert_eq!(status.signatures, 1);
+        assert!(context.tasks_responses.lock().await.is_empty());
+        assert!(context.response_cache.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_confirmed_submission_notifies_subscribers() {
+        let task_manager = Address::repeat_byte(0x42);
+        let tx_hash = B256::repeat_byte(0x77);
+        let block_hash = B256::repeat_byte(0x78);
+        let event = IncredibleSquaringTaskManager::TaskResponded {
+            taskResponse: TaskResponse {
+                referenceTaskIndex: 5,
+                resultHash: B256::repeat_byte(0xab),
+            },
+            taskResponseMetadata: TaskResponseMetadata {
+                taskResponsedBlock: 120,
+                hashOfNonSigners: B256::ZERO,
+            },
+        }
+        .encode_log_data();
+        let log = |address: Address, log_index: u64| {
+            serde_json::json!({
+                "address": address,
+                "topics": event.topics(),
+                "data": event.data,
+                "blockHash": block_hash,
+                "blockNumber": "0x78",
+                "transactionHash": tx_hash,
+                "transactionIndex": "0x0",
+                "logIndex": format!("{:#x}", log_index),
+                "removed": false,
+            })
+        };
+        // The same event emitted by another contract is no confirmation
+        let receipt = serde_json::json!({
+            "transactionHash": tx_hash,
+            "transactionIndex": "0x0",
+            "blockHash": block_hash,
+            "blockNumber": "0x78",
+            "from": Address::repeat_byte(0x01),
+            "to": task_manager,
+            "contractAddress": null,
+            "cumulativeGasUsed": "0x5208",
+            "gasUsed": "0x5208",
+            "effectiveGasPrice": "0x1",
+            "status": "0x1",
+            "type": "0x2",
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "logs": [log(Address::repeat_byte(0x43), 0), log(task_manager, 1)],
+        });
+
+        let mut context = test_context(free_address(), task_manager);
+        context.http_rpc_url = serve_json_rpc(move |method, _| match method {
+            "eth_getTransactionReceipt" => receipt.clone(),
+            _ => serde_json::Value::Null,
+        })
+        .await;
+        let mut confirmations = context.subscribe_confirmations();
+
+        let expected = TaskConfirmation {
+            task_index: 5,
+            result_hash: B256::repeat_byte(0xab),
+            tx_hash,
+            block_number: Some(0x78),
+        };
+        assert_eq!(
+            context.confirm_submission(tx_hash).await.unwrap(),
+            vec![expected]
+        );
+        assert_eq!(confirmations.try_recv().unwrap(), expected);
+        assert!(confirmations.try_recv().is_err());
+
+        // Unknown transactions have no receipt
+        context.http_rpc_url = serve_json_rpc(|_, _| serde_json::Value::Null).await;
+        assert!(matches!(
+            context.confirm_submission(tx_hash).await,
+            Err(Error::Chain(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_receipt_is_not_waited_for_past_the_deadline() {
+        let provider = get_provider(&serve_json_rpc(|_, _| serde_json::Value::Null).await);
+        let sent = [B256::repeat_byte(0x77)];
+
+        let waited = tokio::time::timeout(
+            Duration::from_secs(5),
+            wait_for_receipt(
+                &provider,
+                &sent,
+                Instant::now() + Duration::from_millis(100),
+            ),
+        )
+        .await
+        .expect("waiting ends at the deadline");
+        assert!(matches!(waited, Ok(None)));
+    }
+
+    #[tokio::test]
+    async fn test_health_endpoint_when_ready() {
+        let health_address = free_address();
+        let mut context = test_context(free_address(), Address::repeat_byte(1))
+            .with_health_address(health_address.parse().unwrap());
+        context.http_rpc_url = mock_rpc("0x6080").await;
+
+        // Not started yet, so not up
+        let health = context.health().await;
+        assert!(health.rpc_connected && health.task_manager_deployed);
+        assert!(!health.up && !health.ready);
+
+        let handle = context.clone().start().await;
+        let (status, body) = wait_for_health(&health_address).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["ready"], true);
+
+        context.shutdown().await.unwrap();
+        handle.await.unwrap();
+        assert!(get_health(&health_address).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_health_endpoint_when_not_ready() {
+        // No code at the task manager address
+        let health_address = free_address();
+        let mut context = test_context(free_address(), Address::repeat_byte(1))
+            .with_health_address(health_address.parse().unwrap());
+        context.http_rpc_url = mock_rpc("0x").await;
+        let handle = context.clone().start().await;
+
+        let (status, body) = wait_for_health(&health_address).await;
+        assert_eq!(status, 503);
+        assert_eq!(body["up"], true);
+        assert_eq!(body["rpc_connected"], true);
+        assert_eq!(body["task_manager_deployed"], false);
+        context.shutdown().await.unwrap();
+        handle.await.unwrap();
+
+        // Unreachable RPC and unset task manager
+        let mut context = test_context(free_address(), Address::ZERO);
+        context.http_rpc_url = format!("http://{}", free_address());
+        let health = context.health().await;
+        assert_eq!(health, HealthStatus::new(false, false, false));
+    }
+}
@@ -0,0 +1,241 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use alloy_primitives::B256;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::debug;
+
+use crate::api_client::{ApiClient, ApiClientError, ApiResponse, Calculation};
+use crate::jobs::compute_x_square::{signed_schedule_for_task, ResultHashMode};
+
+#[derive(Debug, Error)]
+pub enum ApiRecordingError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// The helper API response a task was computed from, with the hashes computed from it.
+///
+/// The recording is a valid helper API response itself, so it can also be served as a
+/// [`BlockSource::File`](crate::api_client::BlockSource::File).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiRecording {
+    pub task_index: u32,
+    /// Unix time in seconds at which the response was recorded
+    pub recorded_at: u64,
+    /// Hash of the blocks, as computed by the API client
+    pub calculation_hash: B256,
+    /// Hash of the task's schedule, as signed in the task response
+    pub result_hash: B256,
+    /// How `result_hash` was derived from the schedule hash
+    #[serde(default)]
+    pub result_hash_mode: ResultHashMode,
+    /// Block the task was created at, whose schedule is signed and which is mixed into
+    /// `result_hash` by [`ResultHashMode::TaskCreatedBlock`]. Zero in recordings without
+    /// it, which are replayed against their newest block
+    #[serde(default)]
+    pub task_created_block: u32,
+    #[serde(flatten)]
+    pub response: ApiResponse,
+}
+
+/// Hashes computed again from an [`ApiRecording`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Replay {
+    pub calculation_hash: B256,
+    pub result_hash: B256,
+}
+
+impl ApiRecording {
+    /// Records the blocks of `calculation`, from which the task's `result_hash` was computed
+    pub fn new(task_index: u32, calculation: &Calculation, result_hash: B256) -> Self {
+        let recorded_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self {
+            task_index,
+            recorded_at,
+            calculation_hash: calculation.hash,
+            result_hash,
+            result_hash_mode: ResultHashMode::default(),
+            task_created_block: 0,
+            response: ApiResponse {
+                status: "success".to_string(),
+                message: format!("recorded for task {}", task_index),
+                data: calculation.blocks.clone(),
+            },
+        }
+    }
+
+    /// Records that `result_hash` was derived with `mode` for a task created at
+    /// `task_created_block`, so a replay derives it the same way
+    pub fn with_result_hash_mode(mut self, mode: ResultHashMode, task_created_block: u32) -> Self {
+        self.result_hash_mode = mode;
+        self.task_created_block = task_created_block;
+        self
+    }
+
+    /// Hashes and schedules the recorded blocks again, with the hash settings of `api_client`.
+    ///
+    /// The blocks are checked like freshly fetched ones, except for their age.
+    pub fn replay(&self, api_client: &ApiClient) -> Result<Replay, ApiClientError> {
+        let calculation = Calculation {
+            hash: api_client.rehash_blocks(&self.response.data)?,
+            blocks: self.response.data.clone(),
+            missing_blocks: 0,
+        };
+        let task_created_block = match self.task_created_block {
+            0 => calculation
+                .blocks
+                .last()
+                .ok_or(ApiClientError::EmptyResponse)?
+                .number_u64()?,
+            block => block.into(),
+        };
+        let (schedule, _) = signed_schedule_for_task(api_client, &calculation, task_created_block)?;
+        Ok(Replay {
+            calculation_hash: calculation.hash,
+            result_hash: self.result_hash_mode.result_hash(
+                schedule.hash(),
+                self.task_index,
+                self.task_created_block,
+            ),
+        })
+    }
+}
+
+impl Replay {
+    /// Returns whether the replay reproduced both recorded hashes
+    pub fn matches(&self, recording: &ApiRecording) -> bool {
+        self.calculation_hash == recording.calculation_hash
+            && self.result_hash == recording.result_hash
+    }
+}
+
+/// Directory of the helper API responses tasks were computed from, so a disagreement on a
+/// task's hash can be reproduced after the fact.
+///
+/// Every recording is stored as `<task_index>.json`, computing a task again overwrites it.
+#[derive(Debug, Clone)]
+pub struct ApiRecordings {
+    dir: PathBuf,
+}
+
+impl ApiRecordings {
+    /// Opens the recordings stored in `dir`, creating the directory if needed
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, ApiRecordingError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Returns the directory the recordings are stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the path of the recording of `task_index`
+    pub fn path_for(&self, task_index: u32) -> PathBuf {
+        self.dir.join(format!("{}.json", task_index))
+    }
+
+    /// Writes `recording`, replacing an earlier recording of the same task
+    pub fn write(&self, recording: &ApiRecording) -> Result<PathBuf, ApiRecordingError> {
+        // Write to a temporary file first so a replay never reads a truncated recording
+        let path = self.path_for(recording.task_index);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(recording)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        debug!(
+            "Recorded the API response of task {} to {}",
+            recording.task_index,
+            path.display()
+        );
+        Ok(path)
+    }
+
+    /// Reads the recording of `task_index`, or `None` if the task was not recorded
+    pub fn read(&self, task_index: u32) -> Result<Option<ApiRecording>, ApiRecordingError> {
+        match fs::read(self.path_for(task_index)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::{BlockSource, HashMode};
+    use crate::jobs::compute_x_square::schedule_for_task;
+
+    const BLOCKS: &str = r#"{
+        "status": "success",
+        "message": "ok",
+        "data": [
+            {
+                "hash": "0x0a885e8a3a5cb03e0540e7ef65734c46189f17a65596009570a227d3abb6d466",
+                "number": "0x10",
+                "timestamp": "0x67c2a1b0",
+                "transactions_root": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                "parent_hash": "0x2946018d361c6825c64c35c1421f3d80b16c3262dd01234a65dbf6bdb4422121",
+                "transactions": [
+                    {"hash": "0x01", "gas": "0x5208", "access_list": [{"address": "0x000000000000000000000000000000000000000a", "storage_keys": []}]},
+                    {"hash": "0x02", "gas": "0x5208", "access_list": [{"address": "0x000000000000000000000000000000000000000b", "storage_keys": []}]}
+                ]
+            }
+        ]
+    }"#;
+
+    #[tokio::test]
+    async fn test_recorded_response_replays_to_the_same_hashes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let blocks_file = dir.path().join("blocks.json");
+        fs::write(&blocks_file, BLOCKS).unwrap();
+        let api_client = ApiClient::new().with_block_source(BlockSource::File(blocks_file));
+
+        // Compute a task the way the job does and record what it was computed from
+        let calculation = api_client.get_calculation_detailed().await.unwrap();
+        let (schedule, _) = schedule_for_task(&calculation, 0x10).unwrap();
+        let recordings = ApiRecordings::open(dir.path().join("recordings")).unwrap();
+        let path = recordings
+            .write(&ApiRecording::new(7, &calculation, schedule.hash()))
+            .unwrap();
+        assert_eq!(path, dir.path().join("recordings").join("7.json"));
+        assert!(recordings.read(8).unwrap().is_none());
+
+        let recording = recordings.read(7).unwrap().unwrap();
+        assert_eq!(recording.task_index, 7);
+        let replay = recording.replay(&api_client).unwrap();
+        assert_eq!(replay.calculation_hash, calculation.hash);
+        assert_eq!(replay.result_hash, schedule.hash());
+        assert!(replay.matches(&recording));
+
+        // Replaying with other hash settings shows where a disagreeing operator went wrong
+        let raw_bytes = ApiClient::new().with_hash_mode(HashMode::RawBytes);
+        assert!(!recording.replay(&raw_bytes).unwrap().matches(&recording));
+
+        // The recording can be served as a block file in place of the helper API
+        let served = ApiClient::new()
+            .with_block_source(BlockSource::File(path))
+            .get_calculation()
+            .await
+            .unwrap();
+        assert_eq!(served, calculation.hash);
+
+        // A hash with the task mixed in is replayed with the same mode
+        let mode = ResultHashMode::TaskCreatedBlock;
+        let mixed = ApiRecording::new(7, &calculation, mode.result_hash(schedule.hash(), 7, 16))
+            .with_result_hash_mode(mode, 16);
+        let replay = mixed.replay(&api_client).unwrap();
+        assert_ne!(replay.result_hash, schedule.hash());
+        assert!(replay.matches(&mixed));
+    }
+}
@@ -1,84 +1,1063 @@
-use alloy_rpc_client::ReqwestClient;
-use color_eyre::Result;
+use alloy_rpc_client::{ReqwestClient, RpcClient};
+use alloy_transport::TransportError;
+use alloy_transport_http::Http;
+use async_trait::async_trait;
 use eigensdk::crypto_bls::{OperatorId, Signature};
+use futures::stream::{FuturesUnordered, StreamExt};
+use parking_lot::RwLock;
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use tokio::time::{sleep, Duration};
-use tracing::{debug, info};
+use serde_json::{json, Value};
+use std::error::Error as StdError;
+use std::hash::{Hash, Hasher};
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::time::{sleep, timeout, Duration};
+use tracing::{debug, info, warn};
 
+use crate::contexts::response_codec::{JsonCodec, ResponseCodec, ResponseCodecError};
+use crate::signer::{EcdsaSignedTaskResponse, SignedTaskPayload};
 use crate::IIncredibleSquaringTaskManager::TaskResponse;
 
 const MAX_RETRIES: u32 = 5;
 const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+const DEFAULT_SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum AggregatorClientError {
+    #[error("Transport error: {0}")]
+    Transport(#[from] TransportError),
+    #[error("Aggregator rejected the task response")]
+    Rejected,
+    #[error("Aggregator did not acknowledge the task response within {0:?}")]
+    AckTimeout(Duration),
+    #[error("Invalid aggregator address {address:?}: {reason}")]
+    InvalidAddress { address: String, reason: String },
+    #[error("All {} aggregators failed", .0.len())]
+    AllAggregatorsFailed(Vec<(String, AggregatorClientError)>),
+    #[error("Failed to encode the task response: {0}")]
+    Codec(#[from] ResponseCodecError),
+    #[error("Failed to build the HTTP client: {0}")]
+    HttpClient(#[from] reqwest::Error),
+}
+
+impl AggregatorClientError {
+    /// Lost connections and missing acknowledgements are worth retrying, rejections are not
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AggregatorClientError::Transport(e) => is_connection_error(e),
+            AggregatorClientError::Rejected => false,
+            AggregatorClientError::AckTimeout(_) => true,
+            AggregatorClientError::InvalidAddress { .. } => false,
+            AggregatorClientError::AllAggregatorsFailed(failures) => {
+                failures.iter().any(|(_, e)| e.is_retryable())
+            }
+            AggregatorClientError::Codec(_) => false,
+            AggregatorClientError::HttpClient(_) => false,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignedTaskResponse {
     pub task_response: TaskResponse,
     pub signature: Signature,
     pub operator_id: OperatorId,
+    /// Quorum the response is sent for, `None` for responses of operators that do not track
+    /// their quorums
+    #[serde(default)]
+    pub quorum_number: Option<u8>,
+}
+
+// BLS signatures are compared by their G1 point, so responses can be deduplicated in sets
+impl PartialEq for SignedTaskResponse {
+    fn eq(&self, other: &Self) -> bool {
+        self.task_response == other.task_response
+            && self.signature.g1_point().g1() == other.signature.g1_point().g1()
+            && self.operator_id == other.operator_id
+            && self.quorum_number == other.quorum_number
+    }
+}
+
+impl Eq for SignedTaskResponse {}
+
+impl Hash for SignedTaskResponse {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.task_response.hash(state);
+        self.signature.g1_point().g1().hash(state);
+        self.operator_id.hash(state);
+        self.quorum_number.hash(state);
+    }
+}
+
+/// How often and how quickly the client reconnects after losing its connection to the aggregator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectConfig {
+    /// Number of reconnects attempted for a single request before the error is surfaced
+    pub reconnect_attempts: u32,
+    /// Delay before the first reconnect, doubled for every subsequent one
+    pub base_delay: Duration,
+    /// Upper bound for a single reconnect delay
+    pub max_delay: Duration,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            reconnect_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// Returns the delay to wait before the given (1-based) reconnect
+    pub fn backoff(&self, reconnect: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(reconnect.saturating_sub(1)))
+            .min(self.max_delay)
+    }
+}
+
+/// How the client keeps HTTP connections to the aggregator open between sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Idle connections kept open for reuse, 0 opens a new connection for every send
+    pub max_idle_connections: usize,
+    /// How long an idle connection is kept open before it is closed
+    pub idle_timeout: Duration,
+    /// Interval of TCP keep-alive probes, so dead connections are noticed between tasks
+    pub tcp_keepalive: Duration,
+}
+
+/// Keeps a few connections for up to 90 seconds, which covers the time between tasks while
+/// still letting the aggregator restart without the client holding on to stale connections
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_connections: 4,
+            idle_timeout: Duration::from_secs(90),
+            tcp_keepalive: Duration::from_secs(60),
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Builds an HTTP client with this pool configuration
+    fn http_client(&self) -> reqwest::Result<reqwest::Client> {
+        reqwest::Client::builder()
+            .pool_max_idle_per_host(self.max_idle_connections)
+            .pool_idle_timeout(self.idle_timeout)
+            .tcp_keepalive(self.tcp_keepalive)
+            .build()
+    }
+}
+
+/// Delivers signed task responses to the aggregator, so the job can be tested without one
+#[async_trait]
+pub trait AggregatorTransport: std::fmt::Debug + Send + Sync {
+    /// Sends a signed task response, returning `Ok(())` once the aggregator acknowledged it
+    async fn send_signed_task_response(
+        &self,
+        response: SignedTaskResponse,
+    ) -> std::result::Result<(), AggregatorClientError>;
+
+    /// Sends an ECDSA signed task response, returning `Ok(())` once the aggregator acknowledged it
+    async fn send_ecdsa_signed_task_response(
+        &self,
+        response: EcdsaSignedTaskResponse,
+    ) -> std::result::Result<(), AggregatorClientError>;
+
+    /// Sends a signed task response of either scheme
+    async fn send_task_payload(
+        &self,
+        payload: SignedTaskPayload,
+    ) -> std::result::Result<(), AggregatorClientError> {
+        match payload {
+            SignedTaskPayload::Bls(response) => self.send_signed_task_response(response).await,
+            SignedTaskPayload::Ecdsa(response) => {
+                self.send_ecdsa_signed_task_response(response).await
+            }
+        }
+    }
 }
 
 /// Client for interacting with the Aggregator RPC server
 #[derive(Debug, Clone)]
 pub struct AggregatorClient {
-    client: ReqwestClient,
+    url: Url,
+    client: Arc<RwLock<ReqwestClient>>,
+    reconnect_config: ReconnectConfig,
+    pool_config: PoolConfig,
+    send_timeout: Duration,
+    max_attempts: u32,
+    codec: Arc<dyn ResponseCodec>,
 }
 
 impl AggregatorClient {
-    /// Creates a new AggregatorClient
-    pub fn new(aggregator_address: &str) -> Result<Self> {
-        let url = Url::parse(&format!("http://{}", aggregator_address))?;
-        let client = ReqwestClient::new_http(url);
-        Ok(Self { client })
+    /// Creates a new AggregatorClient for `aggregator_address`, given either as `host:port` or
+    /// as an `http://` or `https://` URL with an explicit port
+    pub fn new(aggregator_address: &str) -> std::result::Result<Self, AggregatorClientError> {
+        let url = parse_aggregator_url(aggregator_address)?;
+        let pool_config = PoolConfig::default();
+        let client = connect(&url, &pool_config)?;
+        Ok(Self {
+            url,
+            client: Arc::new(RwLock::new(client)),
+            reconnect_config: ReconnectConfig::default(),
+            pool_config,
+            send_timeout: DEFAULT_SEND_TIMEOUT,
+            max_attempts: MAX_RETRIES,
+            codec: Arc::new(JsonCodec),
+        })
+    }
+
+    /// Sets how the client reconnects after a connection reset or refusal
+    pub fn with_reconnect_config(mut self, reconnect_config: ReconnectConfig) -> Self {
+        self.reconnect_config = reconnect_config;
+        self
+    }
+
+    /// Sets how connections to the aggregator are reused between sends
+    pub fn with_pool_config(
+        mut self,
+        pool_config: PoolConfig,
+    ) -> std::result::Result<Self, AggregatorClientError> {
+        self.client = Arc::new(RwLock::new(connect(&self.url, &pool_config)?));
+        self.pool_config = pool_config;
+        Ok(self)
+    }
+
+    /// Sets how long a single request may wait for the aggregator's acknowledgement. Time spent
+    /// reconnecting in between is not counted
+    pub fn with_send_timeout(mut self, send_timeout: Duration) -> Self {
+        self.send_timeout = send_timeout;
+        self
+    }
+
+    /// Sets how many times a task response is sent before giving up, including the first send
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Encodes signed task responses with `codec` instead of as JSON objects, which the
+    /// aggregator has to decode with the same codec
+    pub fn with_response_codec(mut self, codec: Arc<dyn ResponseCodec>) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Returns the URL task responses are sent to
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Drops the current connection pool and starts over with a fresh client
+    fn reconnect(&self) -> std::result::Result<(), AggregatorClientError> {
+        *self.client.write() = connect(&self.url, &self.pool_config)?;
+        Ok(())
+    }
+
+    /// Issues the RPC request, transparently reconnecting on connection-level failures. Every
+    /// request gets the full send timeout to be acknowledged, the reconnect backoff between
+    /// them is not counted against it
+    async fn request_with_reconnect(
+        &self,
+        method: &'static str,
+        params: &Value,
+    ) -> std::result::Result<(), AggregatorClientError> {
+        let mut reconnects = 0;
+        loop {
+            let client = self.client.read().clone();
+            let request = client.request::<_, bool>(method, params);
+            let Ok(result) = timeout(self.send_timeout, request).await else {
+                return Err(AggregatorClientError::AckTimeout(self.send_timeout));
+            };
+            match result {
+                Err(e)
+                    if is_connection_error(&e)
+                        && reconnects < self.reconnect_config.reconnect_attempts =>
+                {
+                    reconnects += 1;
+                    let delay = self.reconnect_config.backoff(reconnects);
+                    warn!(
+                        "Lost connection to aggregator: {}, reconnecting in {:?} ({}/{})",
+                        e, delay, reconnects, self.reconnect_config.reconnect_attempts
+                    );
+                    sleep(delay).await;
+                    self.reconnect()?;
+                }
+                Ok(true) => return Ok(()),
+                Ok(false) => return Err(AggregatorClientError::Rejected),
+                Err(e) => return Err(AggregatorClientError::Transport(e)),
+            }
+        }
+    }
+
+    /// Sends a signed task response to the aggregator.
+    ///
+    /// Returns `Ok(())` only once the aggregator has acknowledged the response, otherwise the
+    /// error from the last attempt is returned. Errors that are not
+    /// [retryable](AggregatorClientError::is_retryable), like a rejection, are returned right
+    /// away.
+    pub async fn send_signed_task_response(
+        &self,
+        response: SignedTaskResponse,
+    ) -> std::result::Result<(), AggregatorClientError> {
+        let params = self.codec.encode(&response)?;
+        self.send_with_retries("process_signed_task_response", params)
+            .await
+    }
+
+    /// Sends an ECDSA signed task response to the aggregator, retrying like
+    /// [`send_signed_task_response`](Self::send_signed_task_response).
+    ///
+    /// The aggregator has to serve `process_ecdsa_signed_task_response` for this to succeed.
+    pub async fn send_ecdsa_signed_task_response(
+        &self,
+        response: EcdsaSignedTaskResponse,
+    ) -> std::result::Result<(), AggregatorClientError> {
+        self.send_with_retries("process_ecdsa_signed_task_response", json!(response))
+            .await
     }
 
-    /// Sends a signed task response to the aggregator
-    pub async fn send_signed_task_response(&self, response: SignedTaskResponse) -> Result<()> {
+    async fn send_with_retries(
+        &self,
+        method: &'static str,
+        response: Value,
+    ) -> std::result::Result<(), AggregatorClientError> {
         let params = json!({
             "params": response,
             "id": 1,
             "jsonrpc": "2.0"
         });
+        let max_attempts = self.max_attempts.max(1);
 
-        for attempt in 1..=MAX_RETRIES {
-            match self
-                .client
-                .request::<_, bool>("process_signed_task_response", &params)
-                .await
-            {
-                Ok(true) => {
+        let mut attempt = 1;
+        loop {
+            let error = match self.request_with_reconnect(method, &params).await {
+                Ok(()) => {
                     info!("Task response accepted by aggregator");
                     // MARK: Uncomment when metrics are implemented
                     // incredible_metrics::inc_num_tasks_accepted_by_aggregator();
                     return Ok(());
                 }
-                Ok(false) => debug!("Task response not accepted, retrying..."),
-                Err(e) => debug!("Error sending task response: {}", e),
+                Err(error) => error,
+            };
+
+            if !error.is_retryable() {
+                debug!("Not retrying task response: {}", error);
+                return Err(error);
+            }
+            if attempt >= max_attempts {
+                debug!(
+                    "Failed to send signed task response after {} attempts",
+                    max_attempts
+                );
+                return Err(error);
+            }
+
+            debug!("Error sending task response: {}", error);
+            let delay = retry_delay(attempt);
+            info!("Retrying in {} seconds...", delay.as_secs());
+            sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl AggregatorTransport for AggregatorClient {
+    async fn send_signed_task_response(
+        &self,
+        response: SignedTaskResponse,
+    ) -> std::result::Result<(), AggregatorClientError> {
+        AggregatorClient::send_signed_task_response(self, response).await
+    }
+
+    async fn send_ecdsa_signed_task_response(
+        &self,
+        response: EcdsaSignedTaskResponse,
+    ) -> std::result::Result<(), AggregatorClientError> {
+        AggregatorClient::send_ecdsa_signed_task_response(self, response).await
+    }
+}
+
+/// Sends every task response to several aggregators at once, so a single aggregator going down
+/// does not keep responses from being aggregated.
+///
+/// A response counts as delivered once any aggregator acknowledged it, without waiting for the
+/// slower aggregators, which still receive it in the background. Only when every aggregator
+/// failed is [`AggregatorClientError::AllAggregatorsFailed`] returned.
+#[derive(Debug, Clone, Default)]
+pub struct MultiAggregatorClient {
+    targets: Vec<AggregatorTarget>,
+}
+
+#[derive(Debug, Clone)]
+struct AggregatorTarget {
+    name: String,
+    transport: Arc<dyn AggregatorTransport>,
+    accepted: Arc<AtomicU64>,
+    failed: Arc<AtomicU64>,
+}
+
+impl AggregatorTarget {
+    /// Counts the outcome of sending a response to this aggregator
+    fn record(&self, result: &std::result::Result<(), AggregatorClientError>) {
+        match result {
+            Ok(()) => {
+                self.accepted.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => {
+                warn!(
+                    "Aggregator {} did not take the task response: {}",
+                    self.name, e
+                );
+                self.failed.fetch_add(1, Ordering::Relaxed);
             }
+        }
+    }
+}
+
+/// How many responses one aggregator of a [`MultiAggregatorClient`] accepted and failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregatorDeliveries {
+    pub name: String,
+    pub accepted: u64,
+    pub failed: u64,
+}
+
+impl MultiAggregatorClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends to every client, each named by its URL
+    pub fn from_clients(clients: impl IntoIterator<Item = AggregatorClient>) -> Self {
+        clients.into_iter().fold(Self::new(), |multi, client| {
+            let name = client.url().to_string();
+            multi.with_target(name, Arc::new(client))
+        })
+    }
+
+    /// Also sends to `transport`, named `name` in logs and [`deliveries`](Self::deliveries)
+    pub fn with_target(
+        mut self,
+        name: impl Into<String>,
+        transport: Arc<dyn AggregatorTransport>,
+    ) -> Self {
+        self.targets.push(AggregatorTarget {
+            name: name.into(),
+            transport,
+            accepted: Arc::new(AtomicU64::new(0)),
+            failed: Arc::new(AtomicU64::new(0)),
+        });
+        self
+    }
+
+    /// Returns the deliveries to every aggregator, in the order they were added
+    pub fn deliveries(&self) -> Vec<AggregatorDeliveries> {
+        self.targets
+            .iter()
+            .map(|target| AggregatorDeliveries {
+                name: target.name.clone(),
+                accepted: target.accepted.load(Ordering::Relaxed),
+                failed: target.failed.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
 
-            if attempt < MAX_RETRIES {
-                let delay = INITIAL_RETRY_DELAY * 2u32.pow(attempt - 1);
-                info!("Retrying in {} seconds...", delay.as_secs());
-                sleep(delay).await;
+    async fn fan_out(
+        &self,
+        payload: SignedTaskPayload,
+    ) -> std::result::Result<(), AggregatorClientError> {
+        let mut sends: FuturesUnordered<_> = self
+            .targets
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(index, target)| {
+                let payload = payload.clone();
+                async move {
+                    let result = target.transport.send_task_payload(payload).await;
+                    (index, target, result)
+                }
+            })
+            .collect();
+
+        let mut failures = Vec::new();
+        while let Some((index, target, result)) = sends.next().await {
+            target.record(&result);
+            match result {
+                Ok(()) => {
+                    // The slower aggregators still get the response, without holding up the
+                    // caller
+                    tokio::spawn(async move {
+                        while let Some((_, target, result)) = sends.next().await {
+                            target.record(&result);
+                        }
+                    });
+                    return Ok(());
+                }
+                Err(e) => failures.push((index, target.name, e)),
             }
         }
+        // Report the failures in the order the aggregators were added
+        failures.sort_by_key(|(index, _, _)| *index);
+        Err(AggregatorClientError::AllAggregatorsFailed(
+            failures.into_iter().map(|(_, name, e)| (name, e)).collect(),
+        ))
+    }
+}
 
-        debug!(
-            "Failed to send signed task response after {} attempts",
-            MAX_RETRIES
-        );
-        Ok(())
+#[async_trait]
+impl AggregatorTransport for MultiAggregatorClient {
+    async fn send_signed_task_response(
+        &self,
+        response: SignedTaskResponse,
+    ) -> std::result::Result<(), AggregatorClientError> {
+        self.fan_out(SignedTaskPayload::Bls(response)).await
+    }
+
+    async fn send_ecdsa_signed_task_response(
+        &self,
+        response: EcdsaSignedTaskResponse,
+    ) -> std::result::Result<(), AggregatorClientError> {
+        self.fan_out(SignedTaskPayload::Ecdsa(response)).await
     }
+
+    async fn send_task_payload(
+        &self,
+        payload: SignedTaskPayload,
+    ) -> std::result::Result<(), AggregatorClientError> {
+        self.fan_out(payload).await
+    }
+}
+
+/// Returns the delay to wait after the given (1-based) failed attempt, doubling from
+/// [`INITIAL_RETRY_DELAY`] up to [`MAX_RETRY_DELAY`]
+fn retry_delay(attempt: u32) -> Duration {
+    INITIAL_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Creates an RPC client for `url` whose connections are pooled according to `pool_config`
+fn connect(
+    url: &Url,
+    pool_config: &PoolConfig,
+) -> std::result::Result<ReqwestClient, AggregatorClientError> {
+    Ok(RpcClient::new(
+        Http::with_client(pool_config.http_client()?, url.clone()),
+        false,
+    ))
+}
+
+/// Parses and validates an aggregator address, normalizing bare `host:port` to `http://host:port`
+fn parse_aggregator_url(address: &str) -> std::result::Result<Url, AggregatorClientError> {
+    let invalid = |reason: &str| AggregatorClientError::InvalidAddress {
+        address: address.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let trimmed = address.trim();
+    let (scheme, rest) = trimmed.split_once("://").unwrap_or(("http", trimmed));
+    if !matches!(scheme, "http" | "https") {
+        return Err(invalid("scheme must be http or https"));
+    }
+
+    // `Url` hides default ports, so check the authority itself for an explicit one
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    match authority.rsplit_once(':') {
+        Some((host, _)) if host.is_empty() => return Err(invalid("missing host")),
+        Some((_, port)) if port.parse::<u16>().is_ok() => {}
+        Some((_, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => {
+            return Err(invalid("port out of range"))
+        }
+        _ => return Err(invalid("missing port, expected host:port")),
+    }
+
+    let url = Url::parse(&format!("{}://{}", scheme, rest)).map_err(|e| invalid(&e.to_string()))?;
+    match url.host_str() {
+        Some(host) if !host.is_empty() => Ok(url),
+        _ => Err(invalid("missing host")),
+    }
+}
+
+/// Returns true if the error, or any error it wraps, means the connection was refused or dropped
+fn is_connection_error(error: &TransportError) -> bool {
+    let mut source: Option<&(dyn StdError + 'static)> = Some(error);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+            if err.is_connect() {
+                return true;
+            }
+        }
+        if let Some(err) = err.downcast_ref::<std::io::Error>() {
+            if matches!(
+                err.kind(),
+                ErrorKind::ConnectionRefused
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::NotConnected
+            ) {
+                return true;
+            }
+        }
+        source = err.source();
+    }
+    false
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{self, json_response, read_request};
+    use crate::IIncredibleSquaringTaskManager::TaskResponse;
+    use alloy_primitives::B256;
+    use eigensdk::crypto_bls::BlsKeyPair;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    /// Minimal JSON-RPC aggregator that resets the first `resets` connections and answers
+    /// every later request with `ack`, or never answers at all if `ack` is `None`.
+    struct MockAggregator {
+        addr: SocketAddr,
+        connections: Arc<AtomicUsize>,
+    }
+
+    impl MockAggregator {
+        async fn start(resets: usize, ack: Option<bool>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let connections = Arc::new(AtomicUsize::new(0));
+
+            let counter = connections.clone();
+            tokio::spawn(async move {
+                let mut silent = Vec::new();
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let seen = counter.fetch_add(1, Ordering::SeqCst);
+                    let Some((_, body)) = read_request(&mut stream).await else {
+                        continue;
+                    };
+                    if seen < resets {
+                        // Close with RST so the client sees a connection reset
+                        let _ = stream.set_linger(Some(Duration::ZERO));
+                        drop(stream);
+                        continue;
+                    }
+
+                    let Some(ack) = ack else {
+                        // Keep the connection open without ever acknowledging
+                        silent.push(stream);
+                        continue;
+                    };
+
+                    let response = rpc_reply(&body, ack, "close");
+                    let _ = stream.write_all(response.as_bytes()).await;
+                }
+            });
+
+            Self { addr, connections }
+        }
+
+        /// Aggregator acknowledging every request and keeping connections open between them
+        async fn start_keep_alive() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let connections = Arc::new(AtomicUsize::new(0));
+
+            let counter = connections.clone();
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    tokio::spawn(async move {
+                        while let Some((_, body)) = read_request(&mut stream).await {
+                            let response = rpc_reply(&body, true, "keep-alive");
+                            if stream.write_all(response.as_bytes()).await.is_err() {
+                                break;
+                            }
+                        }
+                    });
+                }
+            });
+
+            Self { addr, connections }
+        }
+
+        fn address(&self) -> String {
+            self.addr.to_string()
+        }
+
+        fn connections(&self) -> usize {
+            self.connections.load(Ordering::SeqCst)
+        }
+    }
+
+    /// Answers a JSON-RPC request with `ack`, sending the given `Connection` header
+    fn rpc_reply(body: &[u8], ack: bool, connection: &str) -> String {
+        let request: Value = serde_json::from_slice(body).unwrap_or_default();
+        let reply = json!({ "jsonrpc": "2.0", "id": request["id"], "result": ack }).to_string();
+        json_response(200, &format!("Connection: {}\r\n", connection), &reply)
+    }
+
+    fn signed_response() -> SignedTaskResponse {
+        let key_pair = BlsKeyPair::new("1".to_string()).unwrap();
+        SignedTaskResponse {
+            task_response: TaskResponse {
+                referenceTaskIndex: 1,
+                resultHash: B256::ZERO,
+            },
+            signature: key_pair.sign_message(B256::ZERO.as_slice()),
+            operator_id: OperatorId::ZERO,
+            quorum_number: None,
+        }
+    }
+
+    /// Waits until every aggregator of `multi` took or failed `sends` responses, since the
+    /// slower ones finish in the background, and returns their (accepted, failed) counts
+    async fn settled_deliveries(multi: &MultiAggregatorClient, sends: u64) -> Vec<(u64, u64)> {
+        let counts = || {
+            multi
+                .deliveries()
+                .iter()
+                .map(|d| (d.accepted, d.failed))
+                .collect::<Vec<_>>()
+        };
+        timeout(Duration::from_secs(5), async {
+            while !counts()
+                .iter()
+                .all(|(accepted, failed)| accepted + failed == sends)
+            {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("every aggregator settles");
+        counts()
+    }
+
+    fn hash_of(value: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_equal_responses_hash_alike() {
+        let (first, second) = (signed_response(), signed_response());
+        assert_eq!(first, second);
+        assert_eq!(first.task_response, second.task_response);
+
+        assert_eq!(hash_of(&first), hash_of(&second));
+        assert_eq!(
+            hash_of(&first.task_response),
+            hash_of(&second.task_response)
+        );
+
+        let other_quorum = SignedTaskResponse {
+            quorum_number: Some(1),
+            ..second.clone()
+        };
+        let other_signature = SignedTaskResponse {
+            signature: BlsKeyPair::new("2".to_string())
+                .unwrap()
+                .sign_message(B256::ZERO.as_slice()),
+            ..second.clone()
+        };
+        assert_ne!(first, other_quorum);
+        assert_ne!(first, other_signature);
+
+        let responses: HashSet<_> = [first, second, other_quorum, other_signature]
+            .into_iter()
+            .collect();
+        assert_eq!(responses.len(), 3);
+    }
 
     #[test]
     fn test_new_client() {
         let client = AggregatorClient::new("127.0.0.1:8545");
         assert!(client.is_ok());
     }
+
+    #[test]
+    fn test_new_client_normalizes_address() {
+        for (address, expected) in [
+            ("127.0.0.1:8081", "http://127.0.0.1:8081/"),
+            ("  aggregator.local:8081 ", "http://aggregator.local:8081/"),
+            ("http://127.0.0.1:8081", "http://127.0.0.1:8081/"),
+            ("https://aggregator.local:443", "https://aggregator.local/"),
+            ("[::1]:8081", "http://[::1]:8081/"),
+        ] {
+            let client = AggregatorClient::new(address).unwrap();
+            assert_eq!(client.url().as_str(), expected, "{}", address);
+        }
+    }
+
+    #[test]
+    fn test_new_client_rejects_missing_port() {
+        for address in [
+            "127.0.0.1",
+            "aggregator.local",
+            "http://127.0.0.1",
+            "127.0.0.1:",
+            "[::1]",
+        ] {
+            let err = AggregatorClient::new(address).unwrap_err();
+            assert!(
+                matches!(&err, AggregatorClientError::InvalidAddress { reason, .. } if reason.contains("missing port")),
+                "{}: {}",
+                address,
+                err
+            );
+            assert!(!err.is_retryable());
+        }
+    }
+
+    #[test]
+    fn test_new_client_rejects_garbage() {
+        for address in [
+            "",
+            "not an address",
+            ":8081",
+            "ftp://127.0.0.1:21",
+            "127.0.0.1:99999",
+            "http://exa mple.com:80",
+        ] {
+            let err = AggregatorClient::new(address).unwrap_err();
+            assert!(
+                matches!(err, AggregatorClientError::InvalidAddress { .. }),
+                "{}",
+                address
+            );
+        }
+
+        let err = AggregatorClient::new("127.0.0.1:99999").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid aggregator address \"127.0.0.1:99999\": port out of range"
+        );
+    }
+
+    #[test]
+    fn test_reconnect_backoff_is_capped() {
+        let config = ReconnectConfig {
+            reconnect_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+        assert_eq!(config.backoff(1), Duration::from_millis(100));
+        assert_eq!(config.backoff(2), Duration::from_millis(200));
+        assert_eq!(config.backoff(5), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_retry_delay_is_capped() {
+        assert_eq!(retry_delay(1), INITIAL_RETRY_DELAY);
+        assert_eq!(retry_delay(2), INITIAL_RETRY_DELAY * 2);
+        assert_eq!(retry_delay(7), MAX_RETRY_DELAY);
+        // Far more attempts than fit a u32 power of two
+        assert_eq!(retry_delay(40), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u32::MAX), MAX_RETRY_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_send_reconnects_after_connection_reset() {
+        let aggregator = MockAggregator::start(1, Some(true)).await;
+        let client = AggregatorClient::new(&aggregator.address())
+            .unwrap()
+            .with_reconnect_config(ReconnectConfig {
+                reconnect_attempts: 2,
+                base_delay: Duration::from_millis(10),
+                max_delay: Duration::from_millis(10),
+            });
+
+        let started = Instant::now();
+        client
+            .send_signed_task_response(signed_response())
+            .await
+            .unwrap();
+
+        // The reconnect path retries well before the slower per-send retry loop kicks in
+        assert!(started.elapsed() < INITIAL_RETRY_DELAY);
+        assert_eq!(aggregator.connections(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_send_times_out_without_ack() {
+        let aggregator = MockAggregator::start(0, None).await;
+        let client = AggregatorClient::new(&aggregator.address())
+            .unwrap()
+            .with_send_timeout(Duration::from_millis(100))
+            .with_max_attempts(1);
+
+        let err = client
+            .send_signed_task_response(signed_response())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            AggregatorClientError::AckTimeout(waited) if waited == Duration::from_millis(100)
+        ));
+        assert!(err.is_retryable());
+        assert_eq!(aggregator.connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sequential_sends_reuse_pooled_connection() {
+        const SENDS: usize = 5;
+
+        let aggregator = MockAggregator::start_keep_alive().await;
+        let client = AggregatorClient::new(&aggregator.address()).unwrap();
+        for _ in 0..SENDS {
+            client
+                .send_signed_task_response(signed_response())
+                .await
+                .unwrap();
+        }
+        assert_eq!(aggregator.connections(), 1);
+
+        // Without idle connections every send has to connect again
+        let aggregator = MockAggregator::start_keep_alive().await;
+        let client = AggregatorClient::new(&aggregator.address())
+            .unwrap()
+            .with_pool_config(PoolConfig {
+                max_idle_connections: 0,
+                ..Default::default()
+            })
+            .unwrap();
+        for _ in 0..SENDS {
+            client
+                .send_signed_task_response(signed_response())
+                .await
+                .unwrap();
+        }
+        assert_eq!(aggregator.connections(), SENDS);
+    }
+
+    #[tokio::test]
+    async fn test_send_surfaces_rejection() {
+        let aggregator = MockAggregator::start(0, Some(false)).await;
+        let client = AggregatorClient::new(&aggregator.address()).unwrap();
+
+        let started = Instant::now();
+        let err = client
+            .send_signed_task_response(signed_response())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, AggregatorClientError::Rejected));
+        assert!(!err.is_retryable());
+        // A rejection is final, so it is never sent again
+        assert!(started.elapsed() < INITIAL_RETRY_DELAY);
+        assert_eq!(aggregator.connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_backoff_does_not_count_against_the_ack_timeout() {
+        let aggregator = MockAggregator::start(1, Some(true)).await;
+        let client = AggregatorClient::new(&aggregator.address())
+            .unwrap()
+            .with_reconnect_config(ReconnectConfig {
+                reconnect_attempts: 1,
+                base_delay: Duration::from_millis(300),
+                max_delay: Duration::from_millis(300),
+            })
+            .with_send_timeout(Duration::from_millis(200))
+            .with_max_attempts(1);
+
+        client
+            .send_signed_task_response(signed_response())
+            .await
+            .unwrap();
+        assert_eq!(aggregator.connections(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_multi_aggregator_succeeds_if_any_accepts() {
+        let rejecting = MockAggregator::start(0, Some(false)).await;
+        let accepting = MockAggregator::start(0, Some(true)).await;
+        let client = |aggregator: &MockAggregator| {
+            AggregatorClient::new(&aggregator.address())
+                .unwrap()
+                .with_max_attempts(1)
+        };
+        let multi = MultiAggregatorClient::from_clients([client(&rejecting), client(&accepting)]);
+
+        multi
+            .send_signed_task_response(signed_response())
+            .await
+            .unwrap();
+
+        assert_eq!(settled_deliveries(&multi, 1).await, vec![(0, 1), (1, 0)]);
+        assert_eq!(
+            multi.deliveries()[0].name,
+            format!("http://{}/", rejecting.address())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multi_aggregator_does_not_wait_for_the_slowest_aggregator() {
+        let hanging = Arc::new(test_support::MockAggregator::hanging_after(0));
+        let accepting = Arc::new(test_support::MockAggregator::default());
+        let multi = MultiAggregatorClient::new()
+            .with_target("hanging", hanging.clone())
+            .with_target("accepting", accepting.clone());
+
+        timeout(
+            Duration::from_secs(1),
+            multi.send_signed_task_response(signed_response()),
+        )
+        .await
+        .expect("the first acknowledgement is enough")
+        .unwrap();
+
+        assert_eq!(accepting.received().len(), 1);
+        assert!(hanging.received().is_empty());
+        let deliveries = multi.deliveries();
+        assert_eq!((deliveries[0].accepted, deliveries[0].failed), (0, 0));
+        assert_eq!((deliveries[1].accepted, deliveries[1].failed), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_multi_aggregator_fails_once_every_aggregator_failed() {
+        let rejecting = MockAggregator::start(0, Some(false)).await;
+        let client = AggregatorClient::new(&rejecting.address())
+            .unwrap()
+            .with_max_attempts(1);
+        let multi = MultiAggregatorClient::new()
+            .with_target("first", Arc::new(client.clone()))
+            .with_target("second", Arc::new(client));
+
+        let err = multi
+            .send_signed_task_response(signed_response())
+            .await
+            .unwrap_err();
+        let AggregatorClientError::AllAggregatorsFailed(failures) = &err else {
+            panic!("expected every aggregator to fail, got {:?}", err);
+        };
+        let names: Vec<_> = failures.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["first", "second"]);
+        assert!(!err.is_retryable());
+        assert!(multi.deliveries().iter().all(|d| d.failed == 1));
+
+        // Without any aggregator a response can never be delivered
+        assert!(MultiAggregatorClient::new()
+            .send_signed_task_response(signed_response())
+            .await
+            .is_err());
+    }
 }
@@ -0,0 +1,206 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+pub const HEALTH_PATH: &str = "/healthz";
+
+/// Largest request head the health server reads before giving up on a connection
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// Readiness of the aggregator as reported by `GET /healthz`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HealthStatus {
+    pub ready: bool,
+    /// The aggregator is running and not shutting down
+    pub up: bool,
+    /// The RPC endpoint answered
+    pub rpc_connected: bool,
+    /// The task manager address is set and has code deployed
+    pub task_manager_deployed: bool,
+}
+
+impl HealthStatus {
+    pub fn new(up: bool, rpc_connected: bool, task_manager_deployed: bool) -> Self {
+        Self {
+            ready: up && rpc_connected && task_manager_deployed,
+            up,
+            rpc_connected,
+            task_manager_deployed,
+        }
+    }
+}
+
+/// Serves `GET /healthz` on `listener` until `shutdown` resolves, answering 200 when `check`
+/// reports ready and 503 otherwise
+pub fn serve<F, Fut, S>(listener: TcpListener, check: F, shutdown: S) -> JoinHandle<()>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = HealthStatus> + Send,
+    S: Future<Output = ()> + Send + 'static,
+{
+    let check = Arc::new(check);
+    tokio::spawn(async move {
+        tokio::pin!(shutdown);
+        if let Ok(address) = listener.local_addr() {
+            info!("Health endpoint listening on {}{}", address, HEALTH_PATH);
+        }
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let check = Arc::clone(&check);
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_connection(stream, check.as_ref()).await {
+                                debug!("Health check connection failed: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => warn!("Failed to accept health check connection: {}", e),
+                },
+                _ = &mut shutdown => break,
+            }
+        }
+        info!("Health endpoint stopped");
+    })
+}
+
+/// Binds `address` and serves the health endpoint on it, see [`serve`]
+pub async fn bind<F, Fut, S>(
+    address: SocketAddr,
+    check: F,
+    shutdown: S,
+) -> std::io::Result<JoinHandle<()>>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = HealthStatus> + Send,
+    S: Future<Output = ()> + Send + 'static,
+{
+    let listener = TcpListener::bind(address).await?;
+    Ok(serve(listener, check, shutdown))
+}
+
+async fn handle_connection<F, Fut>(mut stream: TcpStream, check: &F) -> std::io::Result<()>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = HealthStatus>,
+{
+    let Some((method, path)) = read_request_line(&mut stream).await? else {
+        return Ok(());
+    };
+
+    let (status, body) = if method == "GET" && path == HEALTH_PATH {
+        let health = check().await;
+        let status = if health.ready {
+            "200 OK"
+        } else {
+            "503 Service Unavailable"
+        };
+        (status, serde_json::to_string(&health).unwrap_or_default())
+    } else {
+        ("404 Not Found", r#"{"error":"not found"}"#.to_string())
+    };
+
+    write_response(&mut stream, status, "application/json", &body).await
+}
+
+/// Reads the request head from `stream` and returns its method and path, or `None` if the
+/// connection closed or sent more than [`MAX_REQUEST_HEAD`] bytes first
+pub(crate) async fn read_request_line(
+    stream: &mut TcpStream,
+) -> std::io::Result<Option<(String, String)>> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || head.len() + n > MAX_REQUEST_HEAD {
+            return Ok(None);
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    Ok(Some((method, path)))
+}
+
+/// Writes a complete response and closes the connection
+pub(crate) async fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tokio::sync::oneshot;
+
+    async fn get(address: SocketAddr, path: &str) -> (u16, serde_json::Value) {
+        let response = reqwest::get(format!("http://{}{}", address, path))
+            .await
+            .unwrap();
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap();
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_health_endpoint_reports_readiness() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let rpc_connected = Arc::new(AtomicBool::new(false));
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+        let check = {
+            let rpc_connected = Arc::clone(&rpc_connected);
+            move || {
+                let rpc_connected = rpc_connected.load(Ordering::SeqCst);
+                async move { HealthStatus::new(true, rpc_connected, true) }
+            }
+        };
+        let handle = serve(listener, check, async move {
+            let _ = shutdown_rx.await;
+        });
+
+        let (status, body) = get(address, HEALTH_PATH).await;
+        assert_eq!(status, 503);
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["up"], true);
+        assert_eq!(body["rpc_connected"], false);
+
+        rpc_connected.store(true, Ordering::SeqCst);
+        let (status, body) = get(address, HEALTH_PATH).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["ready"], true);
+        assert_eq!(body["task_manager_deployed"], true);
+
+        let (status, _) = get(address, "/metrics").await;
+        assert_eq!(status, 404);
+
+        shutdown_tx.send(()).unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(5), handle)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
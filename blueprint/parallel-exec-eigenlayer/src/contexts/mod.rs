@@ -1,3 +1,13 @@
 pub mod aggregator;
+pub mod api_recordings;
 pub mod client;
+pub mod health;
+pub mod processed_tasks;
+pub mod quorum_status;
+pub mod response_codec;
+pub mod response_queue;
+pub mod schedule_artifacts;
+pub mod submission;
+pub mod task_config;
+pub mod task_limiter;
 pub mod x_square;
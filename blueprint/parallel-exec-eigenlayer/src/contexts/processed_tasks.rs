@@ -0,0 +1,322 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use alloy_primitives::B256;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::contexts::response_queue::unix_now;
+
+#[derive(Debug, Error)]
+pub enum ProcessedTasksError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// A task whose signed response was sent to the aggregator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskRecord {
+    pub task_index: u32,
+    pub result_hash: B256,
+    /// Seconds since the unix epoch
+    pub sent_at: u64,
+}
+
+/// Bounded set of the most recently processed task indices, shared between clones.
+///
+/// Used to avoid signing the same task twice when a `NewTaskCreated` event is delivered
+/// again after a reorg or listener replay. Once `capacity` tasks are tracked, the least
+/// recently seen one is forgotten.
+///
+/// When opened from a file, every task recorded with [`ProcessedTasks::record_sent`] is
+/// appended to it as a JSON line and claimed again on the next start, so a restarted operator
+/// does not sign and submit the same task a second time.
+#[derive(Debug, Clone)]
+pub struct ProcessedTasks {
+    capacity: usize,
+    inner: Arc<Mutex<Inner>>,
+    path: Option<Arc<PathBuf>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    order: VecDeque<u32>,
+    seen: HashSet<u32>,
+    records: HashMap<u32, TaskRecord>,
+    /// Lines in the file, which is compacted once they far outnumber the tracked records
+    stored_lines: usize,
+}
+
+impl Inner {
+    fn claim(&mut self, task_index: u32, capacity: usize) -> bool {
+        if self.seen.contains(&task_index) {
+            // Refresh the entry so a task that keeps being replayed is not evicted
+            if let Some(position) = self.order.iter().position(|&index| index == task_index) {
+                self.order.remove(position);
+            }
+            self.order.push_back(task_index);
+            return false;
+        }
+
+        if self.order.len() >= capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.seen.remove(&evicted);
+                self.records.remove(&evicted);
+            }
+        }
+        self.order.push_back(task_index);
+        self.seen.insert(task_index);
+        true
+    }
+
+    /// Records in the order their tasks were last seen
+    fn ordered_records(&self) -> impl Iterator<Item = &TaskRecord> {
+        self.order
+            .iter()
+            .filter_map(|task_index| self.records.get(task_index))
+    }
+}
+
+impl ProcessedTasks {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            inner: Arc::new(Mutex::new(Inner::default())),
+            path: None,
+        }
+    }
+
+    /// Opens the record of sent tasks stored at `path`, creating it if needed, and claims every
+    /// task in it. Unreadable lines are skipped.
+    pub fn open(path: impl Into<PathBuf>, capacity: usize) -> Result<Self, ProcessedTasksError> {
+        let path = path.into();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut processed = Self::new(capacity);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        {
+            let mut inner = processed.inner.lock();
+            for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+                match serde_json::from_str::<TaskRecord>(line) {
+                    Ok(record) => {
+                        inner.claim(record.task_index, processed.capacity);
+                        inner.records.insert(record.task_index, record);
+                    }
+                    Err(e) => warn!("Skipping unreadable processed task record: {}", e),
+                }
+            }
+            // Rewrite the file so it only holds the records that are still tracked
+            write_records(&path, &mut inner)?;
+        }
+
+        processed.path = Some(Arc::new(path));
+        Ok(processed)
+    }
+
+    /// Returns the file sent tasks are recorded in, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref().map(PathBuf::as_path)
+    }
+
+    /// Records `task_index` as processed, returning `false` if it already was
+    pub fn claim(&self, task_index: u32) -> bool {
+        self.inner.lock().claim(task_index, self.capacity)
+    }
+
+    /// Remembers that the response for `task_index` was sent, persisting it when opened from
+    /// a file
+    pub fn record_sent(
+        &self,
+        task_index: u32,
+        result_hash: B256,
+    ) -> Result<(), ProcessedTasksError> {
+        let record = TaskRecord {
+            task_index,
+            result_hash,
+            sent_at: unix_now(),
+        };
+
+        let mut inner = self.inner.lock();
+        inner.claim(task_index, self.capacity);
+        inner.records.insert(task_index, record);
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if inner.stored_lines >= 2 * self.capacity {
+            return write_records(path, &mut inner);
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.as_path())?;
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        inner.stored_lines += 1;
+        Ok(())
+    }
+
+    /// Returns the record of `task_index`, if its response was sent
+    pub fn record(&self, task_index: u32) -> Option<TaskRecord> {
+        self.inner.lock().records.get(&task_index).copied()
+    }
+
+    /// Forgets `task_index`, so that a redelivered event for it is processed again
+    pub fn release(&self, task_index: u32) {
+        let mut inner = self.inner.lock();
+        if inner.seen.remove(&task_index) {
+            inner.order.retain(|&index| index != task_index);
+        }
+        if inner.records.remove(&task_index).is_some() {
+            if let Some(path) = &self.path {
+                if let Err(e) = write_records(path, &mut inner) {
+                    warn!("Failed to forget processed task {}: {}", task_index, e);
+                }
+            }
+        }
+    }
+
+    pub fn contains(&self, task_index: u32) -> bool {
+        self.inner.lock().seen.contains(&task_index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Replaces the file at `path` with the tracked records
+fn write_records(path: &Path, inner: &mut Inner) -> Result<(), ProcessedTasksError> {
+    let mut contents = Vec::new();
+    let mut lines = 0;
+    for record in inner.ordered_records() {
+        serde_json::to_writer(&mut contents, record)?;
+        contents.push(b'\n');
+        lines += 1;
+    }
+
+    // Write to a temporary file first so a crash never leaves a truncated record behind
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, path)?;
+    inner.stored_lines = lines;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_task_is_only_claimed_once() {
+        let processed = ProcessedTasks::new(8);
+        let shared = processed.clone();
+
+        assert!(processed.claim(42));
+        assert!(!shared.claim(42));
+        assert!(!processed.claim(42));
+        assert_eq!(processed.len(), 1);
+    }
+
+    #[test]
+    fn test_least_recently_seen_task_is_evicted() {
+        let processed = ProcessedTasks::new(2);
+
+        assert!(processed.claim(1));
+        assert!(processed.claim(2));
+        // Seeing 1 again makes 2 the least recently seen task
+        assert!(!processed.claim(1));
+        assert!(processed.claim(3));
+
+        assert!(processed.contains(1));
+        assert!(!processed.contains(2));
+        assert!(processed.contains(3));
+        assert_eq!(processed.len(), 2);
+    }
+
+    #[test]
+    fn test_released_task_can_be_claimed_again() {
+        let processed = ProcessedTasks::new(2);
+
+        assert!(processed.claim(7));
+        processed.release(7);
+        assert!(processed.is_empty());
+        assert!(processed.claim(7));
+    }
+
+    #[test]
+    fn test_sent_tasks_are_written_and_reloaded() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("processed-tasks.jsonl");
+
+        let processed = ProcessedTasks::open(&path, 8).unwrap();
+        assert!(processed.claim(1));
+        processed.record_sent(1, B256::repeat_byte(1)).unwrap();
+        assert!(processed.claim(2));
+        processed.record_sent(2, B256::repeat_byte(2)).unwrap();
+        // Claimed but never sent, so it is not persisted
+        assert!(processed.claim(3));
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        drop(processed);
+
+        let reopened = ProcessedTasks::open(&path, 8).unwrap();
+        assert_eq!(reopened.len(), 2);
+        let record = reopened.record(2).unwrap();
+        assert_eq!(record.result_hash, B256::repeat_byte(2));
+        assert!(record.sent_at > 0);
+        assert!(reopened.record(3).is_none());
+    }
+
+    #[test]
+    fn test_sent_tasks_are_not_claimed_again_after_restart() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("processed-tasks.jsonl");
+
+        let processed = ProcessedTasks::open(&path, 8).unwrap();
+        assert!(processed.claim(7));
+        processed.record_sent(7, B256::ZERO).unwrap();
+        drop(processed);
+
+        let restarted = ProcessedTasks::open(&path, 8).unwrap();
+        assert!(!restarted.claim(7));
+        assert!(restarted.claim(8));
+    }
+
+    #[test]
+    fn test_reload_skips_corrupt_lines_and_compacts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("processed-tasks.jsonl");
+
+        let processed = ProcessedTasks::open(&path, 2).unwrap();
+        for task_index in 0..4 {
+            processed.record_sent(task_index, B256::ZERO).unwrap();
+        }
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"not json\n").unwrap();
+        drop(file);
+
+        // Only the two most recently sent tasks are kept
+        let reopened = ProcessedTasks::open(&path, 2).unwrap();
+        assert!(!reopened.contains(1));
+        assert!(reopened.contains(2) && reopened.contains(3));
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+    }
+}
@@ -0,0 +1,493 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use alloy_primitives::U256;
+use eigensdk::crypto_bls::OperatorId;
+use eigensdk::types::avs::TaskIndex;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+use crate::contexts::task_config::TaskParams;
+
+/// Where a task stands in collecting signatures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuorumState {
+    /// Signatures are still being collected
+    Collecting,
+    /// Every quorum reached its threshold
+    Reached,
+    /// The task's quorum window closed, or the task expired, before every quorum reached its
+    /// threshold
+    Failed,
+}
+
+/// Stake of the operators registered in a task's quorums, at the block the task was created
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuorumStakes {
+    totals: BTreeMap<u8, U256>,
+    operators: HashMap<OperatorId, BTreeMap<u8, U256>>,
+}
+
+impl QuorumStakes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `stake` of `operator_id` in `quorum_number`, adding it to the quorum's total
+    pub fn with_operator(
+        mut self,
+        operator_id: OperatorId,
+        quorum_number: u8,
+        stake: U256,
+    ) -> Self {
+        *self.totals.entry(quorum_number).or_default() += stake;
+        *self
+            .operators
+            .entry(operator_id)
+            .or_default()
+            .entry(quorum_number)
+            .or_default() += stake;
+        self
+    }
+
+    /// Returns true if no stake is known, e.g. because the registry could not be read
+    pub fn is_empty(&self) -> bool {
+        self.totals.is_empty()
+    }
+}
+
+/// Stake a single quorum has signed a task with, against its threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct QuorumProgress {
+    pub quorum_number: u8,
+    pub threshold_percentage: u8,
+    pub signed_stake: U256,
+    pub total_stake: U256,
+}
+
+impl QuorumProgress {
+    /// Returns the signed share of the quorum's stake in whole percent, rounded down, or
+    /// `None` if the quorum's stake is unknown
+    pub fn signed_percentage(&self) -> Option<u8> {
+        if self.total_stake.is_zero() {
+            return None;
+        }
+        let percentage = self.signed_stake * U256::from(100) / self.total_stake;
+        Some(percentage.saturating_to::<u64>().min(100) as u8)
+    }
+
+    /// Returns true if the signed stake reaches the threshold, the same check the BLS
+    /// aggregation service applies
+    pub fn is_met(&self) -> bool {
+        !self.total_stake.is_zero()
+            && self.signed_stake * U256::from(100)
+                >= self.total_stake * U256::from(self.threshold_percentage)
+    }
+}
+
+/// Signatures and stake collected for a task, as returned by
+/// [`AggregatorContext::quorum_status`](crate::contexts::aggregator::AggregatorContext::quorum_status)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuorumStatus {
+    pub task_index: TaskIndex,
+    pub state: QuorumState,
+    /// Number of distinct operators whose signature was processed
+    pub signatures: usize,
+    pub quorums: Vec<QuorumProgress>,
+}
+
+impl fmt::Display for QuorumStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state {
+            QuorumState::Collecting => "collecting",
+            QuorumState::Reached => "reached",
+            QuorumState::Failed => "failed",
+        };
+        write!(
+            f,
+            "task {}: quorum {}, {} signature(s)",
+            self.task_index, state, self.signatures
+        )?;
+        for quorum in &self.quorums {
+            match quorum.signed_percentage() {
+                Some(percentage) => write!(
+                    f,
+                    ", quorum {} signed {}% of {}% required",
+                    quorum.quorum_number, percentage, quorum.threshold_percentage
+                )?,
+                None => write!(
+                    f,
+                    ", quorum {} stake unknown, {}% required",
+                    quorum.quorum_number, quorum.threshold_percentage
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct TrackedTask {
+    status: QuorumStatus,
+    stakes: QuorumStakes,
+    signers: HashSet<OperatorId>,
+    quorum_window: Option<Duration>,
+    /// When the task is finalized, the earlier of its expiry and the end of its quorum window
+    deadline: Instant,
+}
+
+/// Tracks how close every task is to its quorum thresholds, so the aggregator can report
+/// tasks whose window closes before enough operators signed.
+///
+/// Clones share the tracked tasks.
+#[derive(Debug, Clone, Default)]
+pub struct QuorumTracker {
+    tasks: Arc<Mutex<HashMap<TaskIndex, TrackedTask>>>,
+    deadline_changed: Arc<Notify>,
+}
+
+impl QuorumTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking a task initialized with `params` at `now`, replacing an earlier entry
+    /// for the same task
+    pub fn start(
+        &self,
+        task_index: TaskIndex,
+        params: &TaskParams,
+        stakes: QuorumStakes,
+        now: Instant,
+    ) {
+        let quorums = params
+            .quorum_numbers
+            .iter()
+            .zip(&params.quorum_threshold_percentages)
+            .map(|(&quorum_number, &threshold_percentage)| QuorumProgress {
+                quorum_number,
+                threshold_percentage,
+                signed_stake: U256::ZERO,
+                total_stake: stakes
+                    .totals
+                    .get(&quorum_number)
+                    .copied()
+                    .unwrap_or_default(),
+            })
+            .collect();
+        self.tasks.lock().insert(
+            task_index,
+            TrackedTask {
+                status: QuorumStatus {
+                    task_index,
+                    state: QuorumState::Collecting,
+                    signatures: 0,
+                    quorums,
+                },
+                stakes,
+                signers: HashSet::new(),
+                quorum_window: params.quorum_window,
+                deadline: now + params.time_to_expiry,
+            },
+        );
+        self.deadline_changed.notify_one();
+    }
+
+    /// Adds the stake of `operator_id` to the task's quorums, once per operator, and returns
+    /// the task's status or `None` if the task is not tracked.
+    ///
+    /// The first signature, received at `now`, opens the task's quorum window.
+    pub fn record_signature(
+        &self,
+        task_index: TaskIndex,
+        operator_id: OperatorId,
+        now: Instant,
+    ) -> Option<QuorumStatus> {
+        let mut tasks = self.tasks.lock();
+        let task = tasks.get_mut(&task_index)?;
+        if task.signers.is_empty() {
+            if let Some(window) = task.quorum_window {
+                task.deadline = task.deadline.min(now + window);
+                self.deadline_changed.notify_one();
+            }
+        }
+        if task.signers.insert(operator_id) {
+            task.status.signatures += 1;
+            if let Some(stake) = task.stakes.operators.get(&operator_id) {
+                for quorum in &mut task.status.quorums {
+                    quorum.signed_stake += stake
+                        .get(&quorum.quorum_number)
+                        .copied()
+                        .unwrap_or_default();
+                }
+            }
+            if task.status.state == QuorumState::Collecting
+                && task.status.quorums.iter().all(QuorumProgress::is_met)
+            {
+                task.status.state = QuorumState::Reached;
+            }
+        }
+        Some(task.status.clone())
+    }
+
+    /// Returns true if a signature of `operator_id` was already recorded for the task
+    pub fn has_signed(&self, task_index: TaskIndex, operator_id: OperatorId) -> bool {
+        self.tasks
+            .lock()
+            .get(&task_index)
+            .is_some_and(|task| task.signers.contains(&operator_id))
+    }
+
+    /// Marks the task as having reached its quorum, e.g. once the BLS aggregation service
+    /// produced an aggregate for it even though its stake is unknown here.
+    ///
+    /// Returns false, leaving the task as it is, if the task already failed at its deadline
+    /// or is not tracked, in which case its aggregate must not be submitted.
+    pub fn mark_reached(&self, task_index: TaskIndex) -> bool {
+        let mut tasks = self.tasks.lock();
+        match tasks.get_mut(&task_index) {
+            Some(task) if task.status.state != QuorumState::Failed => {
+                task.status.state = QuorumState::Reached;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the status of the task, or `None` if it is not tracked
+    pub fn status(&self, task_index: TaskIndex) -> Option<QuorumStatus> {
+        self.tasks
+            .lock()
+            .get(&task_index)
+            .map(|task| task.status.clone())
+    }
+
+    /// Returns when the task is finalized, or `None` if it is not tracked
+    pub fn deadline(&self, task_index: TaskIndex) -> Option<Instant> {
+        self.tasks.lock().get(&task_index).map(|task| task.deadline)
+    }
+
+    /// Returns the earliest deadline of the tasks still collecting signatures
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.tasks
+            .lock()
+            .values()
+            .filter(|task| task.status.state == QuorumState::Collecting)
+            .map(|task| task.deadline)
+            .min()
+    }
+
+    /// Resolves once a deadline may have moved, since a task was started or received its
+    /// first signature. A change made while nobody waits wakes the next caller.
+    pub async fn deadline_changed(&self) {
+        self.deadline_changed.notified().await
+    }
+
+    /// Fails every task still collecting signatures whose deadline passed by `now`, returning
+    /// their statuses. Each task is only returned once.
+    pub fn expire(&self, now: Instant) -> Vec<QuorumStatus> {
+        let mut failed: Vec<QuorumStatus> = self
+            .tasks
+            .lock()
+            .values_mut()
+            .filter(|task| task.status.state == QuorumState::Collecting && task.deadline <= now)
+            .map(|task| {
+                task.status.state = QuorumState::Failed;
+                task.status.clone()
+            })
+            .collect();
+        failed.sort_by_key(|status| status.task_index);
+        failed
+    }
+
+    /// Stops tracking the tasks that reached or failed their quorum and whose deadline passed
+    /// at least `retention` before `now`, returning how many were dropped
+    pub fn prune(&self, now: Instant, retention: Duration) -> usize {
+        let mut tasks = self.tasks.lock();
+        let tracked = tasks.len();
+        tasks.retain(|_, task| {
+            task.status.state == QuorumState::Collecting || now < task.deadline + retention
+        });
+        tracked - tasks.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operator(byte: u8) -> OperatorId {
+        OperatorId::repeat_byte(byte)
+    }
+
+    /// Quorum 0 with three operators of 40, 30 and 30 stake
+    fn stakes() -> QuorumStakes {
+        QuorumStakes::new()
+            .with_operator(operator(1), 0, U256::from(40))
+            .with_operator(operator(2), 0, U256::from(30))
+            .with_operator(operator(3), 0, U256::from(30))
+    }
+
+    fn params(threshold_percentage: u8) -> TaskParams {
+        TaskParams {
+            quorum_numbers: vec![0],
+            quorum_threshold_percentages: vec![threshold_percentage],
+            time_to_expiry: Duration::from_secs(60),
+            quorum_window: None,
+        }
+    }
+
+    #[test]
+    fn test_quorum_is_reached_at_threshold() {
+        let tracker = QuorumTracker::new();
+        let now = Instant::now();
+        tracker.start(7, &params(67), stakes(), now);
+
+        let status = tracker.record_signature(7, operator(1), now).unwrap();
+        assert_eq!(status.state, QuorumState::Collecting);
+        assert_eq!(status.quorums[0].signed_percentage(), Some(40));
+
+        // The same operator signing again adds nothing
+        assert!(tracker.has_signed(7, operator(1)));
+        assert!(!tracker.has_signed(7, operator(2)));
+        let status = tracker.record_signature(7, operator(1), now).unwrap();
+        assert_eq!(status.signatures, 1);
+        assert_eq!(status.quorums[0].signed_stake, U256::from(40));
+
+        let status = tracker.record_signature(7, operator(2), now).unwrap();
+        assert_eq!(status.state, QuorumState::Reached);
+        assert_eq!(status.quorums[0].signed_percentage(), Some(70));
+
+        // A task that reached its quorum never fails
+        assert!(tracker.expire(now + Duration::from_secs(61)).is_empty());
+        assert!(tracker.record_signature(8, operator(1), now).is_none());
+    }
+
+    #[test]
+    fn test_insufficient_signatures_fail_the_quorum() {
+        let tracker = QuorumTracker::new();
+        let now = Instant::now();
+        tracker.start(7, &params(67), stakes(), now);
+        tracker.start(8, &params(67), stakes(), now + Duration::from_secs(30));
+        tracker.record_signature(7, operator(2), now);
+        tracker.record_signature(7, operator(3), now);
+
+        // Still within the window
+        assert!(tracker.expire(now + Duration::from_secs(59)).is_empty());
+        assert_eq!(tracker.status(7).unwrap().state, QuorumState::Collecting);
+
+        let failed = tracker.expire(now + Duration::from_secs(60));
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].task_index, 7);
+        assert_eq!(failed[0].state, QuorumState::Failed);
+        assert_eq!(failed[0].signatures, 2);
+        assert_eq!(failed[0].quorums[0].signed_percentage(), Some(60));
+        assert!(!failed[0].quorums[0].is_met());
+        assert_eq!(
+            failed[0].to_string(),
+            "task 7: quorum failed, 2 signature(s), quorum 0 signed 60% of 67% required"
+        );
+        assert_eq!(tracker.status(7).unwrap().state, QuorumState::Failed);
+
+        // Reported once, while the later task is still collecting
+        assert!(tracker.expire(now + Duration::from_secs(61)).is_empty());
+        assert_eq!(tracker.status(8).unwrap().state, QuorumState::Collecting);
+    }
+
+    #[test]
+    fn test_quorum_window_opens_at_the_first_signature() {
+        let tracker = QuorumTracker::new();
+        let now = Instant::now();
+        let windowed = TaskParams {
+            quorum_window: Some(Duration::from_secs(10)),
+            ..params(67)
+        };
+        tracker.start(7, &windowed, stakes(), now);
+        tracker.start(8, &windowed, stakes(), now);
+        assert_eq!(tracker.deadline(7), Some(now + Duration::from_secs(60)));
+
+        let first_signature = now + Duration::from_secs(5);
+        tracker.record_signature(7, operator(1), first_signature);
+        tracker.record_signature(7, operator(2), now + Duration::from_secs(8));
+        tracker.record_signature(8, operator(1), now + Duration::from_secs(55));
+        assert_eq!(tracker.deadline(7), Some(now + Duration::from_secs(15)));
+        // The window never outlasts the task
+        assert_eq!(tracker.deadline(8), Some(now + Duration::from_secs(60)));
+        assert_eq!(tracker.next_deadline(), Some(now + Duration::from_secs(15)));
+
+        // Task 7 reached its quorum within the window, so it is not failed at its deadline
+        assert_eq!(tracker.status(7).unwrap().state, QuorumState::Reached);
+        assert!(tracker.expire(now + Duration::from_secs(15)).is_empty());
+        assert_eq!(tracker.next_deadline(), Some(now + Duration::from_secs(60)));
+
+        tracker.start(9, &windowed, stakes(), now);
+        tracker.record_signature(9, operator(3), first_signature);
+        assert!(tracker.expire(now + Duration::from_secs(14)).is_empty());
+        let failed = tracker.expire(now + Duration::from_secs(15));
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].task_index, 9);
+    }
+
+    #[test]
+    fn test_unknown_stake_waits_for_the_aggregation_service() {
+        let tracker = QuorumTracker::new();
+        let now = Instant::now();
+        tracker.start(7, &params(67), QuorumStakes::new(), now);
+
+        let status = tracker.record_signature(7, operator(1), now).unwrap();
+        assert_eq!(status.state, QuorumState::Collecting);
+        assert_eq!(status.quorums[0].signed_percentage(), None);
+        assert_eq!(
+            status.to_string(),
+            "task 7: quorum collecting, 1 signature(s), quorum 0 stake unknown, 67% required"
+        );
+
+        assert!(tracker.mark_reached(7));
+        assert_eq!(tracker.status(7).unwrap().state, QuorumState::Reached);
+        assert!(tracker.expire(now + Duration::from_secs(60)).is_empty());
+    }
+
+    #[test]
+    fn test_failed_task_is_never_marked_reached() {
+        let tracker = QuorumTracker::new();
+        let now = Instant::now();
+        tracker.start(7, &params(67), QuorumStakes::new(), now);
+        tracker.record_signature(7, operator(1), now);
+        assert_eq!(tracker.expire(now + Duration::from_secs(60)).len(), 1);
+
+        // An aggregate arriving after the deadline does not turn the failure into a success
+        assert!(!tracker.mark_reached(7));
+        assert_eq!(tracker.status(7).unwrap().state, QuorumState::Failed);
+        assert!(!tracker.mark_reached(8));
+    }
+
+    #[test]
+    fn test_finished_tasks_are_pruned_after_the_retention() {
+        let tracker = QuorumTracker::new();
+        let now = Instant::now();
+        let retention = Duration::from_secs(30);
+        tracker.start(7, &params(67), stakes(), now);
+        tracker.start(8, &params(67), stakes(), now);
+        tracker.start(9, &params(67), stakes(), now + Duration::from_secs(60));
+        tracker.record_signature(7, operator(1), now);
+        tracker.record_signature(7, operator(2), now);
+        assert_eq!(tracker.expire(now + Duration::from_secs(60)).len(), 1);
+
+        // Finished tasks stay queryable within the retention
+        assert_eq!(tracker.prune(now + Duration::from_secs(89), retention), 0);
+        assert_eq!(tracker.status(7).unwrap().state, QuorumState::Reached);
+        assert_eq!(tracker.status(8).unwrap().state, QuorumState::Failed);
+
+        assert_eq!(tracker.prune(now + Duration::from_secs(90), retention), 2);
+        assert!(tracker.status(7).is_none());
+        assert!(tracker.status(8).is_none());
+
+        // A task still collecting signatures is kept until it is finalized
+        assert_eq!(tracker.prune(now + Duration::from_secs(300), retention), 0);
+        assert_eq!(tracker.status(9).unwrap().state, QuorumState::Collecting);
+    }
+}
@@ -0,0 +1,258 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use alloy_primitives::{Bytes, U256};
+use alloy_sol_types::{sol, SolValue};
+use ark_bn254::{Fq, G1Affine};
+use ark_ec::AffineRepr;
+use ark_ff::{BigInt, PrimeField};
+use eigensdk::crypto_bls::{convert_to_g1_point, Signature};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::contexts::client::SignedTaskResponse;
+use crate::IIncredibleSquaringTaskManager::TaskResponse;
+
+#[derive(Debug, Error)]
+pub enum ResponseCodecError {
+    #[error("Invalid JSON task response: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid ABI encoded task response: {0}")]
+    Abi(String),
+    #[error("Unknown response format {0:?}, expected `json` or `abi`")]
+    UnknownFormat(String),
+}
+
+/// Encodes signed task responses into the `params` of the aggregator's
+/// `process_signed_task_response` call, and decodes them on the aggregator's side.
+///
+/// Operators and the aggregator have to use the same codec. Implement it to talk to an
+/// aggregator expecting another wire format.
+pub trait ResponseCodec: fmt::Debug + Send + Sync {
+    fn encode(&self, response: &SignedTaskResponse) -> Result<Value, ResponseCodecError>;
+
+    fn decode(&self, params: &Value) -> Result<SignedTaskResponse, ResponseCodecError>;
+}
+
+/// The response as a JSON object, as the bundled aggregator has always accepted it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl ResponseCodec for JsonCodec {
+    fn encode(&self, response: &SignedTaskResponse) -> Result<Value, ResponseCodecError> {
+        Ok(serde_json::to_value(response)?)
+    }
+
+    fn decode(&self, params: &Value) -> Result<SignedTaskResponse, ResponseCodecError> {
+        Ok(serde_json::from_value(params.clone())?)
+    }
+}
+
+sol! {
+    /// Layout of a response encoded by [`AbiCodec`]
+    struct AbiSignedTaskResponse {
+        uint32 referenceTaskIndex;
+        bytes32 resultHash;
+        uint256 signatureX;
+        uint256 signatureY;
+        bytes32 operatorId;
+        bool hasQuorumNumber;
+        uint8 quorumNumber;
+    }
+}
+
+/// The response ABI encoded as `AbiSignedTaskResponse` and sent as a `0x` prefixed hex
+/// string, for aggregators written against Solidity types.
+///
+/// The signature is the affine G1 point, `(0, 0)` for the point at infinity as in the
+/// contracts' `BN254` library.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AbiCodec;
+
+impl ResponseCodec for AbiCodec {
+    fn encode(&self, response: &SignedTaskResponse) -> Result<Value, ResponseCodecError> {
+        let signature = convert_to_g1_point(response.signature.g1_point().g1())
+            .map_err(|e| ResponseCodecError::Abi(e.to_string()))?;
+        let encoded = AbiSignedTaskResponse {
+            referenceTaskIndex: response.task_response.referenceTaskIndex,
+            resultHash: response.task_response.resultHash,
+            signatureX: signature.X,
+            signatureY: signature.Y,
+            operatorId: response.operator_id,
+            hasQuorumNumber: response.quorum_number.is_some(),
+            quorumNumber: response.quorum_number.unwrap_or_default(),
+        }
+        .abi_encode();
+        Ok(Value::String(Bytes::from(encoded).to_string()))
+    }
+
+    fn decode(&self, params: &Value) -> Result<SignedTaskResponse, ResponseCodecError> {
+        let hex = params
+            .as_str()
+            .ok_or_else(|| ResponseCodecError::Abi("expected a hex string".to_string()))?;
+        let bytes = Bytes::from_str(hex).map_err(|e| ResponseCodecError::Abi(e.to_string()))?;
+        let decoded = AbiSignedTaskResponse::abi_decode(&bytes, true)
+            .map_err(|e| ResponseCodecError::Abi(e.to_string()))?;
+
+        Ok(SignedTaskResponse {
+            task_response: TaskResponse {
+                referenceTaskIndex: decoded.referenceTaskIndex,
+                resultHash: decoded.resultHash,
+            },
+            signature: Signature::new(g1_point(decoded.signatureX, decoded.signatureY)?),
+            operator_id: decoded.operatorId,
+            quorum_number: decoded.hasQuorumNumber.then_some(decoded.quorumNumber),
+        })
+    }
+}
+
+/// Parses an affine G1 point, rejecting points off the curve
+fn g1_point(x: U256, y: U256) -> Result<G1Affine, ResponseCodecError> {
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1Affine::zero());
+    }
+    let coordinate = |value: U256| {
+        Fq::from_bigint(BigInt::new(value.into_limbs())).ok_or_else(|| {
+            ResponseCodecError::Abi(format!("signature coordinate {} is out of range", value))
+        })
+    };
+    let point = G1Affine::new_unchecked(coordinate(x)?, coordinate(y)?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(ResponseCodecError::Abi(
+            "signature is not a point on BN254".to_string(),
+        ));
+    }
+    Ok(point)
+}
+
+/// Wire format of signed task responses, selected by configuration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseFormat {
+    /// [`JsonCodec`]
+    #[default]
+    Json,
+    /// [`AbiCodec`]
+    Abi,
+}
+
+impl ResponseFormat {
+    /// Returns the codec of this format
+    pub fn codec(self) -> Arc<dyn ResponseCodec> {
+        match self {
+            ResponseFormat::Json => Arc::new(JsonCodec),
+            ResponseFormat::Abi => Arc::new(AbiCodec),
+        }
+    }
+}
+
+impl FromStr for ResponseFormat {
+    type Err = ResponseCodecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(ResponseFormat::Json),
+            "abi" => Ok(ResponseFormat::Abi),
+            _ => Err(ResponseCodecError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for ResponseFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResponseFormat::Json => f.write_str("json"),
+            ResponseFormat::Abi => f.write_str("abi"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::B256;
+    use eigensdk::crypto_bls::{BlsKeyPair, OperatorId};
+
+    fn signed_response(quorum_number: Option<u8>) -> SignedTaskResponse {
+        let key_pair = BlsKeyPair::new("7".to_string()).unwrap();
+        SignedTaskResponse {
+            task_response: TaskResponse {
+                referenceTaskIndex: 42,
+                resultHash: B256::repeat_byte(0xab),
+            },
+            signature: key_pair.sign_message(B256::repeat_byte(0xcd).as_slice()),
+            operator_id: OperatorId::repeat_byte(0x11),
+            quorum_number,
+        }
+    }
+
+    fn assert_round_trips(codec: &dyn ResponseCodec) {
+        for response in [
+            signed_response(None),
+            signed_response(Some(0)),
+            signed_response(Some(3)),
+        ] {
+            let encoded = codec.encode(&response).unwrap();
+            assert_eq!(codec.decode(&encoded).unwrap(), response);
+        }
+    }
+
+    #[test]
+    fn test_json_codec_round_trip() {
+        assert_round_trips(&JsonCodec);
+
+        // The object the aggregator has always accepted
+        let encoded = JsonCodec.encode(&signed_response(Some(3))).unwrap();
+        assert_eq!(encoded["task_response"]["referenceTaskIndex"], 42);
+        assert_eq!(encoded["quorum_number"], 3);
+        assert!(JsonCodec
+            .decode(&Value::String("0x00".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_abi_codec_round_trip() {
+        assert_round_trips(&AbiCodec);
+
+        let encoded = AbiCodec.encode(&signed_response(None)).unwrap();
+        let hex = encoded.as_str().unwrap();
+        // Seven static words
+        assert_eq!(hex.len(), 2 + 7 * 64);
+
+        // Anything but a valid point on the curve is rejected
+        let mut off_curve = Bytes::from_str(hex).unwrap().to_vec();
+        off_curve[3 * 32 - 1] ^= 1;
+        assert!(matches!(
+            AbiCodec.decode(&Value::String(Bytes::from(off_curve).to_string())),
+            Err(ResponseCodecError::Abi(_))
+        ));
+        assert!(AbiCodec
+            .decode(&Value::String("0x1234".to_string()))
+            .is_err());
+        assert!(AbiCodec.decode(&serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn test_response_format_from_config() {
+        assert_eq!(ResponseFormat::default(), ResponseFormat::Json);
+        assert_eq!(
+            "ABI".parse::<ResponseFormat>().unwrap(),
+            ResponseFormat::Abi
+        );
+        assert_eq!(
+            " json".parse::<ResponseFormat>().unwrap(),
+            ResponseFormat::Json
+        );
+        assert!(matches!(
+            "bincode".parse::<ResponseFormat>(),
+            Err(ResponseCodecError::UnknownFormat(_))
+        ));
+
+        // Each format's codec decodes what it encodes, but not the other format
+        let response = signed_response(Some(1));
+        let json = ResponseFormat::Json.codec();
+        let abi = ResponseFormat::Abi.codec();
+        assert!(abi.decode(&json.encode(&response).unwrap()).is_err());
+        assert!(json.decode(&abi.encode(&response).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,293 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tokio::time::interval;
+use tracing::{debug, info, warn};
+
+use crate::contexts::client::{AggregatorClient, SignedTaskResponse};
+
+#[derive(Debug, Error)]
+pub enum ResponseQueueError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// A signed task response waiting to be delivered, along with when it was queued
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedResponse {
+    /// Seconds since the unix epoch
+    pub queued_at: u64,
+    pub response: SignedTaskResponse,
+}
+
+/// On-disk queue of signed task responses the aggregator has not acknowledged yet.
+///
+/// Every response is stored as `<task_index>.json`, so queueing the same task twice keeps
+/// only the latest response. Responses older than `max_age` are discarded instead of sent.
+#[derive(Debug, Clone)]
+pub struct ResponseQueue {
+    dir: PathBuf,
+    max_age: Duration,
+}
+
+impl ResponseQueue {
+    /// Opens the queue stored in `dir`, creating the directory if needed
+    pub fn open(dir: impl Into<PathBuf>, max_age: Duration) -> Result<Self, ResponseQueueError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, max_age })
+    }
+
+    /// Returns the directory the queue is stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_for(&self, task_index: u32) -> PathBuf {
+        self.dir.join(format!("{}.json", task_index))
+    }
+
+    /// Stores a response for later delivery, replacing any queued response for the same task
+    pub fn push(&self, response: &SignedTaskResponse) -> Result<(), ResponseQueueError> {
+        self.push_at(response, unix_now())
+    }
+
+    fn push_at(
+        &self,
+        response: &SignedTaskResponse,
+        queued_at: u64,
+    ) -> Result<(), ResponseQueueError> {
+        let task_index = response.task_response.referenceTaskIndex;
+        let entry = QueuedResponse {
+            queued_at,
+            response: response.clone(),
+        };
+
+        // Write to a temporary file first so a crash never leaves a truncated entry behind
+        let path = self.path_for(task_index);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec(&entry)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        debug!("Queued signed task response for task index {}", task_index);
+        Ok(())
+    }
+
+    /// Removes the queued response for `task_index`, if any
+    pub fn remove(&self, task_index: u32) -> Result<(), ResponseQueueError> {
+        match fs::remove_file(self.path_for(task_index)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the queued responses ordered by task index, discarding expired and unreadable entries
+    pub fn pending(&self) -> Result<Vec<QueuedResponse>, ResponseQueueError> {
+        let now = unix_now();
+        let mut pending = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            let queued: QueuedResponse = match fs::read(&path)
+                .map_err(ResponseQueueError::from)
+                .and_then(|bytes| serde_json::from_slice(&bytes).map_err(ResponseQueueError::from))
+            {
+                Ok(queued) => queued,
+                Err(e) => {
+                    warn!(
+                        "Discarding unreadable queued response {}: {}",
+                        path.display(),
+                        e
+                    );
+                    fs::remove_file(&path)?;
+                    continue;
+                }
+            };
+
+            if now.saturating_sub(queued.queued_at) > self.max_age.as_secs() {
+                warn!(
+                    "Discarding expired signed task response for task index {}",
+                    queued.response.task_response.referenceTaskIndex
+                );
+                fs::remove_file(&path)?;
+                continue;
+            }
+
+            pending.push(queued);
+        }
+
+        pending.sort_by_key(|queued| queued.response.task_response.referenceTaskIndex);
+        Ok(pending)
+    }
+
+    /// Tries to deliver every queued response, returning how many were delivered.
+    ///
+    /// Draining stops at the first retryable failure since the aggregator is most likely
+    /// still unreachable, responses the aggregator rejects outright are dropped.
+    pub async fn drain(&self, client: &AggregatorClient) -> Result<usize, ResponseQueueError> {
+        let mut delivered = 0;
+        for queued in self.pending()? {
+            let task_index = queued.response.task_response.referenceTaskIndex;
+            match client.send_signed_task_response(queued.response).await {
+                Ok(()) => {
+                    info!(
+                        "Delivered queued signed task response for task index {}",
+                        task_index
+                    );
+                    self.remove(task_index)?;
+                    delivered += 1;
+                }
+                Err(e) if e.is_retryable() => {
+                    debug!(
+                        "Aggregator still unavailable, keeping queued responses: {}",
+                        e
+                    );
+                    break;
+                }
+                Err(e) => {
+                    warn!(
+                        "Dropping queued signed task response for task index {}: {}",
+                        task_index, e
+                    );
+                    self.remove(task_index)?;
+                }
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// Spawns a background task draining the queue every `period`
+    pub fn spawn_drain(self, client: AggregatorClient, period: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = interval(period);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.drain(&client).await {
+                    warn!("Failed to drain response queue: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Returns the current time in seconds since the unix epoch
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contexts::client::ReconnectConfig;
+    use crate::IIncredibleSquaringTaskManager::TaskResponse;
+    use alloy_primitives::B256;
+    use eigensdk::crypto_bls::{BlsKeyPair, OperatorId};
+
+    fn signed_response(task_index: u32, result_hash: B256) -> SignedTaskResponse {
+        let key_pair = BlsKeyPair::new("1".to_string()).unwrap();
+        SignedTaskResponse {
+            task_response: TaskResponse {
+                referenceTaskIndex: task_index,
+                resultHash: result_hash,
+            },
+            signature: key_pair.sign_message(result_hash.as_slice()),
+            operator_id: OperatorId::ZERO,
+            quorum_number: None,
+        }
+    }
+
+    fn task_indices(queue: &ResponseQueue) -> Vec<u32> {
+        queue
+            .pending()
+            .unwrap()
+            .iter()
+            .map(|queued| queued.response.task_response.referenceTaskIndex)
+            .collect()
+    }
+
+    #[test]
+    fn test_queue_persists_across_restarts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let max_age = Duration::from_secs(3600);
+
+        let queue = ResponseQueue::open(dir.path(), max_age).unwrap();
+        queue.push(&signed_response(7, B256::ZERO)).unwrap();
+        queue.push(&signed_response(3, B256::ZERO)).unwrap();
+        drop(queue);
+
+        let reopened = ResponseQueue::open(dir.path(), max_age).unwrap();
+        assert_eq!(task_indices(&reopened), vec![3, 7]);
+
+        reopened.remove(3).unwrap();
+        reopened.remove(3).unwrap();
+        assert_eq!(task_indices(&reopened), vec![7]);
+    }
+
+    #[test]
+    fn test_queue_is_keyed_by_task_index() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let queue = ResponseQueue::open(dir.path(), Duration::from_secs(3600)).unwrap();
+
+        queue.push(&signed_response(1, B256::ZERO)).unwrap();
+        queue
+            .push(&signed_response(1, B256::repeat_byte(1)))
+            .unwrap();
+
+        let pending = queue.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(
+            pending[0].response.task_response.resultHash,
+            B256::repeat_byte(1)
+        );
+    }
+
+    #[test]
+    fn test_queue_discards_expired_and_corrupt_entries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let queue = ResponseQueue::open(dir.path(), Duration::from_secs(60)).unwrap();
+
+        queue
+            .push_at(&signed_response(1, B256::ZERO), unix_now() - 120)
+            .unwrap();
+        queue.push(&signed_response(2, B256::ZERO)).unwrap();
+        fs::write(dir.path().join("3.json"), b"not json").unwrap();
+
+        assert_eq!(task_indices(&queue), vec![2]);
+        assert!(!dir.path().join("1.json").exists());
+        assert!(!dir.path().join("3.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_drain_keeps_responses_while_aggregator_is_down() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let queue = ResponseQueue::open(dir.path(), Duration::from_secs(3600)).unwrap();
+        queue.push(&signed_response(1, B256::ZERO)).unwrap();
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        let client = AggregatorClient::new(&address)
+            .unwrap()
+            .with_max_attempts(1)
+            .with_reconnect_config(ReconnectConfig {
+                reconnect_attempts: 0,
+                ..Default::default()
+            });
+
+        assert_eq!(queue.drain(&client).await.unwrap(), 0);
+        assert_eq!(task_indices(&queue), vec![1]);
+    }
+}
@@ -0,0 +1,195 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use alloy_primitives::B256;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::debug;
+
+use crate::jobs::compute_x_square::ResultHashMode;
+use crate::scheduler::ParallelSchedule;
+
+#[derive(Debug, Error)]
+pub enum ScheduleArtifactError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// The schedule an operator signed for a task, as written for external verifiers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleArtifact {
+    pub task_index: u32,
+    /// Hash of the schedule, as signed in the task response
+    pub result_hash: B256,
+    /// How `result_hash` was derived from the schedule hash
+    #[serde(default)]
+    pub result_hash_mode: ResultHashMode,
+    /// Block the task was created at, mixed into `result_hash` by
+    /// [`ResultHashMode::TaskCreatedBlock`]
+    #[serde(default)]
+    pub task_created_block: u32,
+    #[serde(flatten)]
+    pub schedule: ParallelSchedule,
+}
+
+impl ScheduleArtifact {
+    pub fn new(task_index: u32, schedule: ParallelSchedule) -> Self {
+        Self {
+            task_index,
+            result_hash: schedule.hash(),
+            result_hash_mode: ResultHashMode::default(),
+            task_created_block: 0,
+            schedule,
+        }
+    }
+
+    /// Records the result hash as derived with `mode` for a task created at
+    /// `task_created_block`
+    pub fn with_result_hash_mode(mut self, mode: ResultHashMode, task_created_block: u32) -> Self {
+        self.result_hash_mode = mode;
+        self.task_created_block = task_created_block;
+        self.result_hash = self.expected_result_hash();
+        self
+    }
+
+    /// Checks that the recorded hash is the result hash of the recorded schedule
+    pub fn verify(&self) -> bool {
+        self.expected_result_hash() == self.result_hash
+    }
+
+    fn expected_result_hash(&self) -> B256 {
+        self.result_hash_mode.result_hash(
+            self.schedule.hash(),
+            self.task_index,
+            self.task_created_block,
+        )
+    }
+}
+
+/// Directory of the schedules computed for tasks, so downstream tools can replay a block's
+/// parallel execution plan and check it against the hash that was signed.
+///
+/// Every schedule is stored as `<task_index>.json`, computing a task again overwrites it.
+#[derive(Debug, Clone)]
+pub struct ScheduleArtifacts {
+    dir: PathBuf,
+}
+
+impl ScheduleArtifacts {
+    /// Opens the artifacts stored in `dir`, creating the directory if needed
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, ScheduleArtifactError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Returns the directory the artifacts are stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the path of the artifact for `task_index`
+    pub fn path_for(&self, task_index: u32) -> PathBuf {
+        self.dir.join(format!("{}.json", task_index))
+    }
+
+    /// Writes the schedule computed for `task_index`
+    pub fn write(
+        &self,
+        task_index: u32,
+        schedule: &ParallelSchedule,
+    ) -> Result<PathBuf, ScheduleArtifactError> {
+        self.write_artifact(&ScheduleArtifact::new(task_index, schedule.clone()))
+    }
+
+    /// Writes `artifact`, replacing the one of its task
+    pub fn write_artifact(
+        &self,
+        artifact: &ScheduleArtifact,
+    ) -> Result<PathBuf, ScheduleArtifactError> {
+        let task_index = artifact.task_index;
+
+        // Write to a temporary file first so verifiers never read a truncated artifact
+        let path = self.path_for(task_index);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(artifact)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        debug!(
+            "Wrote schedule of task {} to {}",
+            task_index,
+            path.display()
+        );
+        Ok(path)
+    }
+
+    /// Reads the artifact of `task_index`, or `None` if no schedule was written for it
+    pub fn read(&self, task_index: u32) -> Result<Option<ScheduleArtifact>, ScheduleArtifactError> {
+        match fs::read(self.path_for(task_index)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifacts_round_trip_and_verify() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let artifacts = ScheduleArtifacts::open(dir.path().join("schedules")).unwrap();
+        let schedule = ParallelSchedule::new(B256::repeat_byte(0xab), vec![vec![0, 2], vec![1]]);
+
+        assert!(artifacts.read(7).unwrap().is_none());
+        let path = artifacts.write(7, &schedule).unwrap();
+        assert_eq!(path, dir.path().join("schedules").join("7.json"));
+
+        let artifact = artifacts.read(7).unwrap().unwrap();
+        assert_eq!(artifact.task_index, 7);
+        assert_eq!(artifact.result_hash, schedule.hash());
+        assert_eq!(artifact.schedule, schedule);
+        assert!(artifact.verify());
+
+        // The artifact is the schedule's JSON with the task index and hash alongside
+        let value: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(value["batches"], serde_json::json!([[0, 2], [1]]));
+        assert_eq!(value["task_index"], 7);
+
+        let tampered = ScheduleArtifact {
+            schedule: ParallelSchedule::new(schedule.block_hash, vec![vec![0, 1, 2]]),
+            ..artifact
+        };
+        assert!(!tampered.verify());
+    }
+
+    #[test]
+    fn test_artifact_verifies_the_result_hash_mode() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let artifacts = ScheduleArtifacts::open(dir.path()).unwrap();
+        let schedule = ParallelSchedule::new(B256::repeat_byte(0xab), vec![vec![0, 1]]);
+
+        let artifact = ScheduleArtifact::new(7, schedule.clone())
+            .with_result_hash_mode(ResultHashMode::TaskIndex, 42);
+        assert_ne!(artifact.result_hash, schedule.hash());
+        assert!(artifact.verify());
+        artifacts.write_artifact(&artifact).unwrap();
+        let read = artifacts.read(7).unwrap().unwrap();
+        assert_eq!(read, artifact);
+        assert!(read.verify());
+
+        // Artifacts written before the mode existed are schedule hashes
+        let mut value = serde_json::to_value(ScheduleArtifact::new(7, schedule)).unwrap();
+        let object = value.as_object_mut().unwrap();
+        object.remove("result_hash_mode");
+        object.remove("task_created_block");
+        let legacy: ScheduleArtifact = serde_json::from_value(value).unwrap();
+        assert_eq!(legacy.result_hash_mode, ResultHashMode::Schedule);
+        assert!(legacy.verify());
+    }
+}
@@ -0,0 +1,466 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use thiserror::Error;
+
+/// Tip offered to block builders by EIP-1559 submissions unless configured, 1 gwei
+pub const DEFAULT_PRIORITY_FEE_PER_GAS: u128 = 1_000_000_000;
+
+/// Percentage a replacement raises the fees of a stuck submission by. Nodes reject
+/// replacements raising them by less than 10%
+pub const DEFAULT_FEE_BUMP_PERCENT: u32 = 20;
+
+/// Times a stuck submission is replaced before the aggregator just waits for it
+pub const DEFAULT_MAX_REPLACEMENTS: u32 = 3;
+
+/// How long the aggregator waits for a submission it no longer replaces to be included
+pub const DEFAULT_RECEIPT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SubmissionError {
+    #[error("Unknown gas strategy {0:?}, expected `legacy` or `eip1559`")]
+    UnknownStrategy(String),
+    #[error(
+        "Fee cap of {cap} wei per gas is below the network's current {required} wei, so the \
+         submission would never be included"
+    )]
+    FeeCapTooLow { required: u128, cap: u128 },
+    #[error("Fee bump must be at least 10%, got {0}%")]
+    FeeBumpTooLow(u32),
+    #[error("Receipt timeout must be greater than zero")]
+    ZeroReceiptTimeout,
+}
+
+/// How aggregated responses are priced when submitted on-chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GasStrategy {
+    /// A single gas price, for chains without EIP-1559
+    Legacy,
+    /// A max fee and priority fee on top of the block's base fee
+    #[default]
+    Eip1559,
+}
+
+impl FromStr for GasStrategy {
+    type Err = SubmissionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "legacy" => Ok(GasStrategy::Legacy),
+            "eip1559" | "eip-1559" => Ok(GasStrategy::Eip1559),
+            _ => Err(SubmissionError::UnknownStrategy(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for GasStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GasStrategy::Legacy => f.write_str("legacy"),
+            GasStrategy::Eip1559 => f.write_str("eip1559"),
+        }
+    }
+}
+
+/// Fees a submission is sent with, in wei per gas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fees {
+    Legacy {
+        gas_price: u128,
+    },
+    Eip1559 {
+        max_fee_per_gas: u128,
+        max_priority_fee_per_gas: u128,
+    },
+}
+
+impl Fees {
+    /// Returns the highest price per gas the submission may pay
+    pub fn max_fee_per_gas(&self) -> u128 {
+        match *self {
+            Fees::Legacy { gas_price } => gas_price,
+            Fees::Eip1559 {
+                max_fee_per_gas, ..
+            } => max_fee_per_gas,
+        }
+    }
+
+    /// Raises every fee by `percent`, rounding up and by at least 1 wei
+    pub fn bumped(&self, percent: u32) -> Self {
+        let bump = |fee: u128| {
+            let raised = fee.saturating_mul(100 + percent as u128).div_ceil(100);
+            raised.max(fee.saturating_add(1))
+        };
+        match *self {
+            Fees::Legacy { gas_price } => Fees::Legacy {
+                gas_price: bump(gas_price),
+            },
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => Fees::Eip1559 {
+                max_fee_per_gas: bump(max_fee_per_gas),
+                max_priority_fee_per_gas: bump(max_priority_fee_per_gas),
+            },
+        }
+    }
+}
+
+/// Gas pricing and replacement of the aggregator's on-chain submissions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasConfig {
+    strategy: GasStrategy,
+    max_fee_per_gas: Option<u128>,
+    priority_fee_per_gas: u128,
+    replacement_timeout: Option<Duration>,
+    max_replacements: u32,
+    fee_bump_percent: u32,
+    receipt_timeout: Duration,
+}
+
+impl Default for GasConfig {
+    fn default() -> Self {
+        Self {
+            strategy: GasStrategy::default(),
+            max_fee_per_gas: None,
+            priority_fee_per_gas: DEFAULT_PRIORITY_FEE_PER_GAS,
+            replacement_timeout: None,
+            max_replacements: DEFAULT_MAX_REPLACEMENTS,
+            fee_bump_percent: DEFAULT_FEE_BUMP_PERCENT,
+            receipt_timeout: DEFAULT_RECEIPT_TIMEOUT,
+        }
+    }
+}
+
+impl GasConfig {
+    pub fn with_strategy(mut self, strategy: GasStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Never pays more than `max_fee_per_gas` wei per gas, replacements included
+    pub fn with_max_fee_per_gas(mut self, max_fee_per_gas: u128) -> Self {
+        self.max_fee_per_gas = Some(max_fee_per_gas);
+        self
+    }
+
+    pub fn with_priority_fee_per_gas(mut self, priority_fee_per_gas: u128) -> Self {
+        self.priority_fee_per_gas = priority_fee_per_gas;
+        self
+    }
+
+    /// Replaces a submission still pending after `timeout` with one paying higher fees, off
+    /// by default
+    pub fn with_replacement_timeout(mut self, timeout: Duration) -> Self {
+        self.replacement_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_max_replacements(mut self, max_replacements: u32) -> Self {
+        self.max_replacements = max_replacements;
+        self
+    }
+
+    pub fn with_fee_bump_percent(mut self, percent: u32) -> Self {
+        self.fee_bump_percent = percent;
+        self
+    }
+
+    /// Gives up on a submission that is no longer replaced once it is still pending after
+    /// `timeout`, [`DEFAULT_RECEIPT_TIMEOUT`] by default
+    pub fn with_receipt_timeout(mut self, timeout: Duration) -> Self {
+        self.receipt_timeout = timeout;
+        self
+    }
+
+    pub fn strategy(&self) -> GasStrategy {
+        self.strategy
+    }
+
+    pub fn replacement_timeout(&self) -> Option<Duration> {
+        self.replacement_timeout
+    }
+
+    pub fn max_replacements(&self) -> u32 {
+        self.max_replacements
+    }
+
+    pub fn receipt_timeout(&self) -> Duration {
+        self.receipt_timeout
+    }
+
+    /// Checks the settings, so a bad configuration fails at startup rather than on a stuck
+    /// submission
+    pub fn validate(&self) -> Result<(), SubmissionError> {
+        if self.replacement_timeout.is_some() && self.fee_bump_percent < 10 {
+            return Err(SubmissionError::FeeBumpTooLow(self.fee_bump_percent));
+        }
+        if self.receipt_timeout.is_zero() {
+            return Err(SubmissionError::ZeroReceiptTimeout);
+        }
+        Ok(())
+    }
+
+    /// Computes the fees of a new submission from the network's current `gas_price` and the
+    /// latest block's `base_fee`, which chains without EIP-1559 do not have.
+    ///
+    /// EIP-1559 submissions offer twice the base fee plus the priority fee, so they stay
+    /// includable while the base fee rises for a few blocks. Fees are capped at the configured
+    /// maximum, which is an error if it is below what the network currently charges.
+    pub fn fees(&self, base_fee: Option<u128>, gas_price: u128) -> Result<Fees, SubmissionError> {
+        let cap = self.max_fee_per_gas.unwrap_or(u128::MAX);
+        match self.strategy {
+            GasStrategy::Legacy => {
+                if cap < gas_price {
+                    return Err(SubmissionError::FeeCapTooLow {
+                        required: gas_price,
+                        cap,
+                    });
+                }
+                Ok(Fees::Legacy { gas_price })
+            }
+            GasStrategy::Eip1559 => {
+                let base_fee = base_fee.unwrap_or(gas_price);
+                if cap < base_fee {
+                    return Err(SubmissionError::FeeCapTooLow {
+                        required: base_fee,
+                        cap,
+                    });
+                }
+                let max_fee_per_gas = base_fee
+                    .saturating_mul(2)
+                    .saturating_add(self.priority_fee_per_gas)
+                    .min(cap);
+                Ok(Fees::Eip1559 {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas: self.priority_fee_per_gas.min(max_fee_per_gas),
+                })
+            }
+        }
+    }
+
+    /// Returns the fees replacing a submission sent with `fees`, or `None` if the fee cap
+    /// leaves no room for a replacement the network would accept
+    pub fn replacement_fees(&self, fees: Fees) -> Option<Fees> {
+        let bumped = fees.bumped(self.fee_bump_percent);
+        match self.max_fee_per_gas {
+            Some(cap) if bumped.max_fee_per_gas() > cap => None,
+            _ => Some(bumped),
+        }
+    }
+}
+
+/// Hands out the nonces of the aggregator's submissions, so concurrent submissions do not
+/// reuse one and a submission that never made it on-chain does not leave a gap.
+///
+/// Nonces are reserved from the account's pending nonce on chain, skipping the ones of
+/// submissions still in flight. A nonce whose submission finished without being included
+/// below the highest one handed out is a gap, which the next reservation fills.
+#[derive(Debug, Clone, Default)]
+pub struct NonceTracker {
+    next: Option<u64>,
+    in_flight: BTreeSet<u64>,
+}
+
+impl NonceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves the nonce of a new submission, given the account's pending nonce on chain
+    pub fn reserve(&mut self, chain_nonce: u64) -> u64 {
+        // Nonces below the chain's were included, by us or by another sender
+        self.in_flight.retain(|&nonce| nonce >= chain_nonce);
+        let next = self.next.map_or(chain_nonce, |next| next.max(chain_nonce));
+        let nonce = (chain_nonce..next)
+            .find(|nonce| !self.in_flight.contains(nonce))
+            .unwrap_or(next);
+        self.next = Some(next.max(nonce + 1));
+        self.in_flight.insert(nonce);
+        nonce
+    }
+
+    /// Marks the submission with `nonce` as finished, whether it was included or abandoned
+    pub fn finish(&mut self, nonce: u64) {
+        self.in_flight.remove(&nonce);
+    }
+
+    /// Returns the nonces of submissions still in flight, in ascending order
+    pub fn in_flight(&self) -> Vec<u64> {
+        self.in_flight.iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u128 = 1_000_000_000;
+
+    #[test]
+    fn test_nonces_increment_from_the_chain() {
+        let mut nonces = NonceTracker::new();
+        assert_eq!(nonces.reserve(5), 5);
+        // The chain does not see the first submission yet
+        assert_eq!(nonces.reserve(5), 6);
+        // Only the first one is pending on chain
+        assert_eq!(nonces.reserve(6), 7);
+        assert_eq!(nonces.in_flight(), vec![6, 7]);
+
+        // All three were included
+        for nonce in [5, 6, 7] {
+            nonces.finish(nonce);
+        }
+        assert_eq!(nonces.reserve(8), 8);
+
+        // Another sender used the account in the meantime
+        nonces.finish(8);
+        assert_eq!(nonces.reserve(12), 12);
+        assert_eq!(nonces.in_flight(), vec![12]);
+    }
+
+    #[test]
+    fn test_abandoned_nonce_is_reused() {
+        let mut nonces = NonceTracker::new();
+        assert_eq!(nonces.reserve(0), 0);
+        assert_eq!(nonces.reserve(0), 1);
+        assert_eq!(nonces.reserve(0), 2);
+
+        // The submission with nonce 1 failed to send, leaving a gap that blocks nonce 2
+        nonces.finish(1);
+        assert_eq!(nonces.reserve(1), 1);
+        assert_eq!(nonces.reserve(1), 3);
+
+        // Nonce 0 was included and the chain moved past it
+        nonces.finish(0);
+        assert_eq!(nonces.in_flight(), vec![1, 2, 3]);
+
+        // A dropped submission leaves a gap the chain reports, which is filled first
+        nonces.finish(2);
+        nonces.finish(3);
+        assert_eq!(nonces.reserve(2), 2);
+        assert_eq!(nonces.reserve(2), 3);
+        assert_eq!(nonces.reserve(2), 4);
+    }
+
+    #[test]
+    fn test_gas_strategy_from_config() {
+        assert_eq!(GasStrategy::default(), GasStrategy::Eip1559);
+        assert_eq!(
+            "LEGACY".parse::<GasStrategy>().unwrap(),
+            GasStrategy::Legacy
+        );
+        assert_eq!(
+            " eip-1559".parse::<GasStrategy>().unwrap(),
+            GasStrategy::Eip1559
+        );
+        assert_eq!(
+            GasStrategy::Eip1559
+                .to_string()
+                .parse::<GasStrategy>()
+                .unwrap(),
+            GasStrategy::Eip1559
+        );
+        assert!(matches!(
+            "fixed".parse::<GasStrategy>(),
+            Err(SubmissionError::UnknownStrategy(_))
+        ));
+    }
+
+    #[test]
+    fn test_fee_computation() {
+        let eip1559 = GasConfig::default();
+        assert_eq!(
+            eip1559.fees(Some(10 * GWEI), 12 * GWEI).unwrap(),
+            Fees::Eip1559 {
+                max_fee_per_gas: 21 * GWEI,
+                max_priority_fee_per_gas: GWEI,
+            }
+        );
+        // Without a base fee the gas price stands in for it
+        assert_eq!(
+            eip1559.fees(None, 12 * GWEI).unwrap().max_fee_per_gas(),
+            25 * GWEI
+        );
+
+        // The cap limits the max fee, and the tip with it
+        let capped = GasConfig::default()
+            .with_max_fee_per_gas(15 * GWEI)
+            .with_priority_fee_per_gas(20 * GWEI);
+        assert_eq!(
+            capped.fees(Some(10 * GWEI), 0).unwrap(),
+            Fees::Eip1559 {
+                max_fee_per_gas: 15 * GWEI,
+                max_priority_fee_per_gas: 15 * GWEI,
+            }
+        );
+        assert_eq!(
+            capped.fees(Some(16 * GWEI), 0),
+            Err(SubmissionError::FeeCapTooLow {
+                required: 16 * GWEI,
+                cap: 15 * GWEI
+            })
+        );
+
+        let legacy = GasConfig::default()
+            .with_strategy(GasStrategy::Legacy)
+            .with_max_fee_per_gas(15 * GWEI);
+        assert_eq!(
+            legacy.fees(Some(10 * GWEI), 12 * GWEI).unwrap(),
+            Fees::Legacy {
+                gas_price: 12 * GWEI
+            }
+        );
+        assert!(legacy.fees(None, 16 * GWEI).is_err());
+    }
+
+    #[test]
+    fn test_replacement_fees() {
+        let config = GasConfig::default().with_max_fee_per_gas(30 * GWEI);
+        let fees = Fees::Eip1559 {
+            max_fee_per_gas: 21 * GWEI,
+            max_priority_fee_per_gas: GWEI,
+        };
+
+        let replacement = config.replacement_fees(fees).unwrap();
+        assert_eq!(
+            replacement,
+            Fees::Eip1559 {
+                max_fee_per_gas: 25_200_000_000,
+                max_priority_fee_per_gas: 1_200_000_000,
+            }
+        );
+        // The next bump would exceed the cap
+        assert_eq!(config.replacement_fees(replacement), None);
+
+        // Tiny fees still rise by at least 1 wei
+        assert_eq!(
+            Fees::Legacy { gas_price: 1 }.bumped(20),
+            Fees::Legacy { gas_price: 2 }
+        );
+        assert_eq!(
+            Fees::Legacy { gas_price: 7 }.bumped(20),
+            Fees::Legacy { gas_price: 9 }
+        );
+
+        assert_eq!(
+            GasConfig::default()
+                .with_replacement_timeout(Duration::from_secs(30))
+                .with_fee_bump_percent(5)
+                .validate(),
+            Err(SubmissionError::FeeBumpTooLow(5))
+        );
+        assert!(GasConfig::default()
+            .with_fee_bump_percent(5)
+            .validate()
+            .is_ok());
+        assert_eq!(
+            GasConfig::default()
+                .with_receipt_timeout(Duration::ZERO)
+                .validate(),
+            Err(SubmissionError::ZeroReceiptTimeout)
+        );
+    }
+}
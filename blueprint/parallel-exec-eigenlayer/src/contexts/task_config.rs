@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::IIncredibleSquaringTaskManager::Task;
+
+/// Blocks a task stays open for signatures by default, matching the task manager's
+/// `TASK_CHALLENGE_WINDOW_BLOCK`
+pub const DEFAULT_TASK_INTERVAL_BLOCKS: u32 = 100;
+pub const DEFAULT_BLOCK_TIME: Duration = Duration::from_secs(12);
+
+/// Highest number of quorums the EigenLayer registry coordinator supports
+pub const MAX_QUORUM_COUNT: u8 = 192;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TaskConfigError {
+    #[error("At least one quorum number must be configured")]
+    NoQuorums,
+    #[error("Quorum number {0} is out of range, quorums must be below 192")]
+    InvalidQuorum(u8),
+    #[error("Quorum number {0} is configured more than once")]
+    DuplicateQuorum(u8),
+    #[error("Quorum threshold percentage must be within 1..=100, got {0}")]
+    InvalidThreshold(u8),
+    #[error("Task interval must be at least one block")]
+    ZeroInterval,
+    #[error("Block time must be positive")]
+    ZeroBlockTime,
+    #[error("Quorum window must be positive")]
+    ZeroQuorumWindow,
+}
+
+/// How a task differs from the quorum setup the operator expects tasks to be created with
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TaskMismatch {
+    #[error("Task was created with quorums {actual:?} instead of the expected {expected:?}")]
+    QuorumNumbers { expected: Vec<u8>, actual: Vec<u8> },
+    #[error("Task requires {actual}% of each quorum instead of the expected {expected}%")]
+    QuorumThreshold { expected: u8, actual: u8 },
+}
+
+/// How [`initialize_bls_task`](crate::jobs::initialize_task::initialize_bls_task) sets up BLS
+/// aggregation for new tasks.
+///
+/// Every task is aggregated over the quorums and with the threshold it was created with, as the
+/// task manager checks the aggregate against those. Operators can configure the quorums and
+/// threshold they expect, which only flags tasks created with others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskConfig {
+    quorum_numbers: Option<Vec<u8>>,
+    quorum_threshold_percentage: Option<u8>,
+    task_interval_blocks: u32,
+    block_time: Duration,
+    quorum_window: Option<Duration>,
+}
+
+/// Parameters a single task is initialized with in the BLS aggregation service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskParams {
+    pub quorum_numbers: Vec<u8>,
+    /// Threshold percentage of each quorum in `quorum_numbers`
+    pub quorum_threshold_percentages: Vec<u8>,
+    /// How long signatures are collected for the task before it expires
+    pub time_to_expiry: Duration,
+    /// How long signatures are collected after the first one arrived, `None` to collect them
+    /// until the task expires
+    pub quorum_window: Option<Duration>,
+}
+
+impl Default for TaskConfig {
+    fn default() -> Self {
+        Self {
+            quorum_numbers: None,
+            quorum_threshold_percentage: None,
+            task_interval_blocks: DEFAULT_TASK_INTERVAL_BLOCKS,
+            block_time: DEFAULT_BLOCK_TIME,
+            quorum_window: None,
+        }
+    }
+}
+
+impl TaskConfig {
+    /// Expects every task to be created with `quorum_numbers`
+    pub fn with_quorum_numbers(mut self, quorum_numbers: Vec<u8>) -> Self {
+        self.quorum_numbers = Some(quorum_numbers);
+        self
+    }
+
+    /// Expects every task to require `percentage` of each quorum's stake
+    pub fn with_quorum_threshold_percentage(mut self, percentage: u8) -> Self {
+        self.quorum_threshold_percentage = Some(percentage);
+        self
+    }
+
+    /// Collects signatures for `blocks` blocks after a task is initialized
+    pub fn with_task_interval_blocks(mut self, blocks: u32) -> Self {
+        self.task_interval_blocks = blocks;
+        self
+    }
+
+    pub fn with_block_time(mut self, block_time: Duration) -> Self {
+        self.block_time = block_time;
+        self
+    }
+
+    /// Finalizes every task `window` after its first signature arrived, failing it unless its
+    /// quorum was reached by then, rather than waiting until it expires
+    pub fn with_quorum_window(mut self, window: Duration) -> Self {
+        self.quorum_window = Some(window);
+        self
+    }
+
+    /// Checks the configured values, so a bad setup fails at startup rather than on the first
+    /// task
+    pub fn validate(&self) -> Result<(), TaskConfigError> {
+        if let Some(quorum_numbers) = &self.quorum_numbers {
+            if quorum_numbers.is_empty() {
+                return Err(TaskConfigError::NoQuorums);
+            }
+            let mut seen = HashSet::new();
+            for &quorum in quorum_numbers {
+                if quorum >= MAX_QUORUM_COUNT {
+                    return Err(TaskConfigError::InvalidQuorum(quorum));
+                }
+                if !seen.insert(quorum) {
+                    return Err(TaskConfigError::DuplicateQuorum(quorum));
+                }
+            }
+        }
+        if let Some(percentage) = self.quorum_threshold_percentage {
+            if !(1..=100).contains(&percentage) {
+                return Err(TaskConfigError::InvalidThreshold(percentage));
+            }
+        }
+        if self.task_interval_blocks == 0 {
+            return Err(TaskConfigError::ZeroInterval);
+        }
+        if self.block_time.is_zero() {
+            return Err(TaskConfigError::ZeroBlockTime);
+        }
+        if self.quorum_window.is_some_and(|window| window.is_zero()) {
+            return Err(TaskConfigError::ZeroQuorumWindow);
+        }
+        Ok(())
+    }
+
+    /// Returns how long signatures are collected for a task
+    pub fn time_to_expiry(&self) -> Duration {
+        self.block_time * self.task_interval_blocks
+    }
+
+    /// Returns the parameters `task` is initialized with, which always uses the task's own
+    /// quorums and threshold
+    pub fn params_for(&self, task: &Task) -> TaskParams {
+        let quorum_numbers = task.quorumNumbers.to_vec();
+        let percentage = task.quorumThresholdPercentage as u8;
+        TaskParams {
+            quorum_threshold_percentages: vec![percentage; quorum_numbers.len()],
+            quorum_numbers,
+            time_to_expiry: self.time_to_expiry(),
+            quorum_window: self.quorum_window,
+        }
+    }
+
+    /// Lists how `task` differs from the expected quorums and threshold
+    pub fn mismatches(&self, task: &Task) -> Vec<TaskMismatch> {
+        let mut mismatches = Vec::new();
+        if let Some(expected) = &self.quorum_numbers {
+            if task.quorumNumbers.as_ref() != expected.as_slice() {
+                mismatches.push(TaskMismatch::QuorumNumbers {
+                    expected: expected.clone(),
+                    actual: task.quorumNumbers.to_vec(),
+                });
+            }
+        }
+        if let Some(expected) = self.quorum_threshold_percentage {
+            let actual = task.quorumThresholdPercentage as u8;
+            if actual != expected {
+                mismatches.push(TaskMismatch::QuorumThreshold { expected, actual });
+            }
+        }
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Bytes;
+
+    fn task() -> Task {
+        Task {
+            taskCreatedBlock: 100,
+            quorumNumbers: Bytes::from(vec![0]),
+            quorumThresholdPercentage: 67,
+        }
+    }
+
+    #[test]
+    fn test_default_config_uses_task_parameters() {
+        let config = TaskConfig::default();
+        config.validate().unwrap();
+
+        let params = config.params_for(&task());
+        assert_eq!(params.quorum_numbers, vec![0]);
+        assert_eq!(params.quorum_threshold_percentages, vec![67]);
+        assert_eq!(params.time_to_expiry, Duration::from_secs(1200));
+        assert_eq!(params.quorum_window, None);
+    }
+
+    #[test]
+    fn test_configured_values_never_override_task_parameters() {
+        let config = TaskConfig::default()
+            .with_quorum_numbers(vec![0, 1])
+            .with_quorum_threshold_percentage(50)
+            .with_task_interval_blocks(10)
+            .with_block_time(Duration::from_secs(2))
+            .with_quorum_window(Duration::from_secs(5));
+        config.validate().unwrap();
+
+        let params = config.params_for(&task());
+        assert_eq!(params.quorum_numbers, vec![0]);
+        assert_eq!(params.quorum_threshold_percentages, vec![67]);
+        assert_eq!(params.time_to_expiry, Duration::from_secs(20));
+        assert_eq!(params.quorum_window, Some(Duration::from_secs(5)));
+
+        assert_eq!(
+            config.mismatches(&task()),
+            vec![
+                TaskMismatch::QuorumNumbers {
+                    expected: vec![0, 1],
+                    actual: vec![0],
+                },
+                TaskMismatch::QuorumThreshold {
+                    expected: 50,
+                    actual: 67,
+                },
+            ]
+        );
+        let matching = TaskConfig::default()
+            .with_quorum_numbers(vec![0])
+            .with_quorum_threshold_percentage(67);
+        assert!(matching.mismatches(&task()).is_empty());
+        assert!(TaskConfig::default().mismatches(&task()).is_empty());
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_values() {
+        let invalid = [
+            (
+                TaskConfig::default().with_quorum_numbers(vec![]),
+                TaskConfigError::NoQuorums,
+            ),
+            (
+                TaskConfig::default().with_quorum_numbers(vec![0, 192]),
+                TaskConfigError::InvalidQuorum(192),
+            ),
+            (
+                TaskConfig::default().with_quorum_numbers(vec![1, 0, 1]),
+                TaskConfigError::DuplicateQuorum(1),
+            ),
+            (
+                TaskConfig::default().with_quorum_threshold_percentage(0),
+                TaskConfigError::InvalidThreshold(0),
+            ),
+            (
+                TaskConfig::default().with_quorum_threshold_percentage(101),
+                TaskConfigError::InvalidThreshold(101),
+            ),
+            (
+                TaskConfig::default().with_task_interval_blocks(0),
+                TaskConfigError::ZeroInterval,
+            ),
+            (
+                TaskConfig::default().with_block_time(Duration::ZERO),
+                TaskConfigError::ZeroBlockTime,
+            ),
+            (
+                TaskConfig::default().with_quorum_window(Duration::ZERO),
+                TaskConfigError::ZeroQuorumWindow,
+            ),
+        ];
+        for (config, expected) in invalid {
+            assert_eq!(config.validate(), Err(expected));
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Bounds how many tasks are processed at once, shared between clones.
+///
+/// At most `max_concurrent` tasks run at the same time and up to `max_queued` more wait for
+/// one of them to finish. Tasks arriving while the queue is full are rejected right away, so a
+/// burst of events cannot pile up unbounded API calls and signings.
+#[derive(Debug, Clone)]
+pub struct TaskLimiter {
+    semaphore: Arc<Semaphore>,
+    queued: Arc<AtomicUsize>,
+    max_concurrent: usize,
+    max_queued: usize,
+}
+
+/// Allows a task to run until dropped
+#[derive(Debug)]
+pub struct TaskPermit(OwnedSemaphorePermit);
+
+/// Removes a waiting task from the queue count, also when its future is dropped while waiting
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl TaskLimiter {
+    pub fn new(max_concurrent: usize, max_queued: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            queued: Arc::new(AtomicUsize::new(0)),
+            max_concurrent,
+            max_queued,
+        }
+    }
+
+    /// Waits until the task may run, or returns `None` if the queue is already full
+    pub async fn acquire(&self) -> Option<TaskPermit> {
+        if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
+            return Some(TaskPermit(permit));
+        }
+
+        self.queued
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+                (queued < self.max_queued).then_some(queued + 1)
+            })
+            .ok()?;
+        let _queued = QueuedGuard(&self.queued);
+        let permit = self.semaphore.clone().acquire_owned().await.ok()?;
+        Some(TaskPermit(permit))
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    pub fn max_queued(&self) -> usize {
+        self.max_queued
+    }
+
+    /// Returns how many tasks are currently running
+    pub fn running(&self) -> usize {
+        self.max_concurrent - self.semaphore.available_permits()
+    }
+
+    /// Returns how many tasks are waiting to run
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tasks_beyond_the_queue_are_rejected() {
+        let limiter = TaskLimiter::new(1, 1);
+        let running = limiter.acquire().await.unwrap();
+        assert_eq!(limiter.running(), 1);
+
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire().await.is_some() }
+        });
+        while limiter.queued() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // The queue is full, so the next task is turned away instead of waiting
+        assert!(limiter.acquire().await.is_none());
+        assert_eq!(limiter.queued(), 1);
+
+        drop(running);
+        assert!(waiting.await.unwrap());
+        assert_eq!(limiter.queued(), 0);
+        assert_eq!(limiter.running(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiters_leave_the_queue() {
+        let limiter = TaskLimiter::new(1, 1);
+        let _running = limiter.acquire().await.unwrap();
+
+        let cancelled =
+            tokio::time::timeout(std::time::Duration::from_millis(10), limiter.acquire()).await;
+        assert!(cancelled.is_err());
+        assert_eq!(limiter.queued(), 0);
+    }
+}
@@ -1,12 +1,67 @@
-use crate::contexts::client::AggregatorClient;
+use crate::contexts::api_recordings::ApiRecordings;
+use crate::contexts::client::AggregatorTransport;
+use crate::contexts::processed_tasks::ProcessedTasks;
+use crate::contexts::response_queue::ResponseQueue;
+use crate::contexts::schedule_artifacts::ScheduleArtifacts;
+use crate::contexts::task_limiter::TaskLimiter;
 use crate::api_client::ApiClient;
+#[cfg(feature = "metrics")]
+use crate::metrics::Metrics;
+use crate::jobs::compute_x_square::ResultHashMode;
+use crate::signer::{SignatureScheme, SigningDomain, TaskSigner};
 use blueprint_sdk::config::GadgetConfiguration;
 use blueprint_sdk::macros::contexts::KeystoreContext;
+use eigensdk::crypto_bls::OperatorId;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
 #[derive(Clone, KeystoreContext)]
 pub struct EigenSquareContext {
-    pub client: AggregatorClient,
+    pub client: Arc<dyn AggregatorTransport>,
     pub api_client: ApiClient,
+    pub response_queue: Option<ResponseQueue>,
+    /// Where the schedule of every task is written for external verifiers, if anywhere
+    pub schedule_artifacts: Option<ScheduleArtifacts>,
+    /// Where the helper API response of every task is recorded for replays, if anywhere
+    pub api_recordings: Option<ApiRecordings>,
+    pub processed_tasks: ProcessedTasks,
+    pub task_limiter: TaskLimiter,
+    pub operator_id: OperatorIdCache,
+    pub signature_scheme: SignatureScheme,
+    /// Signer loaded once at startup for keys held outside the keystore, or `None` to load
+    /// the key from the keystore for every task
+    pub task_signer: Option<Arc<dyn TaskSigner>>,
+    /// Quorums the operator is registered in, or `None` to sign for every quorum of a task
+    pub operator_quorums: Option<Vec<u8>>,
+    /// Chain and task manager that signed task responses are bound to
+    pub signing_domain: SigningDomain,
+    /// What the signed `resultHash` of a task is derived from
+    pub result_hash_mode: ResultHashMode,
+    /// Log signed task responses instead of sending them to the aggregator
+    pub dry_run: bool,
+    /// How long a task may run before it is cancelled, or `None` to let it run until done
+    pub task_deadline: Option<Duration>,
+    /// Operator id to sign with instead of the one derived from the BLS key, for integration
+    /// tests against a local devnet
+    #[cfg(feature = "operator-id-override")]
+    pub operator_id_override: Option<OperatorId>,
+    #[cfg(feature = "metrics")]
+    pub metrics: Metrics,
     #[config]
     pub std_config: GadgetConfiguration,
 }
+
+/// The operator id derived from the BLS key, computed once and shared between clones
+#[derive(Debug, Clone, Default)]
+pub struct OperatorIdCache(Arc<OnceLock<OperatorId>>);
+
+impl OperatorIdCache {
+    /// Returns the cached operator id, computing it with `init` on first use
+    pub fn get_or_init(&self, init: impl FnOnce() -> OperatorId) -> OperatorId {
+        *self.0.get_or_init(init)
+    }
+
+    pub fn get(&self) -> Option<OperatorId> {
+        self.0.get().copied()
+    }
+}
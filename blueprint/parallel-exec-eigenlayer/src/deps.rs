@@ -0,0 +1,346 @@
+use std::collections::{BTreeSet, HashMap};
+
+use alloy_primitives::{Address, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::scheduler::TxId;
+
+/// An EIP-2930 style access list entry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessListItem {
+    pub address: Address,
+    #[serde(default, alias = "storageKeys")]
+    pub storage_keys: Vec<B256>,
+}
+
+/// State touched by a transaction, split into what it reads and what it writes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxAccessList {
+    #[serde(default)]
+    pub reads: Vec<AccessListItem>,
+    #[serde(default)]
+    pub writes: Vec<AccessListItem>,
+}
+
+/// A unit of state two transactions can conflict on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AccessKey {
+    /// The account itself (balance, nonce, code), used for entries without storage keys
+    Account(Address),
+    /// A single storage slot of an account
+    Slot(Address, B256),
+}
+
+impl From<Address> for AccessKey {
+    fn from(address: Address) -> Self {
+        AccessKey::Account(address)
+    }
+}
+
+impl From<(Address, B256)> for AccessKey {
+    fn from((address, slot): (Address, B256)) -> Self {
+        AccessKey::Slot(address, slot)
+    }
+}
+
+/// Expands access list entries into the keys they touch
+pub(crate) fn access_keys(items: &[AccessListItem]) -> BTreeSet<AccessKey> {
+    items
+        .iter()
+        .flat_map(|item| {
+            let address = item.address;
+            let slots = item
+                .storage_keys
+                .iter()
+                .map(move |slot| AccessKey::Slot(address, *slot));
+            let account = item
+                .storage_keys
+                .is_empty()
+                .then_some(AccessKey::Account(address));
+            account.into_iter().chain(slots)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    reads: BTreeSet<AccessKey>,
+    writes: BTreeSet<AccessKey>,
+    /// Earlier transactions this one conflicts with
+    dependencies: BTreeSet<TxId>,
+}
+
+/// Conflict graph over the transactions of a block.
+///
+/// Transactions are added in block order and get an edge to every earlier transaction they
+/// conflict with, that is when both write the same key or one reads a key the other writes.
+///
+/// Earlier transactions are indexed by the keys they touch, so adding a transaction only
+/// looks at the transactions sharing a key with it rather than at the whole block.
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    nodes: Vec<Node>,
+    /// Transactions reading each key, in block order
+    readers: HashMap<AccessKey, Vec<TxId>>,
+    /// Transactions writing each key, in block order
+    writers: HashMap<AccessKey, Vec<TxId>>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds the graph for a block's transactions, in block order
+    pub fn from_transactions<'a>(txs: impl IntoIterator<Item = &'a TxAccessList>) -> Self {
+        let mut graph = Self::new();
+        for tx in txs {
+            graph.add_tx(tx);
+        }
+        graph
+    }
+
+    /// Builds the graph by checking every pair of transactions for a conflict.
+    ///
+    /// Quadratic in the number of transactions, kept as the reference the indexed
+    /// construction of [`from_transactions`](Self::from_transactions) is tested and
+    /// benchmarked against.
+    pub fn from_transactions_pairwise<'a>(txs: impl IntoIterator<Item = &'a TxAccessList>) -> Self {
+        let mut graph = Self::new();
+        for tx in txs {
+            let reads = access_keys(&tx.reads);
+            let writes = access_keys(&tx.writes);
+            let dependencies = graph
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, earlier)| {
+                    !writes.is_disjoint(&earlier.writes)
+                        || !writes.is_disjoint(&earlier.reads)
+                        || !reads.is_disjoint(&earlier.writes)
+                })
+                .map(|(id, _)| id as TxId)
+                .collect();
+            graph.push(reads, writes, dependencies);
+        }
+        graph
+    }
+
+    /// Adds the next transaction in block order and returns its id
+    pub fn add_tx(&mut self, tx: &TxAccessList) -> TxId {
+        let reads = access_keys(&tx.reads);
+        let writes = access_keys(&tx.writes);
+
+        let mut dependencies = BTreeSet::new();
+        for key in &writes {
+            for index in [&self.writers, &self.readers] {
+                dependencies.extend(index.get(key).into_iter().flatten().copied());
+            }
+        }
+        for key in &reads {
+            dependencies.extend(self.writers.get(key).into_iter().flatten().copied());
+        }
+
+        self.push(reads, writes, dependencies)
+    }
+
+    /// Appends a node and indexes its keys
+    fn push(
+        &mut self,
+        reads: BTreeSet<AccessKey>,
+        writes: BTreeSet<AccessKey>,
+        dependencies: BTreeSet<TxId>,
+    ) -> TxId {
+        let id = self.nodes.len() as TxId;
+        for key in &reads {
+            self.readers.entry(*key).or_default().push(id);
+        }
+        for key in &writes {
+            self.writers.entry(*key).or_default().push(id);
+        }
+        self.nodes.push(Node {
+            reads,
+            writes,
+            dependencies,
+        });
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the earlier transactions `tx` conflicts with
+    pub fn dependencies(&self, tx: TxId) -> Option<&BTreeSet<TxId>> {
+        self.nodes.get(tx as usize).map(|node| &node.dependencies)
+    }
+
+    /// Returns true if there is an edge between `a` and `b`
+    pub fn conflicts_with(&self, a: TxId, b: TxId) -> bool {
+        let (earlier, later) = if a < b { (a, b) } else { (b, a) };
+        self.dependencies(later)
+            .is_some_and(|dependencies| dependencies.contains(&earlier))
+    }
+
+    /// Groups the transactions into layers such that every transaction only depends on
+    /// transactions in earlier layers
+    pub fn topological_layers(&self) -> Vec<Vec<TxId>> {
+        let mut depth = Vec::with_capacity(self.nodes.len());
+        let mut layers: Vec<Vec<TxId>> = Vec::new();
+
+        // Dependencies always point to earlier transactions, so block order is already a
+        // topological order
+        for (id, node) in self.nodes.iter().enumerate() {
+            let layer = node
+                .dependencies
+                .iter()
+                .map(|dependency| depth[*dependency as usize] + 1)
+                .max()
+                .unwrap_or(0);
+            depth.push(layer);
+
+            if layer == layers.len() {
+                layers.push(Vec::new());
+            }
+            layers[layer].push(id as TxId);
+        }
+
+        layers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(address: u8, slot: u8) -> AccessListItem {
+        AccessListItem {
+            address: Address::repeat_byte(address),
+            storage_keys: vec![B256::repeat_byte(slot)],
+        }
+    }
+
+    fn tx(reads: Vec<AccessListItem>, writes: Vec<AccessListItem>) -> TxAccessList {
+        TxAccessList { reads, writes }
+    }
+
+    #[test]
+    fn test_read_only_transactions_have_no_edges() {
+        let txs: Vec<_> = (0..4).map(|_| tx(vec![slot(1, 1)], vec![])).collect();
+        let graph = DependencyGraph::from_transactions(&txs);
+
+        for id in 0..4 {
+            assert!(graph.dependencies(id).unwrap().is_empty());
+        }
+        assert!(!graph.conflicts_with(0, 3));
+        assert_eq!(graph.topological_layers(), vec![vec![0, 1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_write_chain_is_linear() {
+        // Each transaction reads the slot the previous one wrote
+        let txs = vec![
+            tx(vec![], vec![slot(1, 1)]),
+            tx(vec![slot(1, 1)], vec![slot(1, 2)]),
+            tx(vec![slot(1, 2)], vec![slot(1, 3)]),
+        ];
+        let graph = DependencyGraph::from_transactions(&txs);
+
+        assert!(graph.conflicts_with(0, 1));
+        assert!(graph.conflicts_with(2, 1));
+        assert!(!graph.conflicts_with(0, 2));
+        assert_eq!(graph.topological_layers(), vec![vec![0], vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn test_diamond_dependency() {
+        //   0
+        //  / \
+        // 1   2
+        //  \ /
+        //   3
+        let txs = vec![
+            tx(vec![], vec![slot(1, 1)]),
+            tx(vec![slot(1, 1)], vec![slot(2, 1)]),
+            tx(vec![slot(1, 1)], vec![slot(3, 1)]),
+            tx(vec![slot(2, 1), slot(3, 1)], vec![]),
+        ];
+        let graph = DependencyGraph::from_transactions(&txs);
+
+        assert_eq!(graph.dependencies(3).unwrap(), &BTreeSet::from([1, 2]));
+        assert!(!graph.conflicts_with(1, 2));
+        assert_eq!(
+            graph.topological_layers(),
+            vec![vec![0], vec![1, 2], vec![3]]
+        );
+    }
+
+    #[test]
+    fn test_account_entries_conflict_on_the_account() {
+        let account = AccessListItem {
+            address: Address::repeat_byte(1),
+            storage_keys: vec![],
+        };
+        let txs = vec![
+            tx(vec![], vec![account.clone()]),
+            tx(vec![account], vec![]),
+            tx(vec![], vec![slot(1, 1)]),
+        ];
+        let graph = DependencyGraph::from_transactions(&txs);
+
+        assert!(graph.conflicts_with(0, 1));
+        assert!(!graph.conflicts_with(0, 2));
+        assert!(graph.dependencies(5).is_none());
+    }
+
+    #[test]
+    fn test_indexed_construction_matches_pairwise() {
+        // Small xorshift generator, so the block is the same on every run
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound) as u8
+        };
+        let mut items = |count: u64| -> Vec<AccessListItem> {
+            (0..next(count))
+                .map(|_| {
+                    // Mix account entries with slots of the same few accounts
+                    let address = next(4);
+                    match next(3) {
+                        0 => AccessListItem {
+                            address: Address::repeat_byte(address),
+                            storage_keys: vec![],
+                        },
+                        _ => slot(address, next(6)),
+                    }
+                })
+                .collect()
+        };
+        let txs: Vec<_> = (0..200).map(|_| tx(items(4), items(3))).collect();
+
+        let indexed = DependencyGraph::from_transactions(&txs);
+        let pairwise = DependencyGraph::from_transactions_pairwise(&txs);
+        assert_eq!(indexed.len(), pairwise.len());
+        let mut edges = 0;
+        for id in 0..txs.len() as TxId {
+            assert_eq!(
+                indexed.dependencies(id),
+                pairwise.dependencies(id),
+                "tx {}",
+                id
+            );
+            edges += indexed.dependencies(id).unwrap().len();
+        }
+        // The block has conflicts as well as independent transactions
+        assert!(edges > 0);
+        assert!(indexed.topological_layers().len() > 1);
+        assert!(indexed.topological_layers()[0].len() > 1);
+        assert_eq!(indexed.topological_layers(), pairwise.topological_layers());
+    }
+}
@@ -1,31 +1,129 @@
 #![allow(dead_code)]
-use crate::contexts::client::SignedTaskResponse;
+use crate::api_client::{ApiClient, ApiClientError, Calculation};
+use crate::bls_keys::{load_bls_key_pair, BlsKeystore, BlsSecret, KeystoreError};
+use crate::contexts::api_recordings::ApiRecording;
+use crate::contexts::schedule_artifacts::ScheduleArtifact;
+use crate::contexts::task_config::MAX_QUORUM_COUNT;
 use crate::contexts::x_square::EigenSquareContext;
+use crate::listener::TaskEvent;
+use crate::scheduler::{ParallelSchedule, ScheduleStats};
+use crate::config::SignerSettings;
+use crate::signer::{
+    ecdsa_signer_from_key, load_ecdsa_signer, operator_id_from_public_key, BlsTaskSigner,
+    HttpSigningTransport, RemoteTaskSigner, SignatureScheme, SignedTaskPayload, SignerBackend,
+    SignerError, TaskSigner,
+};
 use crate::IIncredibleSquaringTaskManager::TaskResponse;
 use crate::{
-    IncredibleSquaringTaskManager, ProcessorError, INCREDIBLE_SQUARING_TASK_MANAGER_ABI_STRING,
+    Error, IncredibleSquaringTaskManager, ProcessorError,
+    INCREDIBLE_SQUARING_TASK_MANAGER_ABI_STRING,
 };
-use alloy_primitives::{keccak256, Bytes};
-use alloy_sol_types::SolType;
+use alloy_primitives::{hex, keccak256, Bytes, B256};
+use alloy_sol_types::SolValue;
 use blueprint_sdk::contexts::keystore::KeystoreContext;
-use blueprint_sdk::crypto::bn254::ArkBlsBn254;
 use blueprint_sdk::event_listeners::evm::EvmContractEventListener;
-use blueprint_sdk::keystore::backends::Backend;
-use blueprint_sdk::logging::{info, debug, error};
-use blueprint_sdk::macros::ext::keystore::backends::bn254::Bn254Backend;
+use blueprint_sdk::logging::{info, debug, error, warn};
 use blueprint_sdk::macros::job;
 use color_eyre::Result;
 use eigensdk::crypto_bls::BlsKeyPair;
 use eigensdk::crypto_bls::OperatorId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::time::timeout;
+use tracing::{field, info_span, Instrument, Span};
+
+/// Outcome of [`calculate_task`], encoded as the job's `u32` result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum TaskStatus {
+    /// The signed task response was accepted by the aggregator
+    Ok = 1,
+    /// The block data could not be fetched or hashed
+    ApiFailed = 2,
+    /// No usable BLS key was found in the keystore
+    NoKey = 3,
+    /// The task response could not be signed
+    SigningFailed = 4,
+    /// The aggregator did not accept the signed task response
+    SendFailed = 5,
+    /// The task was already processed, e.g. because its event was delivered twice
+    Duplicate = 6,
+    /// Too many tasks were already running or waiting to run, or the task's event was malformed
+    Rejected = 7,
+    /// The operator belongs to none of the quorums of the task
+    NotInQuorum = 8,
+    /// The task did not finish within its deadline and was cancelled
+    Timeout = 9,
+    /// The API returned fewer blocks than required to sign a result over
+    InsufficientBlocks = 10,
+}
+
+impl TaskStatus {
+    /// Returns the code reported as the job result
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Maps a job result code back to its status
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            1 => Some(TaskStatus::Ok),
+            2 => Some(TaskStatus::ApiFailed),
+            3 => Some(TaskStatus::NoKey),
+            4 => Some(TaskStatus::SigningFailed),
+            5 => Some(TaskStatus::SendFailed),
+            6 => Some(TaskStatus::Duplicate),
+            7 => Some(TaskStatus::Rejected),
+            8 => Some(TaskStatus::NotInQuorum),
+            9 => Some(TaskStatus::Timeout),
+            10 => Some(TaskStatus::InsufficientBlocks),
+            _ => None,
+        }
+    }
+}
+
+impl From<TaskStatus> for u32 {
+    fn from(status: TaskStatus) -> Self {
+        status.code()
+    }
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TaskStatus::Ok => "ok",
+            TaskStatus::ApiFailed => "api_failed",
+            TaskStatus::NoKey => "no_key",
+            TaskStatus::SigningFailed => "signing_failed",
+            TaskStatus::SendFailed => "send_failed",
+            TaskStatus::Duplicate => "duplicate",
+            TaskStatus::Rejected => "rejected",
+            TaskStatus::NotInQuorum => "not_in_quorum",
+            TaskStatus::Timeout => "timeout",
+            TaskStatus::InsufficientBlocks => "insufficient_blocks",
+        };
+        write!(f, "{} ({})", name, self.code())
+    }
+}
 
 /// Sends a signed task response to the BLS Aggregator.
 ///
 /// This job is triggered by the `NewTaskCreated` event emitted by the `IncredibleSquaringTaskManager`.
-/// The job fetches block data from an external API, hashes the concatenated block hashes,
-/// and sends the signed task response to the BLS Aggregator.
-/// The job returns 1 if the task response was sent successfully.
-/// The job returns 0 if the task response failed to send, failed to get the BLS key, or failed to fetch API data.
+/// The job fetches block data from an external API, schedules the transactions of the task's
+/// block into batches that can execute in parallel, signs the hash of that schedule with the
+/// configured [`SignatureScheme`] and sends the signed task response to the Aggregator, once
+/// for every quorum of the task the operator belongs to.
+/// The job returns a [`TaskStatus`] code: 1 if the task response was sent successfully,
+/// 2 if the API data could not be fetched, 3 if no BLS key is available, 4 if signing failed,
+/// 5 if the aggregator did not accept the response, 6 if the task was already processed,
+/// 7 if it was rejected because too many tasks were in flight, 8 if the operator is in none
+/// of the task's quorums, 9 if it did not finish within its deadline and 10 if the API
+/// returned fewer blocks than required to sign.
 #[job(
     id = 0,
     params(task_created_block, quorum_numbers, quorum_threshold_percentage, task_index),
@@ -43,74 +141,629 @@ pub async fn calculate_task(
     quorum_threshold_percentage: u8,
     task_index: u32,
 ) -> std::result::Result<u32, Infallible> {
+    let load_signer = || load_task_signer(&ctx);
+    Ok(process_task(
+        &ctx,
+        load_signer,
+        task_created_block,
+        &quorum_numbers,
+        task_index,
+    )
+    .await)
+}
+
+/// Runs [`calculate_task`] for a task delivered by a
+/// [`ReconnectingListener`](crate::listener::ReconnectingListener), which is how the operator
+/// receives tasks instead of the SDK's event listener
+pub async fn process_task_event(ctx: &EigenSquareContext, event: &TaskEvent) -> u32 {
+    let task = &event.event.task;
+    let task_index = event.event.taskIndex;
+    let (task_created_block, quorum_numbers, quorum_threshold_percentage, task_index) =
+        match task_inputs(
+            task.taskCreatedBlock,
+            task.quorumNumbers.clone(),
+            task.quorumThresholdPercentage,
+            task_index,
+        ) {
+            Ok(inputs) => inputs,
+            Err(e) => {
+                error!("Rejecting task {}: {}", task_index, e);
+                return TaskStatus::Rejected.code();
+            }
+        };
+    let Ok(status) = calculate_task(
+        ctx.clone(),
+        task_created_block,
+        quorum_numbers,
+        quorum_threshold_percentage,
+        task_index,
+    )
+    .await;
+    status
+}
+
+/// Runs [`calculate_task`] for a single task, signing with the signer returned by
+/// `load_signer`, and returns its [`TaskStatus`] code.
+///
+/// Everything logged while the task is processed belongs to its [`task_span`], so concurrent
+/// tasks can be told apart in the logs. A task still running the context's
+/// [`task_deadline`](EigenSquareContext::task_deadline) after it got its slot in the task
+/// limiter is cancelled, which drops its API request or aggregator send and frees the slot.
+pub async fn process_task(
+    ctx: &EigenSquareContext,
+    load_signer: impl FnOnce() -> Result<Box<dyn TaskSigner>, SignerError>,
+    task_created_block: u32,
+    quorum_numbers: &[u8],
+    task_index: u32,
+) -> u32 {
+    #[cfg(feature = "metrics")]
+    let started = std::time::Instant::now();
+    #[cfg(feature = "metrics")]
+    ctx.metrics.task_received();
+
+    let span = task_span(task_index);
+    let status = async {
+        // Never sign the same task twice, e.g. when its event is replayed after a reorg.
+        // Claimed before waiting for a slot, so a duplicate never holds or queues for one
+        if !ctx.processed_tasks.claim(task_index) {
+            info!("Skipping task {}: already processed", task_index);
+            return TaskStatus::Duplicate.code();
+        }
+
+        // Bound the number of tasks fetching and signing at once, waiting for a slot if needed
+        let Some(_permit) = ctx.task_limiter.acquire().await else {
+            error!(
+                "Rejecting task {}: {} tasks running and {} queued",
+                task_index,
+                ctx.task_limiter.max_concurrent(),
+                ctx.task_limiter.max_queued()
+            );
+            return finish(ctx, task_index, TaskStatus::Rejected);
+        };
+
+        // Set once the first response is handed to the aggregator client
+        let sending = AtomicBool::new(false);
+        let task = run_task(
+            ctx,
+            load_signer,
+            task_created_block,
+            quorum_numbers,
+            task_index,
+            &span,
+            &sending,
+        );
+        // The deadline covers the task's own work, not the time it waited for its slot
+        let Some(deadline) = ctx.task_deadline else {
+            return task.await;
+        };
+        match timeout(deadline, task).await {
+            Ok(status) => status,
+            Err(_) => {
+                error!(
+                    "Task {} did not finish within {:?}, cancelling it",
+                    task_index, deadline
+                );
+                if sending.load(Ordering::SeqCst) {
+                    // Responses for some of its quorums may already have reached the
+                    // aggregator, so a redelivered event must not sign and send them again
+                    error!(
+                        "Task {} finished with status {}, its response was partly sent so it stays claimed",
+                        task_index,
+                        TaskStatus::Timeout
+                    );
+                    TaskStatus::Timeout.code()
+                } else {
+                    finish(ctx, task_index, TaskStatus::Timeout)
+                }
+            }
+        }
+    }
+    .instrument(span.clone())
+    .await;
+
+    #[cfg(feature = "metrics")]
+    record_task_metrics(ctx, status, started.elapsed());
+    status
+}
+
+#[cfg(feature = "metrics")]
+fn record_task_metrics(ctx: &EigenSquareContext, status: u32, latency: std::time::Duration) {
+    match TaskStatus::from_code(status) {
+        Some(TaskStatus::ApiFailed) => ctx.metrics.api_failed(),
+        Some(TaskStatus::SendFailed) => ctx.metrics.send_failed(),
+        _ => {}
+    }
+    ctx.metrics.observe_task_latency(latency);
+}
+
+/// Span covering fetch, sign and send of a task, the operator id and result hash are recorded
+/// once known
+pub fn task_span(task_index: u32) -> Span {
+    info_span!(
+        "task",
+        task_index,
+        operator_id = field::Empty,
+        result_hash = field::Empty,
+        missing_blocks = field::Empty
+    )
+}
+
+async fn run_task(
+    ctx: &EigenSquareContext,
+    load_signer: impl FnOnce() -> Result<Box<dyn TaskSigner>, SignerError>,
+    task_created_block: u32,
+    quorum_numbers: &[u8],
+    task_index: u32,
+    span: &Span,
+    sending: &AtomicBool,
+) -> u32 {
     let client = ctx.client.clone();
     let api_client = ctx.api_client.clone();
 
-    // Get the calculation result from the API
-    let result_hash = match api_client.get_calculation().await {
-        Ok(result) => {
-            info!("Successfully obtained hash from API: {:?}", result);
-            result
-        },
+    // Only sign for the quorums the operator has stake in, there is nothing to attest otherwise
+    let quorums = signing_quorums(
+        &parse_quorum_numbers(Bytes::copy_from_slice(quorum_numbers)),
+        ctx.operator_quorums.as_deref(),
+    );
+    if quorums.is_empty() {
+        info!(
+            "Skipping task {}: operator is in none of its quorums {:?}",
+            task_index, quorum_numbers
+        );
+        return finish(ctx, task_index, TaskStatus::NotInQuorum);
+    }
+
+    // Get the blocks leading up to the task from the API and schedule the task's block
+    let (calculation, schedule, stats) = match api_client
+        .get_calculation_at_detailed(task_created_block.into())
+        .await
+        .and_then(|calculation| {
+            info!(
+                "Successfully obtained blocks from API: {:?}",
+                calculation.block_numbers()
+            );
+            let (schedule, stats) =
+                signed_schedule_for_task(&api_client, &calculation, task_created_block.into())?;
+            Ok((calculation, schedule, stats))
+        }) {
+        Ok(scheduled) => scheduled,
+        Err(e) if e.is_insufficient_blocks() => {
+            warn!("Skipping task {}: {}", task_index, e);
+            return finish(ctx, task_index, TaskStatus::InsufficientBlocks);
+        }
         Err(e) => {
-            error!("Failed to get calculation from API: {}", e);
-            return Ok(0);
+            error!(
+                "Failed to get calculation from API (retryable: {}): {}",
+                e.is_retryable(),
+                e
+            );
+            return finish(ctx, task_index, TaskStatus::ApiFailed);
         }
     };
+    // The live blocks only serve complete windows, so flag tasks the API served in part
+    if calculation.is_partial() {
+        span.record("missing_blocks", calculation.missing_blocks);
+        warn!(
+            "Task {} is signed over a partial block range, {} blocks are missing",
+            task_index, calculation.missing_blocks
+        );
+    }
+    let result_hash =
+        ctx.result_hash_mode
+            .result_hash(schedule.hash(), task_index, task_created_block);
+    span.record("result_hash", field::display(result_hash));
+    let debug_dump = api_client.debug_dump();
+    if debug_dump.is_enabled() {
+        debug_dump.record(
+            "result-preimage",
+            &result_hash_preimage(
+                &schedule,
+                ctx.result_hash_mode,
+                task_index,
+                task_created_block,
+            ),
+        );
+    }
+    info!(
+        "Scheduled {} transactions of block {:?} into {} batches (largest: {}, estimated speedup: {:.2}x, critical path: {} gas)",
+        stats.num_txs,
+        schedule.block_hash,
+        stats.num_batches,
+        stats.max_batch_size,
+        stats.estimated_speedup,
+        schedule.critical_path()
+    );
+
+    // Publish the schedule before signing, so every signed hash can be checked against it
+    if let Some(artifacts) = &ctx.schedule_artifacts {
+        let artifact = ScheduleArtifact::new(task_index, schedule.clone())
+            .with_result_hash_mode(ctx.result_hash_mode, task_created_block);
+        if let Err(e) = artifacts.write_artifact(&artifact) {
+            error!("Failed to write schedule of task {}: {}", task_index, e);
+        }
+    }
+    // Record the blocks the hash was computed from, so a disagreement can be replayed later
+    if let Some(recordings) = &ctx.api_recordings {
+        let recording = ApiRecording::new(task_index, &calculation, result_hash)
+            .with_result_hash_mode(ctx.result_hash_mode, task_created_block);
+        if let Err(e) = recordings.write(&recording) {
+            error!(
+                "Failed to record API response of task {}: {}",
+                task_index, e
+            );
+        }
+    }
 
-    // Create task response with the API result hash
+    // Create task response with the schedule hash
     let task_response = TaskResponse {
         referenceTaskIndex: task_index,
         resultHash: result_hash,
     };
     debug!("Created task response with hash: {:?}", result_hash);
 
-    let bn254_public = ctx.keystore().first_local::<ArkBlsBn254>().unwrap();
-    let bn254_secret = match ctx.keystore().expose_bls_bn254_secret(&bn254_public) {
-        Ok(s) => match s {
-            Some(s) => s,
-            None => return Ok(0),
-        },
-        Err(_) => return Ok(0),
+    let signer = match load_signer() {
+        Ok(signer) => signer,
+        Err(e) => {
+            error!(
+                "Failed to load {} key from keystore: {}",
+                ctx.signature_scheme, e
+            );
+            return finish(ctx, task_index, e.status());
+        }
     };
-    let bls_key_pair = match BlsKeyPair::new(bn254_secret.0.to_string()) {
-        Ok(pair) => pair,
-        Err(_) => return Ok(0),
+
+    // Sign the Hashed Message and send it to the Aggregator
+    let payload = match signer
+        .sign_task_response(task_response, &ctx.signing_domain)
+        .await
+    {
+        Ok(payload) => payload,
+        Err(e) => {
+            error!("Failed to sign task response: {}", e);
+            return finish(ctx, task_index, e.status());
+        }
     };
-    let operator_id = operator_id_from_key(bls_key_pair.clone());
-
-    // Sign the Hashed Message and send it to the BLS Aggregator
-    let msg_hash = keccak256(<TaskResponse as SolType>::abi_encode(&task_response));
-    let signed_response = SignedTaskResponse {
-        task_response,
-        signature: bls_key_pair.sign_message(msg_hash.as_ref()),
-        operator_id,
+    span.record("operator_id", field::display(payload.operator()));
+
+    // Never submit a signature the aggregator would reject, e.g. from a corrupted keystore
+    if !signer.verify(&payload, &ctx.signing_domain) {
+        error!(
+            "Own {} signature of task {} does not verify against the operator's key, not sending it",
+            payload.scheme(),
+            task_index
+        );
+        return finish(ctx, task_index, TaskStatus::SigningFailed);
+    }
+    #[cfg(feature = "metrics")]
+    ctx.metrics.task_signed();
+
+    for quorum_number in quorums {
+        let payload = payload.clone().with_quorum_number(quorum_number);
+        // One greppable line per response, the full payload only at debug level
+        if ctx.dry_run {
+            info!(
+                "Dry run, not sending signed task response to Aggregator: {}",
+                payload
+            );
+            debug!("Signed task response: {:#?}", payload);
+            continue;
+        }
+
+        info!("Sending signed task response to Aggregator: {}", payload);
+        debug!("Signed task response: {:#?}", payload);
+        sending.store(true, Ordering::SeqCst);
+        if let Err(e) = client.send_task_payload(payload.clone()).await {
+            error!(
+                "Failed to send signed task response for quorum {} (retryable: {}): {}",
+                quorum_number,
+                e.is_retryable(),
+                e
+            );
+            // Keep the signature around so it can still make the quorum once the aggregator is
+            // back. The queue holds one response per task, which carries the same signature for
+            // every quorum
+            if e.is_retryable() {
+                if let (Some(queue), SignedTaskPayload::Bls(signed_response)) =
+                    (&ctx.response_queue, &payload)
+                {
+                    match queue.push(&signed_response) {
+                        Ok(()) => info!("Queued signed task response for later delivery"),
+                        Err(queue_error) => {
+                            error!("Failed to queue signed task response: {}", queue_error)
+                        }
+                    }
+                }
+            }
+            return finish(ctx, task_index, TaskStatus::SendFailed);
+        }
+    }
+    if ctx.dry_run {
+        return finish(ctx, task_index, TaskStatus::Ok);
+    }
+
+    // Remember the task across restarts so a replayed event is not submitted again
+    if let Err(e) = ctx.processed_tasks.record_sent(task_index, result_hash) {
+        error!("Failed to record task {} as sent: {}", task_index, e);
+    }
+    finish(ctx, task_index, TaskStatus::Ok)
+}
+
+/// Loads the signer for the signature scheme configured on the context
+fn load_task_signer(ctx: &EigenSquareContext) -> Result<Box<dyn TaskSigner>, SignerError> {
+    if let Some(signer) = &ctx.task_signer {
+        return Ok(Box::new(signer.clone()));
+    }
+    let keystore = ctx.keystore();
+    match ctx.signature_scheme {
+        SignatureScheme::Bls => Ok(Box::new(bls_task_signer(ctx, &keystore)?)),
+        SignatureScheme::Ecdsa => Ok(Box::new(load_ecdsa_signer(&keystore)?)),
+    }
+}
+
+/// Loads the signer of a `backend` holding the operator's key outside the keystore, to be
+/// shared by every task, or returns `None` for [`SignerBackend::Keystore`] whose key is
+/// loaded by each task
+pub async fn preload_task_signer(
+    ctx: &EigenSquareContext,
+    settings: &SignerSettings,
+) -> Result<Option<Arc<dyn TaskSigner>>, SignerError> {
+    let signer: Arc<dyn TaskSigner> = match (settings.backend, ctx.signature_scheme) {
+        (SignerBackend::Keystore, _) => return Ok(None),
+        (SignerBackend::Env, SignatureScheme::Bls) => {
+            let secret = settings
+                .bls_secret
+                .as_ref()
+                .ok_or(KeystoreError::MissingSecret)?;
+            Arc::new(bls_task_signer(
+                ctx,
+                &BlsSecret(secret.expose().to_string()),
+            )?)
+        }
+        (SignerBackend::Env, SignatureScheme::Ecdsa) => {
+            let key = settings.ecdsa_key.as_ref().ok_or_else(|| {
+                SignerError::NoEcdsaKey("OPERATOR_ECDSA_KEY is not set".to_string())
+            })?;
+            Arc::new(ecdsa_signer_from_key(key.expose())?)
+        }
+        (SignerBackend::Remote, scheme) => Arc::new(connect_remote_signer(settings, scheme).await?),
     };
+    Ok(Some(signer))
+}
 
-    info!(
-        "Sending signed task response to BLS Aggregator: {:#?}",
-        signed_response
-    );
-    if let Err(e) = client.send_signed_task_response(signed_response).await {
-        error!("Failed to send signed task response: {:?}", e);
-        return Ok(0);
+/// Connects to the remote signing service configured in `settings` and fetches its `scheme` key
+pub async fn connect_remote_signer(
+    settings: &SignerSettings,
+    scheme: SignatureScheme,
+) -> Result<RemoteTaskSigner, SignerError> {
+    let url = settings
+        .remote_url
+        .clone()
+        .ok_or_else(|| SignerError::Remote("REMOTE_SIGNER_URL is not set".to_string()))?;
+    let transport = HttpSigningTransport::new(url, settings.remote_timeout())?;
+    RemoteTaskSigner::connect(Arc::new(transport), scheme).await
+}
+
+/// Loads the BLS signer from `keystore`, caching the operator id derived from its key
+///
+/// With the `operator-id-override` feature, a configured
+/// [`operator_id_override`](EigenSquareContext::operator_id_override) takes precedence over the
+/// derived id.
+pub fn bls_task_signer<K: BlsKeystore>(
+    ctx: &EigenSquareContext,
+    keystore: &K,
+) -> Result<BlsTaskSigner, SignerError> {
+    let key_pair = load_bls_key_pair(keystore)?;
+    #[cfg(feature = "operator-id-override")]
+    if let Some(operator_id) = ctx.operator_id_override {
+        blueprint_sdk::logging::warn!(
+            "Signing as overridden operator id {} instead of the id derived from the BLS key",
+            operator_id
+        );
+        return Ok(BlsTaskSigner::new(key_pair, operator_id));
     }
+    let operator_id = ctx
+        .operator_id
+        .get_or_init(|| operator_id_from_key(key_pair.clone()));
+    Ok(BlsTaskSigner::new(key_pair, operator_id))
+}
+
+/// Logs the outcome of a task so failures can be grepped by status, and returns its job code.
+///
+/// Tasks that failed before anything was signed are released again, so that a redelivered
+/// event for them gets another chance. So are cancelled tasks, which were never recorded as
+/// sent.
+fn finish(ctx: &EigenSquareContext, task_index: u32, status: TaskStatus) -> u32 {
+    if matches!(
+        status,
+        TaskStatus::ApiFailed
+            | TaskStatus::NoKey
+            | TaskStatus::SigningFailed
+            | TaskStatus::Rejected
+            | TaskStatus::Timeout
+            | TaskStatus::InsufficientBlocks
+    ) {
+        ctx.processed_tasks.release(task_index);
+    }
+
+    if matches!(
+        status,
+        TaskStatus::Ok | TaskStatus::NotInQuorum | TaskStatus::InsufficientBlocks
+    ) {
+        info!("Task {} finished with status {}", task_index, status);
+    } else {
+        error!("Task {} finished with status {}", task_index, status);
+    }
+    status.code()
+}
+
+/// Builds the parallel schedule attested to for a task, i.e. the schedule of the task's
+/// creation block `task_created_block` within the calculation window, along with its stats
+pub fn schedule_for_task(
+    calculation: &Calculation,
+    task_created_block: u64,
+) -> Result<(ParallelSchedule, ScheduleStats), ApiClientError> {
+    if calculation.blocks.is_empty() {
+        return Err(ApiClientError::EmptyResponse);
+    }
+    let block = calculation
+        .blocks
+        .iter()
+        .find(|block| block.number_u64().ok() == Some(task_created_block))
+        .ok_or(ApiClientError::MissingCreationBlock(task_created_block))?;
+    ParallelSchedule::from_block_with_stats(block)
+}
+
+/// Like [`schedule_for_task`], but binds the schedule to the calculation hash when
+/// `api_client` hashes more block data than the block hashes, so every setting the hash
+/// depends on is signed along with the schedule
+pub fn signed_schedule_for_task(
+    api_client: &ApiClient,
+    calculation: &Calculation,
+    task_created_block: u64,
+) -> Result<(ParallelSchedule, ScheduleStats), ApiClientError> {
+    let (schedule, stats) = schedule_for_task(calculation, task_created_block)?;
+    if api_client.binds_block_data() {
+        return Ok((schedule.with_block_data_hash(calculation.hash), stats));
+    }
+    Ok((schedule, stats))
+}
 
-    Ok(1)
+/// Renders the data the signed result hash of a task is computed from: the canonical encoding
+/// of its schedule, which hashes to the schedule hash, and the task field `mode` mixes into it
+pub fn result_hash_preimage(
+    schedule: &ParallelSchedule,
+    mode: ResultHashMode,
+    task_index: u32,
+    task_created_block: u32,
+) -> String {
+    let mut canonical = schedule.clone();
+    canonical.canonicalize();
+    let task_field = match mode {
+        ResultHashMode::Schedule => String::new(),
+        ResultHashMode::TaskIndex => format!(" of task index {}", task_index),
+        ResultHashMode::TaskCreatedBlock => {
+            format!(" of task created block {}", task_created_block)
+        }
+    };
+    format!(
+        "schedule: {}\nschedule hash: {}\nresult hash ({}{}): {}",
+        hex::encode_prefixed(canonical.encode()),
+        schedule.hash(),
+        mode,
+        task_field,
+        mode.result_hash(schedule.hash(), task_index, task_created_block)
+    )
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("Unknown result hash mode {0}, expected `schedule`, `task_index` or `task_created_block`")]
+pub struct UnknownResultHashMode(pub String);
+
+/// What a task's `resultHash` is computed from.
+///
+/// By default it is the hash of the task's schedule, so two tasks created for the same block
+/// sign the same hash. The other modes mix a field of the task into it, making every task's
+/// hash unique. Every operator and verifier has to use the same mode, as it only depends on
+/// the task, not on the operator.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultHashMode {
+    /// The schedule hash
+    #[default]
+    Schedule,
+    /// `keccak256(abi.encode(scheduleHash, taskIndex))`
+    TaskIndex,
+    /// `keccak256(abi.encode(scheduleHash, taskCreatedBlock))`
+    TaskCreatedBlock,
+}
+
+impl ResultHashMode {
+    /// Returns the result hash of the task `task_index` created at `task_created_block`, whose
+    /// schedule hashes to `schedule_hash`
+    pub fn result_hash(
+        self,
+        schedule_hash: B256,
+        task_index: u32,
+        task_created_block: u32,
+    ) -> B256 {
+        match self {
+            ResultHashMode::Schedule => schedule_hash,
+            ResultHashMode::TaskIndex => keccak256((schedule_hash, task_index).abi_encode()),
+            ResultHashMode::TaskCreatedBlock => {
+                keccak256((schedule_hash, task_created_block).abi_encode())
+            }
+        }
+    }
+}
+
+impl FromStr for ResultHashMode {
+    type Err = UnknownResultHashMode;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "schedule" => Ok(ResultHashMode::Schedule),
+            "task_index" => Ok(ResultHashMode::TaskIndex),
+            "task_created_block" => Ok(ResultHashMode::TaskCreatedBlock),
+            _ => Err(UnknownResultHashMode(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for ResultHashMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResultHashMode::Schedule => f.write_str("schedule"),
+            ResultHashMode::TaskIndex => f.write_str("task_index"),
+            ResultHashMode::TaskCreatedBlock => f.write_str("task_created_block"),
+        }
+    }
 }
 
 /// Generate the Operator ID from the BLS Keypair
 pub fn operator_id_from_key(key: BlsKeyPair) -> OperatorId {
-    let pub_key = key.public_key();
-    let pub_key_affine = pub_key.g1();
+    operator_id_from_public_key(&key.public_key())
+}
 
-    let x_int: num_bigint::BigUint = pub_key_affine.x.into();
-    let y_int: num_bigint::BigUint = pub_key_affine.y.into();
+/// Checks the quorum threshold percentage of a task, which must be within `1..=100`.
+///
+/// The value comes straight from an event, so a buggy task manager must not be able to crash
+/// the listener with it.
+pub fn parse_quorum_threshold_percentage(value: u32) -> Result<u8, Error> {
+    u8::try_from(value)
+        .ok()
+        .filter(|percentage| (1..=100).contains(percentage))
+        .ok_or_else(|| {
+            Error::Conversion(format!(
+                "Quorum threshold percentage must be within 1..=100, got {}",
+                value
+            ))
+        })
+}
 
-    let x_bytes = x_int.to_bytes_be();
-    let y_bytes = y_int.to_bytes_be();
+/// Parses the quorum numbers of a task, which lists one quorum per byte.
+///
+/// The quorums are returned in ascending order without duplicates. Numbers the registry
+/// coordinator does not support are dropped, as no operator can have stake in them.
+pub fn parse_quorum_numbers(quorum_numbers: Bytes) -> Vec<u8> {
+    let quorums: BTreeSet<u8> = quorum_numbers
+        .iter()
+        .copied()
+        .filter(|&quorum| quorum < MAX_QUORUM_COUNT)
+        .collect();
+    quorums.into_iter().collect()
+}
 
-    keccak256([x_bytes, y_bytes].concat())
+/// Returns the quorums of a task the operator signs for, i.e. the task's quorums the operator
+/// belongs to. An operator whose quorums are not known signs for every quorum of the task.
+pub fn signing_quorums(task_quorums: &[u8], operator_quorums: Option<&[u8]>) -> Vec<u8> {
+    match operator_quorums {
+        Some(operator_quorums) => task_quorums
+            .iter()
+            .copied()
+            .filter(|quorum| operator_quorums.contains(quorum))
+            .collect(),
+        None => task_quorums.to_vec(),
+    }
 }
 
 /// Converts the event to inputs.
@@ -119,19 +772,1192 @@ pub fn operator_id_from_key(key: BlsKeyPair) -> OperatorId {
 /// the macro will index all values in the #[job] function
 /// and parse the return type by the index.
 pub async fn convert_event_to_inputs(
-    (event, _log): (
+    (event, log): (
         IncredibleSquaringTaskManager::NewTaskCreated,
         alloy_rpc_types::Log,
     ),
 ) -> Result<Option<(u32, Bytes, u8, u32)>, ProcessorError> {
     let task_index = event.taskIndex;
-    let task_created_block = event.task.taskCreatedBlock;
-    let quorum_numbers = event.task.quorumNumbers;
-    let quorum_threshold_percentage = event.task.quorumThresholdPercentage.try_into().unwrap();
-    Ok(Some((
+    // The log's block was reorged out, so the task no longer exists on-chain
+    if log.removed {
+        info!(
+            "Skipping task {}: block {:?} was reorged out",
+            task_index, log.block_hash
+        );
+        return Ok(None);
+    }
+    task_inputs(
+        event.task.taskCreatedBlock,
+        event.task.quorumNumbers,
+        event.task.quorumThresholdPercentage,
+        task_index,
+    )
+    .map(Some)
+}
+
+/// Builds the inputs of [`calculate_task`] from the fields of a `NewTaskCreated` event,
+/// rejecting a quorum threshold percentage outside of `1..=100`.
+///
+/// This is the conversion done by [`convert_event_to_inputs`], without the event and log
+/// types around it.
+pub fn task_inputs(
+    task_created_block: u32,
+    quorum_numbers: Bytes,
+    quorum_threshold_percentage: u32,
+    task_index: u32,
+) -> Result<(u32, Bytes, u8, u32), ProcessorError> {
+    let quorum_threshold_percentage =
+        parse_quorum_threshold_percentage(quorum_threshold_percentage)?;
+    Ok((
         task_created_block,
         quorum_numbers,
         quorum_threshold_percentage,
         task_index,
-    )))
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::{
+        Block, BlockTransaction, HashMode, HttpRequest, HttpResponse, HttpTransport,
+        TASK_BLOCK_WINDOW,
+    };
+    use crate::constants::PROCESSED_TASKS_WINDOW;
+    use crate::contexts::processed_tasks::ProcessedTasks;
+    use crate::contexts::schedule_artifacts::ScheduleArtifacts;
+    use crate::contexts::task_limiter::TaskLimiter;
+    use crate::contexts::x_square::OperatorIdCache;
+    use crate::deps::AccessListItem;
+    use crate::scheduler::ParallelSchedule;
+    use crate::signer::{task_response_digest, EcdsaTaskSigner, SigningDomain};
+    use crate::test_support::{MockAggregator, MockApi};
+    use alloy_primitives::Address;
+    use alloy_signer_local::PrivateKeySigner;
+    use async_trait::async_trait;
+    use blueprint_sdk::config::GadgetConfiguration;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    const BLOCK_HASH: &str = "0x0a885e8a3a5cb03e0540e7ef65734c46189f17a65596009570a227d3abb6d466";
+    /// Number of the block in [`calculation_with_transactions`], the task's creation block
+    const CREATED_BLOCK: u64 = 0x10;
+
+    fn calculation_with_transactions(transactions: Vec<BlockTransaction>) -> Calculation {
+        let block = Block {
+            hash: BLOCK_HASH.to_string(),
+            number: "0x10".to_string(),
+            timestamp: "0x67c2a1b0".to_string(),
+            transactions_root: B256::ZERO.to_string(),
+            parent_hash: B256::ZERO.to_string(),
+            state_root: None,
+            transactions,
+        };
+        Calculation {
+            hash: B256::ZERO,
+            blocks: vec![block],
+            missing_blocks: 0,
+        }
+    }
+
+    fn write_tx(address: u8) -> BlockTransaction {
+        BlockTransaction {
+            hash: None,
+            gas: None,
+            access_list: Some(vec![AccessListItem {
+                address: Address::repeat_byte(address),
+                storage_keys: vec![B256::ZERO],
+            }]),
+        }
+    }
+
+    fn signed_digest(calculation: &Calculation) -> B256 {
+        let (schedule, _) = schedule_for_task(calculation, CREATED_BLOCK).unwrap();
+        task_response_digest(&TaskResponse {
+            referenceTaskIndex: 7,
+            resultHash: schedule.hash(),
+        })
+    }
+
+    #[test]
+    fn test_result_hash_modes() {
+        let transactions = vec![write_tx(1), write_tx(2), write_tx(1)];
+        let (schedule, _) = schedule_for_task(
+            &calculation_with_transactions(transactions.clone()),
+            CREATED_BLOCK,
+        )
+        .unwrap();
+        let schedule_hash = schedule.hash();
+
+        // By default tasks over the same blocks share the schedule hash
+        let mode = ResultHashMode::default();
+        assert_eq!(mode.result_hash(schedule_hash, 7, 42), schedule_hash);
+        assert_eq!(
+            mode.result_hash(schedule_hash, 7, 42),
+            mode.result_hash(schedule_hash, 8, 43)
+        );
+
+        // Mixing in the task index makes each task's hash unique for identical blocks
+        let mode = ResultHashMode::TaskIndex;
+        let first = mode.result_hash(schedule_hash, 7, 42);
+        assert_ne!(first, schedule_hash);
+        assert_ne!(first, mode.result_hash(schedule_hash, 8, 42));
+        assert_eq!(first, mode.result_hash(schedule_hash, 7, 43));
+        // Another operator fetching its own copy of the blocks derives the same hash
+        let (other_copy, _) =
+            schedule_for_task(&calculation_with_transactions(transactions), CREATED_BLOCK).unwrap();
+        assert_eq!(mode.result_hash(other_copy.hash(), 7, 42), first);
+
+        let mode = ResultHashMode::TaskCreatedBlock;
+        assert_ne!(
+            mode.result_hash(schedule_hash, 7, 42),
+            mode.result_hash(schedule_hash, 7, 43)
+        );
+        assert_eq!(
+            mode.result_hash(schedule_hash, 7, 42),
+            mode.result_hash(schedule_hash, 8, 42)
+        );
+    }
+
+    #[test]
+    fn test_parse_result_hash_mode() {
+        assert_eq!("schedule".parse(), Ok(ResultHashMode::Schedule));
+        assert_eq!(" Task_Index ".parse(), Ok(ResultHashMode::TaskIndex));
+        assert_eq!(
+            "task_created_block".parse(),
+            Ok(ResultHashMode::TaskCreatedBlock)
+        );
+        assert_eq!(
+            "block".parse::<ResultHashMode>(),
+            Err(UnknownResultHashMode("block".to_string()))
+        );
+        for mode in [
+            ResultHashMode::Schedule,
+            ResultHashMode::TaskIndex,
+            ResultHashMode::TaskCreatedBlock,
+        ] {
+            assert_eq!(mode.to_string().parse(), Ok(mode));
+            assert_eq!(
+                serde_json::to_value(mode).unwrap(),
+                serde_json::Value::String(mode.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_operators_sign_identical_schedule_hashes() {
+        let transactions = vec![write_tx(1), write_tx(2), write_tx(1)];
+        let first_operator = BlsKeyPair::new("12345".to_string()).unwrap();
+        let second_operator = BlsKeyPair::new("67890".to_string()).unwrap();
+
+        // Every operator fetches its own copy of the same blocks
+        let first_digest = signed_digest(&calculation_with_transactions(transactions.clone()));
+        let second_digest = signed_digest(&calculation_with_transactions(transactions.clone()));
+        assert_eq!(first_digest, second_digest);
+        assert_ne!(
+            operator_id_from_key(first_operator),
+            operator_id_from_key(second_operator)
+        );
+
+        let (schedule, stats) =
+            schedule_for_task(&calculation_with_transactions(transactions), CREATED_BLOCK).unwrap();
+        assert_eq!(schedule.batches, vec![vec![0, 1], vec![2]]);
+        assert_eq!(stats.num_batches, 2);
+
+        // A different plan must produce a different signed hash
+        let serial = vec![write_tx(1), write_tx(1), write_tx(1)];
+        assert_ne!(
+            signed_digest(&calculation_with_transactions(serial)),
+            first_digest
+        );
+    }
+
+    #[test]
+    fn test_schedule_for_task_without_blocks() {
+        let calculation = Calculation {
+            hash: B256::ZERO,
+            blocks: vec![],
+            missing_blocks: 0,
+        };
+        assert!(matches!(
+            schedule_for_task(&calculation, CREATED_BLOCK),
+            Err(ApiClientError::EmptyResponse)
+        ));
+    }
+
+    #[test]
+    fn test_schedule_for_task_schedules_the_creation_block() {
+        let mut calculation = calculation_with_transactions(vec![write_tx(1), write_tx(2)]);
+        let (created, _) = schedule_for_task(&calculation, CREATED_BLOCK).unwrap();
+
+        // The API returned a block past the task's creation block, which must not be signed
+        let mut later = calculation.blocks[0].clone();
+        later.number = "0x11".to_string();
+        later.transactions = vec![write_tx(1), write_tx(1)];
+        calculation.blocks.push(later);
+        let (schedule, _) = schedule_for_task(&calculation, CREATED_BLOCK).unwrap();
+        assert_eq!(schedule.hash(), created.hash());
+        assert_eq!(schedule.batches, vec![vec![0, 1]]);
+
+        // Without the creation block there is nothing to attest to
+        calculation.blocks.remove(0);
+        assert!(matches!(
+            schedule_for_task(&calculation, CREATED_BLOCK),
+            Err(ApiClientError::MissingCreationBlock(CREATED_BLOCK))
+        ));
+    }
+
+    #[test]
+    fn test_signed_schedule_binds_hashed_block_data() {
+        let mut calculation = calculation_with_transactions(vec![write_tx(1), write_tx(2)]);
+        calculation.hash = B256::repeat_byte(0x11);
+        let (schedule, _) = schedule_for_task(&calculation, CREATED_BLOCK).unwrap();
+
+        // By default only the schedule of the block is signed
+        let (signed, _) =
+            signed_schedule_for_task(&ApiClient::new(), &calculation, CREATED_BLOCK).unwrap();
+        assert_eq!(signed.hash(), schedule.hash());
+
+        // Hashing the roots too binds the data they were hashed from
+        let with_roots = ApiClient::new().with_include_tx_root(true);
+        let (signed, _) =
+            signed_schedule_for_task(&with_roots, &calculation, CREATED_BLOCK).unwrap();
+        assert_eq!(signed.block_data_hash, Some(calculation.hash));
+        assert_ne!(signed.hash(), schedule.hash());
+
+        let mut other = calculation.clone();
+        other.hash = B256::repeat_byte(0x22);
+        let raw_bytes = ApiClient::new().with_hash_mode(HashMode::RawBytes);
+        let (first, _) = signed_schedule_for_task(&raw_bytes, &calculation, CREATED_BLOCK).unwrap();
+        let (second, _) = signed_schedule_for_task(&raw_bytes, &other, CREATED_BLOCK).unwrap();
+        assert_ne!(first.hash(), second.hash());
+    }
+
+    #[test]
+    fn test_result_hash_preimage_hashes_to_the_signed_hash() {
+        let (schedule, _) = schedule_for_task(
+            &calculation_with_transactions(vec![write_tx(2), write_tx(1)]),
+            CREATED_BLOCK,
+        )
+        .unwrap();
+        let preimage = result_hash_preimage(&schedule, ResultHashMode::TaskIndex, 7, 42);
+        let lines: Vec<_> = preimage.lines().collect();
+        let encoding = lines[0].strip_prefix("schedule: ").unwrap();
+        assert_eq!(keccak256(hex::decode(encoding).unwrap()), schedule.hash());
+        assert_eq!(
+            lines[2],
+            format!(
+                "result hash (task_index of task index 7): {}",
+                ResultHashMode::TaskIndex.result_hash(schedule.hash(), 7, 42)
+            )
+        );
+    }
+
+    #[test]
+    fn test_cached_operator_id_matches_fresh_computation() {
+        let key_pair = BlsKeyPair::new("12345".to_string()).unwrap();
+        let cache = OperatorIdCache::default();
+        let shared = cache.clone();
+        assert_eq!(cache.get(), None);
+
+        let cached = cache.get_or_init(|| operator_id_from_key(key_pair.clone()));
+
+        assert_eq!(cached, operator_id_from_key(key_pair.clone()));
+        assert_eq!(shared.get(), Some(cached));
+        // Later lookups never recompute, even with a different key
+        let other = BlsKeyPair::new("67890".to_string()).unwrap();
+        assert_eq!(shared.get_or_init(|| operator_id_from_key(other)), cached);
+    }
+
+    #[cfg(feature = "operator-id-override")]
+    #[tokio::test]
+    async fn test_operator_id_override_takes_precedence() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator);
+        let derived = operator_id_from_key(BlsKeyPair::new("12345".to_string()).unwrap());
+        ctx.operator_id_override = Some(OperatorId::repeat_byte(0x07));
+
+        let signer = bls_task_signer(&ctx, &BlsSecret("12345".to_string())).unwrap();
+        let payload = signer
+            .sign_task_response(
+                TaskResponse {
+                    referenceTaskIndex: 7,
+                    resultHash: B256::repeat_byte(0xab),
+                },
+                &ctx.signing_domain,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            payload.operator(),
+            OperatorId::repeat_byte(0x07).to_string()
+        );
+        assert_ne!(OperatorId::repeat_byte(0x07), derived);
+        // The derived id is neither used nor cached while the override is set
+        assert_eq!(ctx.operator_id.get(), None);
+
+        ctx.operator_id_override = None;
+        let signer = bls_task_signer(&ctx, &BlsSecret("12345".to_string())).unwrap();
+        assert!(!signer.verify(&payload, &ctx.signing_domain));
+        assert_eq!(ctx.operator_id.get(), Some(derived));
+    }
+
+    fn new_task_event(
+        quorum_threshold_percentage: u32,
+    ) -> IncredibleSquaringTaskManager::NewTaskCreated {
+        IncredibleSquaringTaskManager::NewTaskCreated {
+            taskIndex: 3,
+            task: crate::IIncredibleSquaringTaskManager::Task {
+                taskCreatedBlock: 42,
+                quorumNumbers: Bytes::from_static(&[0]),
+                quorumThresholdPercentage: quorum_threshold_percentage,
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_quorum_threshold_percentage() {
+        for valid in [1, 67, 100] {
+            assert_eq!(
+                parse_quorum_threshold_percentage(valid).unwrap(),
+                valid as u8
+            );
+        }
+        for invalid in [0, 101, 255, 256, u32::MAX] {
+            assert!(matches!(
+                parse_quorum_threshold_percentage(invalid),
+                Err(Error::Conversion(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_quorum_numbers() {
+        assert_eq!(parse_quorum_numbers(Bytes::from_static(&[0])), vec![0]);
+        assert_eq!(
+            parse_quorum_numbers(Bytes::from_static(&[3, 0, 1])),
+            vec![0, 1, 3]
+        );
+        // Duplicates and unsupported quorums are dropped
+        assert_eq!(
+            parse_quorum_numbers(Bytes::from_static(&[1, 192, 1, 255, 0])),
+            vec![0, 1]
+        );
+        assert!(parse_quorum_numbers(Bytes::new()).is_empty());
+    }
+
+    #[test]
+    fn test_signing_quorums() {
+        // Operators that do not track their quorums sign for all of them
+        assert_eq!(signing_quorums(&[0, 1], None), vec![0, 1]);
+        assert_eq!(signing_quorums(&[0], Some(&[0, 1])), vec![0]);
+        assert_eq!(signing_quorums(&[0, 1, 2], Some(&[2, 0])), vec![0, 2]);
+        assert!(signing_quorums(&[0, 1], Some(&[2])).is_empty());
+    }
+
+    #[test]
+    fn test_task_inputs_pass_fields_through() {
+        let quorum_numbers = Bytes::from_static(&[0, 1]);
+        for valid in [1, 67, 100] {
+            assert_eq!(
+                task_inputs(42, quorum_numbers.clone(), valid, 3).unwrap(),
+                (42, quorum_numbers.clone(), valid as u8, 3)
+            );
+        }
+    }
+
+    #[test]
+    fn test_task_inputs_reject_invalid_quorum_threshold() {
+        for invalid in [0, 101, 256, u32::MAX] {
+            assert!(matches!(
+                task_inputs(42, Bytes::new(), invalid, 3),
+                Err(blueprint_sdk::event_listeners::core::Error::ProcessorError(
+                    _
+                ))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_convert_event_rejects_invalid_quorum_threshold() {
+        let (task_created_block, _, percentage, task_index) =
+            convert_event_to_inputs((new_task_event(100), alloy_rpc_types::Log::default()))
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!((task_created_block, percentage, task_index), (42, 100, 3));
+
+        for invalid in [0, 101, 1000] {
+            assert!(convert_event_to_inputs((
+                new_task_event(invalid),
+                alloy_rpc_types::Log::default(),
+            ))
+            .await
+            .is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_convert_event_skips_removed_logs() {
+        let removed = alloy_rpc_types::Log {
+            removed: true,
+            ..Default::default()
+        };
+        let inputs = convert_event_to_inputs((new_task_event(100), removed))
+            .await
+            .unwrap();
+        assert!(inputs.is_none());
+    }
+
+    /// Helper API that accepts requests but never answers them
+    #[derive(Debug)]
+    struct HangingApi;
+
+    #[async_trait]
+    impl HttpTransport for HangingApi {
+        async fn send(&self, _request: HttpRequest) -> Result<HttpResponse, ApiClientError> {
+            std::future::pending().await
+        }
+    }
+
+    /// Helper API tracking how many requests it serves at the same time
+    #[derive(Debug)]
+    struct ConcurrencyTrackingApi {
+        body: String,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl HttpTransport for ConcurrencyTrackingApi {
+        async fn send(&self, _request: HttpRequest) -> Result<HttpResponse, ApiClientError> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(HttpResponse {
+                status: 200,
+                body: self.body.clone(),
+                retry_after: None,
+            })
+        }
+    }
+
+    fn bls_signer(
+        ctx: &EigenSquareContext,
+        secret: &'static str,
+    ) -> Result<Box<dyn TaskSigner>, SignerError> {
+        Ok(Box::new(bls_task_signer(
+            ctx,
+            &BlsSecret(secret.to_string()),
+        )?))
+    }
+
+    /// Transactions writing the same slot of accounts 1, 2 and 1, scheduled as `[[0, 1], [2]]`
+    fn conflicting_transactions() -> serde_json::Value {
+        let write = |address: Address| {
+            serde_json::json!({
+                "access_list": [{ "address": address, "storage_keys": [B256::ZERO] }],
+            })
+        };
+        serde_json::json!([
+            write(Address::repeat_byte(1)),
+            write(Address::repeat_byte(2)),
+            write(Address::repeat_byte(1)),
+        ])
+    }
+
+    /// Helper API response carrying the task's block with `transactions`
+    fn block_body(transactions: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "status": "success",
+            "message": "ok",
+            "data": [{
+                "hash": BLOCK_HASH,
+                "number": "0x2a",
+                "timestamp": "0x67c2a1b0",
+                "transactions_root": B256::ZERO.to_string(),
+                "parent_hash": B256::ZERO.to_string(),
+                "transactions": transactions,
+            }],
+        })
+    }
+
+    fn test_context(
+        transactions: serde_json::Value,
+        aggregator: Arc<MockAggregator>,
+    ) -> EigenSquareContext {
+        EigenSquareContext {
+            client: aggregator,
+            api_client: ApiClient::new().with_transport(Arc::new(MockApi {
+                body: block_body(transactions).to_string(),
+            })),
+            response_queue: None,
+            schedule_artifacts: None,
+            api_recordings: None,
+            processed_tasks: ProcessedTasks::new(PROCESSED_TASKS_WINDOW),
+            task_limiter: TaskLimiter::new(16, 256),
+            operator_id: OperatorIdCache::default(),
+            signature_scheme: SignatureScheme::Bls,
+            task_signer: None,
+            operator_quorums: None,
+            signing_domain: SigningDomain::new(31337, Address::repeat_byte(0x42)),
+            result_hash_mode: ResultHashMode::default(),
+            dry_run: false,
+            task_deadline: None,
+            #[cfg(feature = "operator-id-override")]
+            operator_id_override: None,
+            #[cfg(feature = "metrics")]
+            metrics: crate::metrics::Metrics::new(),
+            std_config: GadgetConfiguration::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_calculate_task_sends_signed_schedule_hash() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let ctx = test_context(conflicting_transactions(), aggregator.clone());
+
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+
+        let expected =
+            ParallelSchedule::new(BLOCK_HASH.parse().unwrap(), vec![vec![0, 1], vec![2]]);
+        let received = aggregator.received();
+        assert_eq!(received.len(), 1);
+        let SignedTaskPayload::Bls(signed) = &received[0] else {
+            panic!("expected a BLS signed task response");
+        };
+        assert_eq!(signed.task_response.referenceTaskIndex, 7);
+        assert_eq!(signed.task_response.resultHash, expected.hash());
+        let key_pair = BlsKeyPair::new("12345".to_string()).unwrap();
+        assert_eq!(signed.operator_id, operator_id_from_key(key_pair));
+
+        // A replayed event must not be signed again
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Duplicate));
+        assert_eq!(aggregator.received().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tasks_over_the_same_block_sign_distinct_hashes() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.result_hash_mode = ResultHashMode::TaskIndex;
+
+        // Two tasks created for the same block, computed from identical blocks
+        for task_index in [7, 8] {
+            let status =
+                process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], task_index).await;
+            assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+        }
+
+        let schedule_hash =
+            ParallelSchedule::new(BLOCK_HASH.parse().unwrap(), vec![vec![0, 1], vec![2]]).hash();
+        let hashes: Vec<B256> = aggregator
+            .received()
+            .iter()
+            .map(|payload| payload.task_response().resultHash)
+            .collect();
+        assert_eq!(
+            hashes,
+            vec![
+                ResultHashMode::TaskIndex.result_hash(schedule_hash, 7, 42),
+                ResultHashMode::TaskIndex.result_hash(schedule_hash, 8, 42),
+            ]
+        );
+        assert_ne!(hashes[0], hashes[1]);
+
+        // Another operator in the same mode signs the same hash for the task
+        let other = Arc::new(MockAggregator::default());
+        let mut other_ctx = test_context(conflicting_transactions(), other.clone());
+        other_ctx.result_hash_mode = ResultHashMode::TaskIndex;
+        process_task(&other_ctx, || bls_signer(&other_ctx, "67890"), 42, &[0], 8).await;
+        assert_eq!(other.received()[0].task_response().resultHash, hashes[1]);
+    }
+
+    #[tokio::test]
+    async fn test_task_is_signed_for_each_quorum_of_the_operator() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.operator_quorums = Some(vec![0, 2]);
+
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[2, 1, 0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+
+        // One response per shared quorum, all carrying the same signature
+        let received = aggregator.received();
+        let quorums: Vec<_> = received.iter().map(|p| p.quorum_number()).collect();
+        assert_eq!(quorums, vec![Some(0), Some(2)]);
+        let (SignedTaskPayload::Bls(first), SignedTaskPayload::Bls(second)) =
+            (&received[0], &received[1])
+        else {
+            panic!("expected BLS signed task responses");
+        };
+        assert_eq!(
+            first.signature.g1_point().g1(),
+            second.signature.g1_point().g1()
+        );
+        assert_eq!(
+            first.task_response.resultHash,
+            second.task_response.resultHash
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_outside_the_operators_quorums_is_not_signed() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.operator_quorums = Some(vec![1]);
+
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0, 2], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::NotInQuorum));
+        assert!(aggregator.received().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_task_with_too_few_blocks_is_skipped() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        let api_client = ctx.api_client.clone();
+
+        // The API serves a single block, one short of the minimum
+        ctx.api_client = api_client.clone().with_min_blocks(2);
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(
+            TaskStatus::from_code(status),
+            Some(TaskStatus::InsufficientBlocks)
+        );
+        assert!(aggregator.received().is_empty());
+
+        // The task is released, so a redelivery with enough blocks is signed
+        ctx.api_client = api_client.with_min_blocks(1);
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+        assert_eq!(aggregator.received().len(), 1);
+    }
+
+    type Fields = HashMap<String, String>;
+
+    /// Subscriber attributing every event to the `task_index` of the span it was logged in
+    #[derive(Clone, Default)]
+    struct TaskSpans(Arc<Mutex<RecordedSpans>>);
+
+    #[derive(Default)]
+    struct RecordedSpans {
+        spans: Vec<Fields>,
+        entered: Vec<u64>,
+        /// The `task_index` of the entered span, if any, and the message of every event
+        events: Vec<(Option<String>, String)>,
+    }
+
+    struct FieldVisitor<'a>(&'a mut Fields);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl Subscriber for TaskSpans {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+            let mut fields = Fields::new();
+            fields.insert("name".to_string(), attributes.metadata().name().to_string());
+            attributes.record(&mut FieldVisitor(&mut fields));
+            let mut recorded = self.0.lock().unwrap();
+            recorded.spans.push(fields);
+            Id::from_u64(recorded.spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut recorded = self.0.lock().unwrap();
+            let fields = &mut recorded.spans[span.into_u64() as usize - 1];
+            values.record(&mut FieldVisitor(fields));
+        }
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            let mut recorded = self.0.lock().unwrap();
+            let task_index = recorded
+                .entered
+                .last()
+                .and_then(|id| recorded.spans[*id as usize - 1].get("task_index").cloned());
+            let message = fields.remove("message").unwrap_or_default();
+            recorded.events.push((task_index, message));
+        }
+
+        fn enter(&self, span: &Id) {
+            self.0.lock().unwrap().entered.push(span.into_u64());
+        }
+
+        fn exit(&self, span: &Id) {
+            let mut recorded = self.0.lock().unwrap();
+            if let Some(position) = recorded
+                .entered
+                .iter()
+                .rposition(|id| *id == span.into_u64())
+            {
+                recorded.entered.remove(position);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_tasks_log_to_separate_spans() {
+        let subscriber = TaskSpans::default();
+        let _guard = tracing::subscriber::set_default(subscriber.clone());
+
+        let aggregator = Arc::new(MockAggregator::default());
+        let ctx = test_context(conflicting_transactions(), aggregator.clone());
+        let (first, second) = tokio::join!(
+            process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7),
+            process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 8),
+        );
+        assert_eq!(TaskStatus::from_code(first), Some(TaskStatus::Ok));
+        assert_eq!(TaskStatus::from_code(second), Some(TaskStatus::Ok));
+
+        let recorded = subscriber.0.lock().unwrap();
+        let task_spans: Vec<_> = recorded
+            .spans
+            .iter()
+            .filter(|fields| fields["name"] == "task")
+            .collect();
+        assert_eq!(task_spans.len(), 2);
+        let operator_id = operator_id_from_key(BlsKeyPair::new("12345".to_string()).unwrap());
+        for (fields, task_index) in task_spans.iter().zip(["7", "8"]) {
+            assert_eq!(fields["task_index"], task_index);
+            assert_eq!(fields["operator_id"], operator_id.to_string());
+            assert!(fields.contains_key("result_hash"));
+            // The API serves only the creation block of the task's window
+            assert_eq!(
+                fields["missing_blocks"],
+                (TASK_BLOCK_WINDOW - 1).to_string()
+            );
+        }
+
+        // Both tasks logged before either finished, yet every line names its own task
+        let first_finished = recorded
+            .events
+            .iter()
+            .position(|(_, message)| message.contains("finished with status"))
+            .unwrap();
+        let started: Vec<_> = recorded.events[..first_finished]
+            .iter()
+            .filter_map(|(task_index, _)| task_index.as_deref())
+            .collect();
+        assert!(started.contains(&"7") && started.contains(&"8"));
+        for task_index in ["7", "8"] {
+            let finished = format!("Task {} finished with status ok (1)", task_index);
+            assert!(
+                recorded
+                    .events
+                    .iter()
+                    .any(|(span, message)| span.as_deref() == Some(task_index)
+                        && *message == finished)
+            );
+        }
+        assert!(recorded.events.iter().all(|(span, _)| span.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_near_simultaneous_tasks_share_one_api_fetch() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.api_client = ctx
+            .api_client
+            .with_coalesce_window(Duration::from_millis(100));
+
+        let (first, second) = tokio::join!(
+            process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7),
+            process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 8),
+        );
+        assert_eq!(TaskStatus::from_code(first), Some(TaskStatus::Ok));
+        assert_eq!(TaskStatus::from_code(second), Some(TaskStatus::Ok));
+        assert_eq!(ctx.api_client.metrics().requests(), 1);
+
+        // The shared schedule hash was signed and sent for each task
+        let received = aggregator.received();
+        let mut task_indices: Vec<_> = received
+            .iter()
+            .map(|payload| payload.task_response().referenceTaskIndex)
+            .collect();
+        task_indices.sort();
+        assert_eq!(task_indices, vec![7, 8]);
+        assert_eq!(
+            received[0].task_response().resultHash,
+            received[1].task_response().resultHash
+        );
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_metrics_endpoint_counts_tasks() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let ctx = test_context(conflicting_transactions(), aggregator);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let _server = crate::metrics::serve(listener, ctx.metrics.clone(), std::future::pending());
+
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+
+        let mut failing = ctx.clone();
+        failing.api_client = ApiClient::new().with_transport(Arc::new(MockApi {
+            body: "not json".to_string(),
+        }));
+        let status = process_task(&failing, || bls_signer(&failing, "12345"), 42, &[0], 8).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::ApiFailed));
+
+        let url = format!("http://{}{}", address, crate::metrics::METRICS_PATH);
+        let scrape = reqwest::get(url).await.unwrap().text().await.unwrap();
+        for line in [
+            "parallel_exec_tasks_received_total 2",
+            "parallel_exec_tasks_signed_total 1",
+            "parallel_exec_send_failures_total 0",
+            "parallel_exec_api_failures_total 1",
+            "parallel_exec_task_latency_seconds_count 2",
+        ] {
+            assert!(
+                scrape.lines().any(|sample| sample == line),
+                "missing {}",
+                line
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_limiter_bounds_concurrent_tasks() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        let api = Arc::new(ConcurrencyTrackingApi {
+            body: block_body(conflicting_transactions()).to_string(),
+            in_flight: AtomicUsize::new(0),
+            max_in_flight: AtomicUsize::new(0),
+        });
+        ctx.api_client = ApiClient::new().with_transport(api.clone());
+        ctx.task_limiter = TaskLimiter::new(4, 64);
+
+        let statuses = futures::future::join_all((0..50).map(|task_index| {
+            process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], task_index)
+        }))
+        .await;
+
+        assert!(statuses
+            .iter()
+            .all(|status| TaskStatus::from_code(*status) == Some(TaskStatus::Ok)));
+        assert_eq!(aggregator.received().len(), 50);
+        assert_eq!(api.max_in_flight.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_stuck_task_times_out_within_its_deadline() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.api_client = ApiClient::new()
+            .with_transport(Arc::new(HangingApi))
+            .with_timeout(Duration::from_secs(3600));
+        ctx.task_limiter = TaskLimiter::new(1, 1);
+        ctx.task_deadline = Some(Duration::from_millis(100));
+
+        let started = std::time::Instant::now();
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Timeout));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // The cancelled task gave its slot back and can be retried
+        assert_eq!(ctx.task_limiter.running(), 0);
+        assert!(!ctx.processed_tasks.contains(7));
+        assert!(aggregator.received().is_empty());
+
+        ctx.api_client = test_context(conflicting_transactions(), aggregator.clone()).api_client;
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+    }
+
+    #[tokio::test]
+    async fn test_partly_sent_task_stays_claimed_after_its_deadline() {
+        let aggregator = Arc::new(MockAggregator::hanging_after(1));
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.task_deadline = Some(Duration::from_millis(200));
+
+        // The response for quorum 0 is sent, the one for quorum 1 never is
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0, 1], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Timeout));
+        assert_eq!(aggregator.received().len(), 1);
+        assert!(ctx.processed_tasks.contains(7));
+
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0, 1], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Duplicate));
+    }
+
+    #[tokio::test]
+    async fn test_deadline_starts_once_the_task_has_a_slot() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.task_limiter = TaskLimiter::new(1, 1);
+        ctx.task_deadline = Some(Duration::from_millis(500));
+
+        // The task waits longer than its deadline for the only slot, then still runs
+        let running = ctx.task_limiter.acquire().await.unwrap();
+        let task = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7);
+        let release = async {
+            tokio::time::sleep(Duration::from_millis(700)).await;
+            drop(running);
+        };
+        let (status, ()) = tokio::join!(task, release);
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+        assert_eq!(aggregator.received().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tasks_beyond_the_queue_are_rejected() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.task_limiter = TaskLimiter::new(1, 1);
+
+        let statuses = futures::future::join_all((0..3).map(|task_index| {
+            process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], task_index)
+        }))
+        .await;
+
+        // The first task runs, the second waits for it and the third finds the queue full
+        let statuses: Vec<_> = statuses.into_iter().map(TaskStatus::from_code).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                Some(TaskStatus::Ok),
+                Some(TaskStatus::Ok),
+                Some(TaskStatus::Rejected)
+            ]
+        );
+        assert_eq!(aggregator.received().len(), 2);
+        // A rejected task is released, so its redelivered event is processed
+        assert!(!ctx.processed_tasks.contains(2));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_tasks_do_not_wait_for_a_slot() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.task_limiter = TaskLimiter::new(1, 0);
+        assert!(ctx.processed_tasks.claim(7));
+
+        // With the only slot taken a new task is rejected, but a duplicate is recognised first
+        let _running = ctx.task_limiter.acquire().await.unwrap();
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Duplicate));
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 8).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Rejected));
+        assert!(ctx.processed_tasks.contains(7));
+        assert!(!ctx.processed_tasks.contains(8));
+        assert!(aggregator.received().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_schedule_artifact_matches_signed_hash() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.schedule_artifacts = Some(ScheduleArtifacts::open(dir.path()).unwrap());
+
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+
+        let artifact = ctx
+            .schedule_artifacts
+            .as_ref()
+            .unwrap()
+            .read(7)
+            .unwrap()
+            .unwrap();
+        assert!(artifact.verify());
+        assert_eq!(artifact.schedule.batches, vec![vec![0, 1], vec![2]]);
+        assert_eq!(
+            artifact.result_hash,
+            aggregator.received()[0].task_response().resultHash
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sent_task_is_not_resubmitted_after_restart() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("processed-tasks.jsonl");
+        let aggregator = Arc::new(MockAggregator::default());
+
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.processed_tasks = ProcessedTasks::open(&path, PROCESSED_TASKS_WINDOW).unwrap();
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+        let result_hash = aggregator.received()[0].task_response().resultHash;
+        drop(ctx);
+
+        // A fresh context, as after a restart, picks the sent task up from disk
+        let mut restarted = test_context(conflicting_transactions(), aggregator.clone());
+        restarted.processed_tasks = ProcessedTasks::open(&path, PROCESSED_TASKS_WINDOW).unwrap();
+        assert_eq!(
+            restarted.processed_tasks.record(7).unwrap().result_hash,
+            result_hash
+        );
+
+        let status =
+            process_task(&restarted, || bls_signer(&restarted, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Duplicate));
+        assert_eq!(aggregator.received().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_signs_without_sending() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.dry_run = true;
+
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+        assert!(aggregator.received().is_empty());
+        // The key was loaded and used even though nothing was sent
+        assert!(ctx.operator_id.get().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_calculate_task_signs_with_ecdsa() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let mut ctx = test_context(conflicting_transactions(), aggregator.clone());
+        ctx.signature_scheme = SignatureScheme::Ecdsa;
+        let signer = EcdsaTaskSigner::new(PrivateKeySigner::random());
+        let operator = signer.address();
+
+        let load_signer = || -> Result<Box<dyn TaskSigner>, SignerError> { Ok(Box::new(signer)) };
+        let status = process_task(&ctx, load_signer, 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+
+        let received = aggregator.received();
+        assert_eq!(received.len(), 1);
+        let SignedTaskPayload::Ecdsa(signed) = &received[0] else {
+            panic!("expected an ECDSA signed task response");
+        };
+        let expected =
+            ParallelSchedule::new(BLOCK_HASH.parse().unwrap(), vec![vec![0, 1], vec![2]]);
+        assert_eq!(signed.task_response.resultHash, expected.hash());
+        assert_eq!(signed.operator, operator);
+        assert_eq!(signed.recover_operator(&ctx.signing_domain), Some(operator));
+    }
+
+    /// Signer producing the signature of another key, like a corrupted keystore would
+    struct WrongKeySigner {
+        signer: BlsTaskSigner,
+        wrong_key: BlsKeyPair,
+    }
+
+    #[async_trait]
+    impl TaskSigner for WrongKeySigner {
+        fn scheme(&self) -> SignatureScheme {
+            SignatureScheme::Bls
+        }
+
+        fn bls_operator_id(&self) -> Option<OperatorId> {
+            self.signer.bls_operator_id()
+        }
+
+        async fn sign_task_response(
+            &self,
+            task_response: TaskResponse,
+            domain: &SigningDomain,
+        ) -> Result<SignedTaskPayload, SignerError> {
+            let digest = domain.signing_hash(&task_response);
+            let mut payload = self
+                .signer
+                .sign_task_response(task_response, domain)
+                .await?;
+            if let SignedTaskPayload::Bls(signed) = &mut payload {
+                signed.signature = self.wrong_key.sign_message(digest.as_ref());
+            }
+            Ok(payload)
+        }
+
+        fn verify(&self, payload: &SignedTaskPayload, domain: &SigningDomain) -> bool {
+            self.signer.verify(payload, domain)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_own_signature_is_not_sent() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let ctx = test_context(conflicting_transactions(), aggregator.clone());
+        let load_signer = || -> Result<Box<dyn TaskSigner>, SignerError> {
+            Ok(Box::new(WrongKeySigner {
+                signer: bls_task_signer(&ctx, &BlsSecret("12345".to_string()))?,
+                wrong_key: BlsKeyPair::new("67890".to_string()).unwrap(),
+            }))
+        };
+
+        let status = process_task(&ctx, load_signer, 42, &[0], 7).await;
+        assert_eq!(
+            TaskStatus::from_code(status),
+            Some(TaskStatus::SigningFailed)
+        );
+        assert!(aggregator.received().is_empty());
+
+        // Nothing was sent, so the task can be retried with a working key
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+    }
+
+    #[tokio::test]
+    async fn test_calculate_task_without_key_is_released() {
+        let aggregator = Arc::new(MockAggregator::default());
+        let ctx = test_context(conflicting_transactions(), aggregator.clone());
+        let missing_key = || -> Result<Box<dyn TaskSigner>, SignerError> {
+            Err(SignerError::NoEcdsaKey("keystore is empty".to_string()))
+        };
+
+        let status = process_task(&ctx, missing_key, 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::NoKey));
+        assert!(aggregator.received().is_empty());
+
+        // Nothing was signed, so the task can be retried
+        let status = process_task(&ctx, || bls_signer(&ctx, "12345"), 42, &[0], 7).await;
+        assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+    }
+
+    #[test]
+    fn test_task_status_codes() {
+        let expected = [
+            (TaskStatus::Ok, 1, "ok (1)"),
+            (TaskStatus::ApiFailed, 2, "api_failed (2)"),
+            (TaskStatus::NoKey, 3, "no_key (3)"),
+            (TaskStatus::SigningFailed, 4, "signing_failed (4)"),
+            (TaskStatus::SendFailed, 5, "send_failed (5)"),
+            (TaskStatus::Duplicate, 6, "duplicate (6)"),
+            (TaskStatus::Rejected, 7, "rejected (7)"),
+            (TaskStatus::NotInQuorum, 8, "not_in_quorum (8)"),
+            (TaskStatus::Timeout, 9, "timeout (9)"),
+            (
+                TaskStatus::InsufficientBlocks,
+                10,
+                "insufficient_blocks (10)",
+            ),
+        ];
+
+        for (status, code, display) in expected {
+            assert_eq!(status.code(), code);
+            assert_eq!(u32::from(status), code);
+            assert_eq!(TaskStatus::from_code(code), Some(status));
+            assert_eq!(status.to_string(), display);
+        }
+        assert_eq!(TaskStatus::from_code(0), None);
+        assert_eq!(TaskStatus::from_code(11), None);
+    }
 }
@@ -1,16 +1,19 @@
+use crate::contexts::quorum_status::QuorumStakes;
+use crate::jobs::compute_x_square::parse_quorum_threshold_percentage;
 use crate::IIncredibleSquaringTaskManager::Task;
 use crate::{
     contexts::aggregator::AggregatorContext, IncredibleSquaringTaskManager, ProcessorError,
     INCREDIBLE_SQUARING_TASK_MANAGER_ABI_STRING,
 };
+use alloy_primitives::{Bytes, U256};
+use blueprint_sdk::contexts::eigenlayer::EigenlayerContext;
 use blueprint_sdk::event_listeners::evm::EvmContractEventListener;
-use blueprint_sdk::logging::info;
+use blueprint_sdk::logging::{info, warn};
 use std::convert::Infallible;
+use std::time::Instant;
 
-const TASK_CHALLENGE_WINDOW_BLOCK: u32 = 100;
-const BLOCK_TIME_SECONDS: u32 = 12;
-
-/// Initializes the task for the aggregator server
+/// Initializes the task for the aggregator server, with the quorums, threshold and expiry
+/// configured by the context's [`TaskConfig`](crate::contexts::task_config::TaskConfig)
 #[blueprint_sdk::job(
     id = 1,
     params(task, task_index),
@@ -30,8 +33,31 @@ pub async fn initialize_bls_task(
 
     let mut tasks = ctx.tasks.lock().await;
     tasks.insert(task_index, task.clone());
-    let time_to_expiry =
-        std::time::Duration::from_secs((TASK_CHALLENGE_WINDOW_BLOCK * BLOCK_TIME_SECONDS).into());
+    let params = ctx.task_params(&task);
+    for mismatch in ctx.task_config().mismatches(&task) {
+        warn!("Task {}: {}", task_index, mismatch);
+    }
+    info!(
+        "Aggregating task {} over quorums {:?} with thresholds {:?}, expiring in {:?}",
+        task_index,
+        params.quorum_numbers,
+        params.quorum_threshold_percentages,
+        params.time_to_expiry
+    );
+
+    // Track the task's quorum progress, counting signatures only if the stake is unavailable
+    let stakes = match operator_stakes(&ctx, task.taskCreatedBlock, &params.quorum_numbers).await {
+        Ok(stakes) => stakes,
+        Err(e) => {
+            warn!(
+                "Failed to read the operator stakes of task {}, its quorum progress only counts signatures: {}",
+                task_index, e
+            );
+            QuorumStakes::new()
+        }
+    };
+    ctx.quorum_tracker
+        .start(task_index, &params, stakes, Instant::now());
 
     if let Some(service) = &ctx.bls_aggregation_service {
         service
@@ -40,9 +66,9 @@ pub async fn initialize_bls_task(
             .initialize_new_task(
                 task_index,
                 task.taskCreatedBlock,
-                task.quorumNumbers.to_vec(),
-                vec![task.quorumThresholdPercentage.try_into().unwrap(); task.quorumNumbers.len()],
-                time_to_expiry,
+                params.quorum_numbers,
+                params.quorum_threshold_percentages,
+                params.time_to_expiry,
             )
             .await
             .unwrap()
@@ -51,6 +77,37 @@ pub async fn initialize_bls_task(
     Ok(1)
 }
 
+/// Reads the stake every operator had in `quorum_numbers` at the block the task was created
+async fn operator_stakes(
+    ctx: &AggregatorContext,
+    task_created_block: u32,
+    quorum_numbers: &[u8],
+) -> Result<QuorumStakes, String> {
+    let operators = ctx
+        .eigenlayer_client()
+        .await
+        .map_err(|e| e.to_string())?
+        .get_operator_stake_in_quorums_at_block(
+            task_created_block,
+            Bytes::copy_from_slice(quorum_numbers),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // One list of operators per quorum, in the order the quorums were asked for
+    let mut stakes = QuorumStakes::new();
+    for (&quorum_number, operators) in quorum_numbers.iter().zip(operators) {
+        for operator in operators {
+            stakes = stakes.with_operator(
+                operator.operatorId,
+                quorum_number,
+                U256::from(operator.stake),
+            );
+        }
+    }
+    Ok(stakes)
+}
+
 /// Converts the event to inputs.
 ///
 /// Uses a tuple to represent the return type because
@@ -62,6 +119,15 @@ pub async fn convert_event_to_inputs(
         alloy_rpc_types::Log,
     ),
 ) -> Result<Option<(Task, u32)>, ProcessorError> {
+    if event.1.removed {
+        info!(
+            "Not initializing task {}: block {:?} was reorged out",
+            event.0.taskIndex, event.1.block_hash
+        );
+        return Ok(None);
+    }
+    // Rejected here so the job can rely on the percentage fitting in a u8
+    parse_quorum_threshold_percentage(event.0.task.quorumThresholdPercentage)?;
     let task_index = event.0.taskIndex;
     Ok(Some((event.0.task, task_index)))
 }
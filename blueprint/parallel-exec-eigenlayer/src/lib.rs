@@ -7,9 +7,27 @@ use std::net::AddrParseError;
 use thiserror::Error;
 
 pub mod api_client;
+#[cfg(feature = "block-provider")]
+pub mod block_provider;
+pub mod bls_keys;
+pub mod circuit_breaker;
+pub mod cli;
+pub mod config;
 pub mod constants;
 pub mod contexts;
+pub mod deps;
 pub mod jobs;
+pub mod listener;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod rate_limiter;
+pub mod registration;
+pub mod scheduler;
+pub mod signer;
+#[cfg(test)]
+mod pipeline_tests;
+#[cfg(test)]
+mod test_support;
 #[cfg(test)]
 mod tests;
 
@@ -49,7 +67,7 @@ impl From<Error>
 sol!(
     #[allow(missing_docs)]
     #[sol(rpc)]
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
     IncredibleSquaringTaskManager,
     "contracts/out/IncredibleSquaringTaskManager.sol/IncredibleSquaringTaskManager.json"
 );
@@ -0,0 +1,998 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use alloy_primitives::{Address, B256};
+use alloy_provider::Provider;
+use alloy_rpc_types::Log;
+use async_trait::async_trait;
+use blueprint_sdk::utils::evm::{get_provider_http, get_provider_ws};
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use parking_lot::Mutex;
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::api_client::RetryConfig;
+use crate::IncredibleSquaringTaskManager::{self, NewTaskCreated};
+
+/// Number of handled logs remembered to skip redeliveries
+const HANDLED_LOGS_WINDOW: usize = 1024;
+
+/// Default interval [`HttpEventSource`] polls for new logs at
+pub const DEFAULT_LOG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default number of received tasks waiting to be processed before the listener is paused
+pub const DEFAULT_TASK_QUEUE_CAPACITY: usize = 64;
+
+#[derive(Debug, Error)]
+pub enum ListenerError {
+    #[error("RPC error: {0}")]
+    Rpc(String),
+    #[error("Failed to decode NewTaskCreated log: {0}")]
+    Decode(String),
+    #[error("Invalid start block {0:?}, expected a block number, `head` or `head-<blocks>`")]
+    InvalidStartBlock(String),
+    #[error("Unsupported RPC endpoint {0:?}, expected a ws://, wss://, http:// or https:// URL")]
+    UnsupportedEndpoint(String),
+}
+
+/// Block the listener starts reading `NewTaskCreated` events from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StartBlock {
+    /// The chain head when the operator starts
+    #[default]
+    Head,
+    /// The given block number
+    Number(u64),
+    /// The given number of blocks before the chain head, to backfill recent tasks
+    HeadMinus(u64),
+}
+
+impl StartBlock {
+    /// Returns the block number to start from when the chain head is at `head`
+    pub fn resolve(self, head: u64) -> u64 {
+        match self {
+            StartBlock::Head => head,
+            StartBlock::Number(number) => number,
+            StartBlock::HeadMinus(blocks) => head.saturating_sub(blocks),
+        }
+    }
+}
+
+impl FromStr for StartBlock {
+    type Err = ListenerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ListenerError::InvalidStartBlock(s.to_string());
+        let value = s.trim().to_ascii_lowercase();
+        match value.strip_prefix("head") {
+            Some("") => Ok(StartBlock::Head),
+            Some(offset) => offset
+                .trim()
+                .strip_prefix('-')
+                .and_then(|blocks| blocks.trim().parse().ok())
+                .map(StartBlock::HeadMinus)
+                .ok_or_else(invalid),
+            None => value.parse().map(StartBlock::Number).map_err(|_| invalid()),
+        }
+    }
+}
+
+impl fmt::Display for StartBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartBlock::Head => f.write_str("head"),
+            StartBlock::Number(number) => write!(f, "{}", number),
+            StartBlock::HeadMinus(blocks) => write!(f, "head-{}", blocks),
+        }
+    }
+}
+
+/// A `NewTaskCreated` event and the log it was emitted in
+#[derive(Debug, Clone)]
+pub struct TaskEvent {
+    pub block_number: u64,
+    pub block_hash: B256,
+    pub log_index: u64,
+    /// The block was reorged out and the event is no longer part of the chain
+    pub removed: bool,
+    pub event: NewTaskCreated,
+}
+
+impl TaskEvent {
+    /// Identifies the log across redeliveries, and tells apart a task re-emitted in the block
+    /// that replaced a reorged one
+    pub fn log_key(&self) -> (B256, u64) {
+        (self.block_hash, self.log_index)
+    }
+}
+
+pub type TaskEventStream = BoxStream<'static, Result<TaskEvent, ListenerError>>;
+
+/// Delivers `NewTaskCreated` events of the task manager
+#[async_trait]
+pub trait TaskEventSource: Send + Sync {
+    /// Streams the events emitted from `from_block` on, including past ones. The stream ends or
+    /// yields [`ListenerError::Rpc`] once the connection drops.
+    async fn subscribe(&self, from_block: u64) -> Result<TaskEventStream, ListenerError>;
+
+    /// Returns the hash of the canonical block numbered `block_number`, if the node has it
+    async fn canonical_block_hash(&self, block_number: u64) -> Result<Option<B256>, ListenerError>;
+}
+
+/// How events are read from an RPC endpoint, following its URL scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcTransport {
+    /// `ws://` or `wss://`, events are pushed over a subscription
+    Ws,
+    /// `http://` or `https://`, new logs are polled for
+    Http,
+}
+
+impl RpcTransport {
+    pub fn of(endpoint: &str) -> Result<Self, ListenerError> {
+        let scheme = endpoint
+            .split_once("://")
+            .map(|(scheme, _)| scheme.to_ascii_lowercase());
+        match scheme.as_deref() {
+            Some("ws" | "wss") => Ok(RpcTransport::Ws),
+            Some("http" | "https") => Ok(RpcTransport::Http),
+            _ => Err(ListenerError::UnsupportedEndpoint(endpoint.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for RpcTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcTransport::Ws => f.write_str("websocket subscription"),
+            RpcTransport::Http => f.write_str("HTTP polling"),
+        }
+    }
+}
+
+/// Reads `NewTaskCreated` events over a fresh websocket connection on every subscription, and
+/// checks blocks over HTTP
+#[derive(Debug, Clone)]
+pub struct WsEventSource {
+    ws_endpoint: String,
+    http_endpoint: String,
+    task_manager: Address,
+}
+
+impl WsEventSource {
+    pub fn new(
+        ws_endpoint: impl Into<String>,
+        http_endpoint: impl Into<String>,
+        task_manager: Address,
+    ) -> Self {
+        Self {
+            ws_endpoint: ws_endpoint.into(),
+            http_endpoint: http_endpoint.into(),
+            task_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl TaskEventSource for WsEventSource {
+    async fn subscribe(&self, from_block: u64) -> Result<TaskEventStream, ListenerError> {
+        // `get_provider_ws` panics when the endpoint is unreachable, so connect on its own task
+        // to get an error the listener can retry on instead
+        let ws_endpoint = self.ws_endpoint.clone();
+        let provider = tokio::spawn(async move { get_provider_ws(&ws_endpoint).await })
+            .await
+            .map_err(|e| ListenerError::Rpc(format!("failed to connect: {}", e)))?;
+        let task_manager = IncredibleSquaringTaskManager::new(self.task_manager, provider);
+        let filter = task_manager.NewTaskCreated_filter().filter;
+
+        // Subscribe before backfilling, so no event falls in between. Events in both are
+        // skipped by the listener.
+        let live = task_manager
+            .provider()
+            .subscribe_logs(&filter)
+            .await
+            .map_err(|e| ListenerError::Rpc(e.to_string()))?
+            .into_stream();
+        let past = task_manager
+            .provider()
+            .get_logs(&filter.from_block(from_block))
+            .await
+            .map_err(|e| ListenerError::Rpc(e.to_string()))?;
+
+        let events = stream::iter(past).chain(live).map(move |log| {
+            // The subscription only lives as long as its connection
+            let _connection = &task_manager;
+            decode_task_event(log)
+        });
+        Ok(events.boxed())
+    }
+
+    async fn canonical_block_hash(&self, block_number: u64) -> Result<Option<B256>, ListenerError> {
+        canonical_block_hash_http(&self.http_endpoint, block_number).await
+    }
+}
+
+/// Reads `NewTaskCreated` events by polling an HTTP endpoint for new logs, for nodes that do
+/// not serve websockets
+#[derive(Debug, Clone)]
+pub struct HttpEventSource {
+    http_endpoint: String,
+    task_manager: Address,
+    poll_interval: Duration,
+}
+
+impl HttpEventSource {
+    pub fn new(http_endpoint: impl Into<String>, task_manager: Address) -> Self {
+        Self {
+            http_endpoint: http_endpoint.into(),
+            task_manager,
+            poll_interval: DEFAULT_LOG_POLL_INTERVAL,
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+}
+
+#[async_trait]
+impl TaskEventSource for HttpEventSource {
+    async fn subscribe(&self, from_block: u64) -> Result<TaskEventStream, ListenerError> {
+        let provider = get_provider_http(&self.http_endpoint);
+        let task_manager = IncredibleSquaringTaskManager::new(self.task_manager, provider);
+        let filter = task_manager.NewTaskCreated_filter().filter;
+
+        // Install the log filter before backfilling, so no event falls in between
+        let live = task_manager
+            .provider()
+            .watch_logs(&filter)
+            .await
+            .map_err(|e| ListenerError::Rpc(e.to_string()))?
+            .with_poll_interval(self.poll_interval)
+            .into_stream()
+            .flat_map(stream::iter);
+        let past = task_manager
+            .provider()
+            .get_logs(&filter.from_block(from_block))
+            .await
+            .map_err(|e| ListenerError::Rpc(e.to_string()))?;
+
+        Ok(stream::iter(past)
+            .chain(live)
+            .map(decode_task_event)
+            .boxed())
+    }
+
+    async fn canonical_block_hash(&self, block_number: u64) -> Result<Option<B256>, ListenerError> {
+        canonical_block_hash_http(&self.http_endpoint, block_number).await
+    }
+}
+
+/// Event source picked by the scheme of the endpoint events are read from
+#[derive(Debug, Clone)]
+pub enum RpcEventSource {
+    Ws(WsEventSource),
+    Http(HttpEventSource),
+}
+
+impl RpcEventSource {
+    /// Reads events from `event_endpoint`, over a websocket subscription for `ws://` and
+    /// `wss://` URLs and by polling for `http://` and `https://` ones. Blocks are always
+    /// checked against `http_endpoint`.
+    pub fn new(
+        event_endpoint: &str,
+        http_endpoint: impl Into<String>,
+        task_manager: Address,
+    ) -> Result<Self, ListenerError> {
+        match RpcTransport::of(event_endpoint)? {
+            RpcTransport::Ws => Ok(RpcEventSource::Ws(WsEventSource::new(
+                event_endpoint,
+                http_endpoint,
+                task_manager,
+            ))),
+            RpcTransport::Http => Ok(RpcEventSource::Http(HttpEventSource::new(
+                event_endpoint,
+                task_manager,
+            ))),
+        }
+    }
+
+    /// Sets how often new logs are polled for over HTTP. Websocket subscriptions are pushed
+    /// new logs, so the interval does not apply to them.
+    pub fn with_poll_interval(self, poll_interval: Duration) -> Self {
+        match self {
+            RpcEventSource::Http(source) => {
+                RpcEventSource::Http(source.with_poll_interval(poll_interval))
+            }
+            ws => ws,
+        }
+    }
+
+    pub fn transport(&self) -> RpcTransport {
+        match self {
+            RpcEventSource::Ws(_) => RpcTransport::Ws,
+            RpcEventSource::Http(_) => RpcTransport::Http,
+        }
+    }
+}
+
+#[async_trait]
+impl TaskEventSource for RpcEventSource {
+    async fn subscribe(&self, from_block: u64) -> Result<TaskEventStream, ListenerError> {
+        match self {
+            RpcEventSource::Ws(source) => source.subscribe(from_block).await,
+            RpcEventSource::Http(source) => source.subscribe(from_block).await,
+        }
+    }
+
+    async fn canonical_block_hash(&self, block_number: u64) -> Result<Option<B256>, ListenerError> {
+        match self {
+            RpcEventSource::Ws(source) => source.canonical_block_hash(block_number).await,
+            RpcEventSource::Http(source) => source.canonical_block_hash(block_number).await,
+        }
+    }
+}
+
+/// Decodes a `NewTaskCreated` log, which must be mined
+fn decode_task_event(log: Log) -> Result<TaskEvent, ListenerError> {
+    let pending = |field: &str| ListenerError::Decode(format!("log has no {}", field));
+    let block_number = log.block_number.ok_or_else(|| pending("block number"))?;
+    let block_hash = log.block_hash.ok_or_else(|| pending("block hash"))?;
+    let log_index = log.log_index.ok_or_else(|| pending("log index"))?;
+    let removed = log.removed;
+    let event = log
+        .log_decode::<NewTaskCreated>()
+        .map_err(|e| ListenerError::Decode(e.to_string()))?
+        .inner
+        .data;
+    Ok(TaskEvent {
+        block_number,
+        block_hash,
+        log_index,
+        removed,
+        event,
+    })
+}
+
+async fn canonical_block_hash_http(
+    http_endpoint: &str,
+    block_number: u64,
+) -> Result<Option<B256>, ListenerError> {
+    let block: Option<serde_json::Value> = get_provider_http(http_endpoint)
+        .raw_request(
+            "eth_getBlockByNumber".into(),
+            (format!("{:#x}", block_number), false),
+        )
+        .await
+        .map_err(|e| ListenerError::Rpc(e.to_string()))?;
+    block
+        .map(|block| serde_json::from_value(block["hash"].clone()))
+        .transpose()
+        .map_err(|e| ListenerError::Rpc(format!("invalid block hash: {}", e)))
+}
+
+/// Bounded queue between the listener and task processing.
+///
+/// When processing falls behind and the queue fills up, [`TaskQueue::push`] waits for room.
+/// Used as the [`ReconnectingListener`] handler, this stops the listener from reading further
+/// events until a task is taken off the queue, instead of buffering them without bound.
+#[derive(Debug, Clone)]
+pub struct TaskQueue {
+    sender: mpsc::Sender<TaskEvent>,
+}
+
+/// Tasks taken off a [`TaskQueue`], in the order they were received
+#[derive(Debug)]
+pub struct QueuedTasks(mpsc::Receiver<TaskEvent>);
+
+impl TaskQueue {
+    /// Creates a queue holding up to `capacity` tasks, at least one
+    pub fn new(capacity: usize) -> (Self, QueuedTasks) {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        (Self { sender }, QueuedTasks(receiver))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.sender.max_capacity()
+    }
+
+    /// Returns whether the next push has to wait for a task to be taken off the queue
+    pub fn is_full(&self) -> bool {
+        self.sender.capacity() == 0
+    }
+
+    /// Queues `event`, waiting for room while the queue is full. Events pushed after the
+    /// receiving side is gone are dropped.
+    pub async fn push(&self, event: TaskEvent) {
+        let task_index = event.event.taskIndex;
+        let event = match self.sender.try_send(event) {
+            Ok(()) => return,
+            Err(mpsc::error::TrySendError::Full(event)) => event,
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                error!("Dropping task {}: task processing has stopped", task_index);
+                return;
+            }
+        };
+
+        warn!(
+            "Task queue is full with {} tasks, pausing the NewTaskCreated listener until task {} \
+             can be queued",
+            self.capacity(),
+            task_index
+        );
+        let started = Instant::now();
+        match self.sender.send(event).await {
+            Ok(()) => info!(
+                "Resuming the NewTaskCreated listener after {:?}",
+                started.elapsed()
+            ),
+            Err(_) => error!("Dropping task {}: task processing has stopped", task_index),
+        }
+    }
+}
+
+impl Stream for QueuedTasks {
+    type Item = TaskEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<TaskEvent>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+/// Feeds `NewTaskCreated` events to a handler, reconnecting when the RPC connection drops.
+///
+/// Each reconnection resumes from the block of the last handled event, so events emitted while
+/// the listener was disconnected are not lost. Logs that were already handled are skipped.
+///
+/// Across reorgs, events are only handled while their block is canonical: events from blocks
+/// that were reorged out are dropped, also when they are delivered again later, while a task
+/// re-emitted in the replacing block is handled as a new log.
+///
+/// Events from before the start block are skipped, also if the source delivers them.
+pub struct ReconnectingListener<S> {
+    source: S,
+    backoff: RetryConfig,
+    /// Block number of the last handled event
+    last_block: Mutex<Option<u64>>,
+    handled: Mutex<LogWindow>,
+    orphaned: Mutex<HashSet<B256>>,
+    start_block: u64,
+}
+
+/// The most recently handled logs, oldest first
+#[derive(Debug, Default)]
+struct LogWindow {
+    keys: HashSet<(B256, u64)>,
+    order: VecDeque<(B256, u64)>,
+}
+
+impl LogWindow {
+    fn contains(&self, key: &(B256, u64)) -> bool {
+        self.keys.contains(key)
+    }
+
+    fn insert(&mut self, key: (B256, u64)) {
+        if self.keys.insert(key) {
+            self.order.push_back(key);
+        }
+        while self.order.len() > HANDLED_LOGS_WINDOW {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl<S: TaskEventSource> ReconnectingListener<S> {
+    pub fn new(source: S, start_block: u64) -> Self {
+        Self {
+            source,
+            backoff: RetryConfig::default(),
+            last_block: Mutex::new(None),
+            handled: Mutex::new(LogWindow::default()),
+            orphaned: Mutex::new(HashSet::new()),
+            start_block,
+        }
+    }
+
+    /// Waits [`RetryConfig::backoff`] between reconnection attempts. The listener never gives
+    /// up, so `max_attempts` is ignored.
+    pub fn with_backoff(mut self, backoff: RetryConfig) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Returns the block of the last handled event
+    pub fn last_block(&self) -> Option<u64> {
+        *self.last_block.lock()
+    }
+
+    fn resume_block(&self) -> u64 {
+        self.last_block().unwrap_or(self.start_block)
+    }
+
+    /// Passes every new event to `handle` until `shutdown` resolves
+    pub async fn run<F, Fut, Sd>(&self, mut handle: F, shutdown: Sd)
+    where
+        F: FnMut(TaskEvent) -> Fut,
+        Fut: Future<Output = ()>,
+        Sd: Future<Output = ()>,
+    {
+        tokio::select! {
+            _ = self.listen(&mut handle) => {}
+            _ = shutdown => info!("NewTaskCreated listener stopped"),
+        }
+    }
+
+    async fn listen<F, Fut>(&self, handle: &mut F)
+    where
+        F: FnMut(TaskEvent) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let mut failures = 0;
+        loop {
+            let from_block = self.resume_block();
+            match self.source.subscribe(from_block).await {
+                Ok(mut events) => {
+                    info!(
+                        "Listening for NewTaskCreated events from block {}",
+                        from_block
+                    );
+                    while let Some(event) = events.next().await {
+                        match event {
+                            Ok(event) => {
+                                failures = 0;
+                                self.handle_event(event, handle).await;
+                            }
+                            Err(ListenerError::Decode(e)) => {
+                                error!("Skipping undecodable NewTaskCreated log: {}", e)
+                            }
+                            Err(e) => {
+                                error!("NewTaskCreated subscription failed: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(e) => error!("Failed to subscribe to NewTaskCreated events: {}", e),
+            }
+
+            failures += 1;
+            let delay = self.backoff.backoff(failures);
+            error!(
+                "Lost the NewTaskCreated subscription, tasks are not received until it is back. \
+                 Reconnecting from block {} in {:?} (attempt {})",
+                self.resume_block(),
+                delay,
+                failures
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn handle_event<F, Fut>(&self, event: TaskEvent, handle: &mut F)
+    where
+        F: FnMut(TaskEvent) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let task_index = event.event.taskIndex;
+        if event.block_number < self.start_block {
+            debug!(
+                "Skipping task {}: block {} is before start block {}",
+                task_index, event.block_number, self.start_block
+            );
+            return;
+        }
+        if event.removed {
+            warn!(
+                "Block {} ({}) was reorged out, dropping task {}",
+                event.block_number, event.block_hash, task_index
+            );
+            self.orphaned.lock().insert(event.block_hash);
+            return;
+        }
+        if self.handled.lock().contains(&event.log_key()) {
+            debug!("Skipping task {}: already received", task_index);
+            return;
+        }
+        if !self.is_canonical(&event).await {
+            warn!(
+                "Dropping task {}: block {} ({}) is not part of the chain",
+                task_index, event.block_number, event.block_hash
+            );
+            return;
+        }
+
+        let (block_number, log_key) = (event.block_number, event.log_key());
+        handle(event).await;
+        self.handled.lock().insert(log_key);
+        *self.last_block.lock() = Some(block_number);
+    }
+
+    /// Checks that the event's block was not reorged out. Blocks that cannot be checked are
+    /// assumed canonical, so an RPC hiccup does not lose tasks.
+    async fn is_canonical(&self, event: &TaskEvent) -> bool {
+        if self.orphaned.lock().contains(&event.block_hash) {
+            return false;
+        }
+        match self.source.canonical_block_hash(event.block_number).await {
+            Ok(Some(hash)) if hash != event.block_hash => {
+                self.orphaned.lock().insert(event.block_hash);
+                false
+            }
+            Ok(_) => true,
+            Err(e) => {
+                warn!(
+                    "Failed to check block {} of task {}: {}",
+                    event.block_number, event.event.taskIndex, e
+                );
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve_json_rpc;
+    use crate::IIncredibleSquaringTaskManager::Task;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Hash of the canonical block `block_number` in the mock chain
+    fn block_hash(block_number: u64) -> B256 {
+        B256::left_padding_from(&block_number.to_be_bytes())
+    }
+
+    fn task_event(block_number: u64, task_index: u32) -> Result<TaskEvent, ListenerError> {
+        task_event_in(block_number, block_hash(block_number), task_index)
+    }
+
+    fn task_event_in(
+        block_number: u64,
+        block_hash: B256,
+        task_index: u32,
+    ) -> Result<TaskEvent, ListenerError> {
+        Ok(TaskEvent {
+            block_number,
+            block_hash,
+            log_index: 0,
+            removed: false,
+            event: NewTaskCreated {
+                taskIndex: task_index,
+                task: Task {
+                    taskCreatedBlock: block_number as u32,
+                    quorumNumbers: vec![0].into(),
+                    quorumThresholdPercentage: 67,
+                },
+            },
+        })
+    }
+
+    /// Serves one canned connection per subscription and records the requested start blocks
+    #[derive(Default)]
+    struct MockSource {
+        connections: Mutex<VecDeque<Vec<Result<TaskEvent, ListenerError>>>>,
+        subscriptions: Mutex<Vec<u64>>,
+        /// Blocks whose canonical hash differs from [`block_hash`]
+        reorged: Mutex<HashMap<u64, B256>>,
+    }
+
+    #[async_trait]
+    impl TaskEventSource for MockSource {
+        async fn subscribe(&self, from_block: u64) -> Result<TaskEventStream, ListenerError> {
+            self.subscriptions.lock().push(from_block);
+            match self.connections.lock().pop_front() {
+                // Stays connected after the canned events
+                Some(events) => Ok(stream::iter(events).chain(stream::pending()).boxed()),
+                None => Err(ListenerError::Rpc("connection refused".to_string())),
+            }
+        }
+
+        async fn canonical_block_hash(
+            &self,
+            block_number: u64,
+        ) -> Result<Option<B256>, ListenerError> {
+            let reorged = self.reorged.lock().get(&block_number).copied();
+            Ok(Some(reorged.unwrap_or_else(|| block_hash(block_number))))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_listener_resumes_after_disconnect() {
+        let source = MockSource::default();
+        source.connections.lock().extend([
+            vec![
+                task_event(10, 1),
+                task_event(11, 2),
+                Err(ListenerError::Rpc("connection reset".to_string())),
+            ],
+            // The resubscription replays the last handled block
+            vec![task_event(11, 2), task_event(12, 3), task_event(13, 4)],
+        ]);
+        let listener = ReconnectingListener::new(source, 10).with_backoff(RetryConfig {
+            max_attempts: 1,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        });
+
+        let handled = Mutex::new(Vec::new());
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+        let mut done_tx = Some(done_tx);
+        let handle = |event: TaskEvent| {
+            let mut handled = handled.lock();
+            handled.push(event.event.taskIndex);
+            if handled.len() == 4 {
+                if let Some(done_tx) = done_tx.take() {
+                    let _ = done_tx.send(());
+                }
+            }
+            async {}
+        };
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            listener.run(handle, async {
+                let _ = done_rx.await;
+            }),
+        )
+        .await
+        .expect("listener never handled the events after reconnecting");
+
+        assert_eq!(*handled.lock(), vec![1, 2, 3, 4]);
+        assert_eq!(*listener.source.subscriptions.lock(), vec![10, 11]);
+        assert_eq!(listener.last_block(), Some(13));
+    }
+
+    #[tokio::test]
+    async fn test_listener_keeps_retrying_failed_subscriptions() {
+        let listener =
+            ReconnectingListener::new(MockSource::default(), 7).with_backoff(RetryConfig {
+                max_attempts: 1,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(2),
+            });
+
+        listener
+            .run(|_| async {}, tokio::time::sleep(Duration::from_millis(100)))
+            .await;
+        let subscriptions = listener.source.subscriptions.lock().clone();
+        assert!(subscriptions.len() > 1);
+        assert!(subscriptions.iter().all(|&block| block == 7));
+    }
+
+    /// Runs `listener` until `count` events were handled, returning their task indices
+    async fn handled_tasks(listener: &ReconnectingListener<MockSource>, count: usize) -> Vec<u32> {
+        let handled = Mutex::new(Vec::new());
+        let done = tokio::sync::Notify::new();
+        let handle = |event: TaskEvent| {
+            let mut handled = handled.lock();
+            handled.push(event.event.taskIndex);
+            if handled.len() == count {
+                done.notify_one();
+            }
+            async {}
+        };
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            listener.run(handle, done.notified()),
+        )
+        .await
+        .expect("listener never handled the expected events");
+        handled.into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_listener_drops_tasks_of_reorged_blocks() {
+        let orphaned = B256::repeat_byte(0xaa);
+        let source = MockSource::default();
+        source.connections.lock().push_back(vec![
+            task_event(10, 1),
+            // Block 11 was replaced, and the removal of its log is announced
+            Ok(TaskEvent {
+                removed: true,
+                ..task_event_in(11, orphaned, 2).unwrap()
+            }),
+            // A late delivery of the orphaned log is dropped
+            task_event_in(11, orphaned, 2),
+            // The replacing block re-emits the task, which is handled as a new log
+            task_event(11, 2),
+            task_event(12, 3),
+            task_event(13, 4),
+        ]);
+        let listener = ReconnectingListener::new(source, 10);
+
+        assert_eq!(handled_tasks(&listener, 4).await, vec![1, 2, 3, 4]);
+        assert!(listener.orphaned.lock().contains(&orphaned));
+    }
+
+    #[tokio::test]
+    async fn test_listener_checks_blocks_against_the_chain() {
+        let source = MockSource::default();
+        source.connections.lock().push_back(vec![
+            task_event(10, 1),
+            // No removal notice arrives, but the node's block 11 has another hash
+            task_event(11, 2),
+            task_event(12, 3),
+            // Logs delivered twice are only handled once
+            task_event(12, 3),
+            task_event(13, 4),
+        ]);
+        source.reorged.lock().insert(11, B256::repeat_byte(0xbb));
+        let listener = ReconnectingListener::new(source, 10);
+
+        assert_eq!(handled_tasks(&listener, 3).await, vec![1, 3, 4]);
+        assert!(listener.orphaned.lock().contains(&block_hash(11)));
+    }
+
+    #[tokio::test]
+    async fn test_full_task_queue_pauses_the_listener() {
+        let source = MockSource::default();
+        source
+            .connections
+            .lock()
+            .push_back((1..=5).map(|i| task_event(9 + i as u64, i)).collect());
+        let listener = ReconnectingListener::new(source, 10);
+        let (queue, mut tasks) = TaskQueue::new(2);
+
+        let handle = |event| {
+            let queue = queue.clone();
+            async move { queue.push(event).await }
+        };
+        let run = listener.run(handle, std::future::pending());
+        tokio::pin!(run);
+
+        // Two tasks fill the queue, and the listener waits with the third one
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut run)
+            .await
+            .is_err());
+        assert!(queue.is_full());
+        assert_eq!(listener.last_block(), Some(11));
+
+        // Taking a task off the queue lets the third one in, after which it waits again
+        assert_eq!(tasks.next().await.unwrap().event.taskIndex, 1);
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut run)
+            .await
+            .is_err());
+        assert!(queue.is_full());
+        assert_eq!(listener.last_block(), Some(12));
+
+        let mut received = Vec::new();
+        while received.len() < 4 {
+            tokio::select! {
+                task = tasks.next() => received.push(task.unwrap().event.taskIndex),
+                _ = &mut run => unreachable!("the listener runs until shut down"),
+            }
+        }
+        assert_eq!(received, vec![2, 3, 4, 5]);
+        assert_eq!(listener.last_block(), Some(14));
+    }
+
+    /// JSON-RPC node without any logs, counting how often new logs are polled for
+    async fn start_polled_node() -> (String, Arc<AtomicUsize>) {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let counter = polls.clone();
+        let endpoint = serve_json_rpc(move |method, _| match method {
+            "eth_newFilter" => serde_json::json!("0x1"),
+            "eth_getFilterChanges" => {
+                counter.fetch_add(1, Ordering::SeqCst);
+                serde_json::json!([])
+            }
+            "eth_getLogs" => serde_json::json!([]),
+            _ => serde_json::json!("0x1"),
+        })
+        .await;
+        (endpoint, polls)
+    }
+
+    /// Number of times new logs are polled for at `poll_interval` while listening for `duration`
+    async fn polls_within(poll_interval: Duration, duration: Duration) -> usize {
+        let (endpoint, polls) = start_polled_node().await;
+        let source = RpcEventSource::new(&endpoint, endpoint.clone(), Address::ZERO)
+            .unwrap()
+            .with_poll_interval(poll_interval);
+
+        let mut events = source.subscribe(0).await.unwrap();
+        let received = tokio::time::timeout(duration, events.next()).await;
+        assert!(received.is_err(), "the node has no logs to deliver");
+        polls.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn test_poll_interval_governs_http_polling() {
+        let listened = Duration::from_millis(600);
+
+        let tight = polls_within(Duration::from_millis(50), listened).await;
+        let loose = polls_within(Duration::from_secs(1), listened).await;
+        assert!(tight >= 5, "polled {} times at a 50ms interval", tight);
+        assert!(loose <= 2, "polled {} times at a 1s interval", loose);
+    }
+
+    #[test]
+    fn test_rpc_transport_follows_url_scheme() {
+        assert_eq!(
+            RpcTransport::of("ws://localhost:8546").unwrap(),
+            RpcTransport::Ws
+        );
+        assert_eq!(
+            RpcTransport::of("WSS://rpc.example.com").unwrap(),
+            RpcTransport::Ws
+        );
+        assert_eq!(
+            RpcTransport::of("http://localhost:8545").unwrap(),
+            RpcTransport::Http
+        );
+        assert_eq!(
+            RpcTransport::of("https://rpc.example.com").unwrap(),
+            RpcTransport::Http
+        );
+        for endpoint in ["localhost:8545", "ipc:///tmp/geth.ipc", ""] {
+            assert!(matches!(
+                RpcTransport::of(endpoint),
+                Err(ListenerError::UnsupportedEndpoint(e)) if e == endpoint
+            ));
+        }
+
+        let source = RpcEventSource::new(
+            "ws://localhost:8546",
+            "http://localhost:8545",
+            Address::ZERO,
+        )
+        .unwrap();
+        assert_eq!(source.transport(), RpcTransport::Ws);
+        let source = RpcEventSource::new(
+            "http://localhost:8545",
+            "http://localhost:8545",
+            Address::ZERO,
+        )
+        .unwrap();
+        assert_eq!(source.transport(), RpcTransport::Http);
+    }
+
+    #[test]
+    fn test_start_block_from_str() {
+        let parse = |s: &str| s.parse::<StartBlock>();
+        assert_eq!(parse("head").unwrap(), StartBlock::Head);
+        assert_eq!(parse(" HEAD ").unwrap(), StartBlock::Head);
+        assert_eq!(parse("1234").unwrap(), StartBlock::Number(1234));
+        assert_eq!(parse("head-100").unwrap(), StartBlock::HeadMinus(100));
+        assert_eq!(parse("head - 100").unwrap(), StartBlock::HeadMinus(100));
+        for invalid in ["", "latest", "head+5", "head-", "-100", "0x10"] {
+            assert!(matches!(
+                parse(invalid),
+                Err(ListenerError::InvalidStartBlock(_))
+            ));
+        }
+
+        assert_eq!(StartBlock::Head.resolve(500), 500);
+        assert_eq!(StartBlock::Number(42).resolve(500), 42);
+        assert_eq!(StartBlock::HeadMinus(100).resolve(500), 400);
+        assert_eq!(StartBlock::HeadMinus(1000).resolve(500), 0);
+        assert_eq!(StartBlock::HeadMinus(100).to_string(), "head-100");
+    }
+
+    #[tokio::test]
+    async fn test_listener_skips_events_before_start_block() {
+        let source = MockSource::default();
+        source.connections.lock().push_back(vec![
+            task_event(8, 1),
+            task_event(9, 2),
+            task_event(10, 3),
+            task_event(11, 4),
+        ]);
+        let start_block = StartBlock::HeadMinus(2).resolve(12);
+        let listener = ReconnectingListener::new(source, start_block);
+
+        assert_eq!(handled_tasks(&listener, 2).await, vec![3, 4]);
+        assert_eq!(*listener.source.subscriptions.lock(), vec![10]);
+    }
+}
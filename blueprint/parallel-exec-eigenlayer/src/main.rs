@@ -1,61 +1,298 @@
 use alloy_network::EthereumWallet;
 use alloy_primitives::Address;
-use alloy_signer_local::PrivateKeySigner;
-use blueprint_sdk::logging::info;
+use alloy_provider::Provider;
+use blueprint_sdk::contexts::keystore::KeystoreContext;
+use blueprint_sdk::logging::{error, info, warn};
 use blueprint_sdk::runners::core::runner::BlueprintRunner;
 use blueprint_sdk::runners::eigenlayer::bls::EigenlayerBLSConfig;
 use blueprint_sdk::utils::evm::get_wallet_provider_http;
+use futures::StreamExt;
+use incredible_squaring_blueprint_eigenlayer::api_client::LiveBlocks;
+use incredible_squaring_blueprint_eigenlayer::config::Config;
 use incredible_squaring_blueprint_eigenlayer::constants::{
-    AGGREGATOR_PRIVATE_KEY, TASK_MANAGER_ADDRESS,
+    ConfigError, LIVE_BLOCKS_CAPACITY, PROCESSED_TASKS_WINDOW, RESPONSE_QUEUE_DRAIN_INTERVAL,
+    RESPONSE_QUEUE_MAX_AGE,
+};
+use incredible_squaring_blueprint_eigenlayer::contexts::aggregator::{
+    shutdown_signal, AggregatorContext,
+};
+use incredible_squaring_blueprint_eigenlayer::contexts::api_recordings::ApiRecordings;
+use incredible_squaring_blueprint_eigenlayer::contexts::client::{
+    AggregatorClient, AggregatorTransport, MultiAggregatorClient,
+};
+use incredible_squaring_blueprint_eigenlayer::contexts::processed_tasks::ProcessedTasks;
+use incredible_squaring_blueprint_eigenlayer::contexts::response_queue::ResponseQueue;
+use incredible_squaring_blueprint_eigenlayer::contexts::schedule_artifacts::ScheduleArtifacts;
+use incredible_squaring_blueprint_eigenlayer::contexts::task_limiter::TaskLimiter;
+use incredible_squaring_blueprint_eigenlayer::contexts::x_square::{
+    EigenSquareContext, OperatorIdCache,
+};
+use incredible_squaring_blueprint_eigenlayer::jobs::compute_x_square::{
+    bls_task_signer, preload_task_signer, process_task_event,
 };
-use incredible_squaring_blueprint_eigenlayer::contexts::aggregator::AggregatorContext;
-use incredible_squaring_blueprint_eigenlayer::contexts::client::AggregatorClient;
-use incredible_squaring_blueprint_eigenlayer::contexts::x_square::EigenSquareContext;
-use incredible_squaring_blueprint_eigenlayer::jobs::compute_x_square::CalculateTaskEventHandler;
 use incredible_squaring_blueprint_eigenlayer::jobs::initialize_task::InitializeBlsTaskEventHandler;
+use incredible_squaring_blueprint_eigenlayer::listener::{
+    ReconnectingListener, RpcEventSource, TaskQueue,
+};
+#[cfg(feature = "metrics")]
+use incredible_squaring_blueprint_eigenlayer::metrics::{self, Metrics};
+use incredible_squaring_blueprint_eigenlayer::registration::{
+    check_operator_registered, RegistrationError, RegistryCoordinator,
+};
+use incredible_squaring_blueprint_eigenlayer::signer::{
+    SignatureScheme, SigningDomain, TaskSigner,
+};
 use incredible_squaring_blueprint_eigenlayer::IncredibleSquaringTaskManager;
-use incredible_squaring_blueprint_eigenlayer::api_client::ApiClient;
+use std::net::SocketAddr;
+use std::sync::Arc;
 
 #[blueprint_sdk::main(env)]
 async fn main() {
-    let signer: PrivateKeySigner = AGGREGATOR_PRIVATE_KEY
-        .parse()
-        .expect("failed to generate wallet ");
+    // Refuse to start with a missing or malformed setting, naming the one to fix
+    let config = exit_on_config_error(Config::load());
+    info!("Effective configuration:\n{}", config.to_redacted_toml());
+    let signer = exit_on_config_error(config.aggregator_signer_key());
+    let task_manager_address = exit_on_config_error(config.task_manager_address());
+    info!("Using task manager {}", task_manager_address);
     let wallet = EthereumWallet::from(signer);
     let provider = get_wallet_provider_http(&env.http_rpc_endpoint, wallet.clone());
+    // The SDK's event listener reads events through this provider, so its pollers follow the
+    // configured interval instead of alloy's default
+    provider
+        .client()
+        .set_poll_interval(config.listener.poll_interval());
+    // Signed task responses are only valid for the task manager on this chain
+    let signing_domain = SigningDomain::new(provider.get_chain_id().await?, task_manager_address)
+        .with_encoding(config.task_response_encoding);
+
+    let server_address = exit_on_config_error(config.aggregator.address()).to_string();
+    #[cfg(feature = "metrics")]
+    let metrics = Metrics::new();
+    #[cfg(feature = "metrics")]
+    if let Some(port) = config.metrics_port {
+        let address = SocketAddr::from(([0, 0, 0, 0], port));
+        metrics::bind(address, metrics.clone(), shutdown_signal()).await?;
+    }
+    let mut api_client = exit_on_config_error(config.api.api_client());
+    // Keep a live view of the latest blocks, so most tasks are served without a fetch
+    if let Some(poll_interval) = config.api.block_poll_interval() {
+        let live_blocks = LiveBlocks::new(LIVE_BLOCKS_CAPACITY);
+        live_blocks
+            .clone()
+            .spawn_updates(api_client.clone(), poll_interval);
+        api_client = api_client.with_live_blocks(live_blocks);
+    }
+    let response_format = config.aggregator.response_format;
+    let aggregator_client =
+        AggregatorClient::new(&server_address)?.with_response_codec(response_format.codec());
+
+    // Redeliver signed responses the aggregator missed while it was unreachable. A dry run
+    // sends nothing, so responses queued by an earlier run are left for the next real one
+    let response_queue = ResponseQueue::open(
+        config.storage.response_queue_dir(env.data_dir.as_deref()),
+        RESPONSE_QUEUE_MAX_AGE,
+    )?;
+    if !config.dry_run {
+        response_queue.clone().spawn_drain(
+            aggregator_client.clone().with_max_attempts(1),
+            RESPONSE_QUEUE_DRAIN_INTERVAL,
+        );
+    }
+
+    // Skip tasks whose responses were already sent before a restart
+    let processed_tasks = ProcessedTasks::open(
+        config.storage.processed_tasks_path(env.data_dir.as_deref()),
+        PROCESSED_TASKS_WINDOW,
+    )?;
+
+    // Publish the schedule of every task for external verifiers, when configured
+    let schedule_artifacts = config
+        .storage
+        .schedule_artifact_dir
+        .as_ref()
+        .map(ScheduleArtifacts::open)
+        .transpose()?;
+    // Record what every task was computed from, so disagreements can be replayed, when configured
+    let api_recordings = config
+        .storage
+        .api_recording_dir
+        .as_ref()
+        .map(ApiRecordings::open)
+        .transpose()?;
+
+    // Fan responses out to every configured aggregator, so one being down does not lose them
+    let client: Arc<dyn AggregatorTransport> = if config.aggregator.additional.is_empty() {
+        Arc::new(aggregator_client)
+    } else {
+        let mut clients = vec![aggregator_client];
+        for address in &config.aggregator.additional {
+            clients
+                .push(AggregatorClient::new(address)?.with_response_codec(response_format.codec()));
+        }
+        info!("Sending task responses to {} aggregators", clients.len());
+        Arc::new(MultiAggregatorClient::from_clients(clients))
+    };
 
-    let server_address = format!("{}:{}", "127.0.0.1", 8081);
-    let api_client = ApiClient::new();
-    
-    let eigen_client_context = EigenSquareContext {
-        client: AggregatorClient::new(&server_address)?,
+    let mut eigen_client_context = EigenSquareContext {
+        client,
         api_client,
+        response_queue: Some(response_queue),
+        schedule_artifacts,
+        api_recordings,
+        processed_tasks,
+        task_limiter: TaskLimiter::new(config.tasks.max_concurrent, config.tasks.max_queued),
+        operator_id: OperatorIdCache::default(),
+        signature_scheme: config.signature_scheme,
+        task_signer: None,
+        operator_quorums: config.operator_quorums.clone(),
+        signing_domain,
+        result_hash_mode: config.result_hash_mode,
+        dry_run: config.dry_run,
+        task_deadline: config.task_deadline(),
+        #[cfg(feature = "operator-id-override")]
+        operator_id_override: config.operator_id_override,
+        #[cfg(feature = "metrics")]
+        metrics: metrics.clone(),
         std_config: env.clone(),
     };
-    let aggregator_context =
-        AggregatorContext::new(server_address, *TASK_MANAGER_ADDRESS, wallet, env.clone())
-            .await
-            .unwrap();
+    // Keys held outside the keystore are loaded once, and a remote signer is reached up front
+    eigen_client_context.task_signer =
+        preload_task_signer(&eigen_client_context, &config.signer).await?;
+    info!(
+        "Signing {} task responses with the {} signer backend",
+        config.signature_scheme, config.signer.backend
+    );
 
-    let contract = IncredibleSquaringTaskManager::new(
-        *TASK_MANAGER_ADDRESS,
-        provider,
+    // Refuse to start with a BLS key the AVS does not know, whose signatures no aggregator
+    // would count, rather than failing every task at aggregation
+    if eigen_client_context.signature_scheme == SignatureScheme::Bls {
+        let operator_id = match eigen_client_context
+            .task_signer
+            .as_ref()
+            .and_then(|signer| signer.bls_operator_id())
+        {
+            Some(operator_id) => operator_id,
+            None => bls_task_signer(&eigen_client_context, &eigen_client_context.keystore())?
+                .operator_id(),
+        };
+        let registered = match RegistryCoordinator::for_task_manager(
+            task_manager_address,
+            env.http_rpc_endpoint.clone(),
+        )
+        .await
+        {
+            Ok(registry) => check_operator_registered(&registry, operator_id).await,
+            Err(e) => Err(e),
+        };
+        match registered {
+            Ok(quorums) => info!(
+                "Operator {} is registered in quorums {:?}",
+                operator_id, quorums
+            ),
+            Err(e @ RegistrationError::NotRegistered { .. }) => {
+                exit_on_config_error(Err(ConfigError::Invalid {
+                    setting: "signer",
+                    reason: e.to_string(),
+                }))
+            }
+            Err(e) => warn!(
+                "Could not check the registration of operator {}: {}",
+                operator_id, e
+            ),
+        }
+    }
+
+    // Tasks are received through this listener only, not the SDK's event listener, which starts
+    // at the head and silently stops when its connection drops. This one backfills from
+    // `LISTENER_FROM_BLOCK`, reconnects and catches up on missed tasks.
+    let start_block = config
+        .listener
+        .from_block
+        .resolve(provider.get_block_number().await?);
+    info!(
+        "Listening for tasks from block {} ({})",
+        start_block, config.listener.from_block
+    );
+    let listener_context = eigen_client_context;
+    let event_source = RpcEventSource::new(
+        config
+            .listener
+            .rpc_url
+            .as_deref()
+            .unwrap_or(&env.ws_rpc_endpoint),
+        env.http_rpc_endpoint.clone(),
+        task_manager_address,
+    )?
+    .with_poll_interval(config.listener.poll_interval());
+    info!("Reading tasks over {}", event_source.transport());
+    // Received tasks go through a bounded queue, so when processing falls behind the listener
+    // pauses instead of buffering events without bound
+    let (task_queue, queued_tasks) = TaskQueue::new(config.tasks.queue_capacity);
+    tokio::spawn(
+        queued_tasks.for_each_concurrent(config.tasks.max_concurrent, move |event| {
+            let ctx = listener_context.clone();
+            async move {
+                process_task_event(&ctx, &event).await;
+            }
+        }),
     );
+    tokio::spawn(async move {
+        let listener = ReconnectingListener::new(event_source, start_block);
+        let handle = |event| {
+            let task_queue = task_queue.clone();
+            async move { task_queue.push(event).await }
+        };
+        listener.run(handle, shutdown_signal()).await;
+    });
+
+    let mut aggregator_context =
+        AggregatorContext::new(server_address, task_manager_address, wallet, env.clone())
+            .await
+            .unwrap()
+            .with_task_config(config.task_config())
+            .with_gas_config(config.gas_config())
+            .with_task_response_encoding(config.task_response_encoding)
+            .with_response_codec(response_format.codec());
+    #[cfg(feature = "metrics")]
+    {
+        aggregator_context = aggregator_context.with_metrics(metrics);
+    }
+    if let Some(port) = config.aggregator.health_port {
+        aggregator_context =
+            aggregator_context.with_health_address(SocketAddr::from(([0, 0, 0, 0], port)));
+    }
+
+    let contract = IncredibleSquaringTaskManager::new(task_manager_address, provider);
 
     let initialize_task =
         InitializeBlsTaskEventHandler::new(contract.clone(), aggregator_context.clone());
 
-    let calculate_task = CalculateTaskEventHandler::new(contract.clone(), eigen_client_context);
-
     info!("~~~ Executing the parallel execution blueprint ~~~");
     let eigen_config = EigenlayerBLSConfig::new(Address::default(), Address::default());
-    BlueprintRunner::new(eigen_config, env)
-        .job(calculate_task)
+    let aggregator_handle = aggregator_context.clone();
+    let mut runner = BlueprintRunner::new(eigen_config, env);
+    let run = runner
         .job(initialize_task)
         .background_service(Box::new(aggregator_context))
-        .run()
-        .await?;
+        .run();
+
+    // Let the aggregator finish in-flight work before exiting on SIGTERM / Ctrl-C
+    tokio::select! {
+        result = run => result?,
+        _ = shutdown_signal() => {
+            info!("Received shutdown signal");
+            aggregator_handle.shutdown().await?;
+        }
+    }
 
     info!("Exiting...");
     Ok(())
 }
+
+/// Unwraps a startup setting, exiting with the error when it is missing or malformed
+fn exit_on_config_error<T>(result: Result<T, ConfigError>) -> T {
+    result.unwrap_or_else(|e| {
+        error!("Invalid configuration: {}", e);
+        std::process::exit(1)
+    })
+}
@@ -0,0 +1,274 @@
+use std::fmt::Write;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::contexts::health::{read_request_line, write_response};
+
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Upper bounds of the task latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Cumulative histogram with fixed buckets, as exposed by Prometheus
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, value: Duration) {
+        let seconds = value.as_secs_f64();
+        for (bucket, le) in self.buckets.iter().zip(LATENCY_BUCKETS) {
+            if seconds <= le {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(value.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (bucket, le) in self.buckets.iter().zip(LATENCY_BUCKETS) {
+            let _ = writeln!(
+                out,
+                "{}_bucket{{le=\"{}\"}} {}",
+                name,
+                le,
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{}_sum {}", name, sum);
+        let _ = writeln!(out, "{}_count {}", name, count);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    tasks_received: AtomicU64,
+    tasks_signed: AtomicU64,
+    send_failures: AtomicU64,
+    api_failures: AtomicU64,
+    signatures_received: AtomicU64,
+    aggregations_submitted: AtomicU64,
+    aggregation_failures: AtomicU64,
+    task_latency: Histogram,
+}
+
+/// Counters and the task latency histogram of the operator and aggregator, shared between clones
+#[derive(Debug, Clone, Default)]
+pub struct Metrics(Arc<Registry>);
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A `NewTaskCreated` event was handed to the job
+    pub fn task_received(&self) {
+        self.0.tasks_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A task response was signed
+    pub fn task_signed(&self) {
+        self.0.tasks_signed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The aggregator did not accept a signed task response
+    pub fn send_failed(&self) {
+        self.0.send_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The block data of a task could not be fetched or hashed
+    pub fn api_failed(&self) {
+        self.0.api_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records how long a task took from receiving its event to its final status
+    pub fn observe_task_latency(&self, latency: Duration) {
+        self.0.task_latency.observe(latency);
+    }
+
+    /// The aggregator accepted a signed task response from an operator
+    pub fn signature_received(&self) {
+        self.0.signatures_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The aggregator submitted an aggregated response to the task manager
+    pub fn aggregation_submitted(&self) {
+        self.0
+            .aggregations_submitted
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The aggregator failed to submit an aggregated response
+    pub fn aggregation_failed(&self) {
+        self.0.aggregation_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let counters = [
+            (
+                "parallel_exec_tasks_received_total",
+                "Tasks received from NewTaskCreated events",
+                &self.0.tasks_received,
+            ),
+            (
+                "parallel_exec_tasks_signed_total",
+                "Task responses signed by the operator",
+                &self.0.tasks_signed,
+            ),
+            (
+                "parallel_exec_send_failures_total",
+                "Signed task responses the aggregator did not accept",
+                &self.0.send_failures,
+            ),
+            (
+                "parallel_exec_api_failures_total",
+                "Tasks whose block data could not be fetched from the helper API",
+                &self.0.api_failures,
+            ),
+            (
+                "parallel_exec_signatures_received_total",
+                "Signed task responses received by the aggregator",
+                &self.0.signatures_received,
+            ),
+            (
+                "parallel_exec_aggregations_submitted_total",
+                "Aggregated responses submitted to the task manager",
+                &self.0.aggregations_submitted,
+            ),
+            (
+                "parallel_exec_aggregation_failures_total",
+                "Aggregated responses that could not be submitted",
+                &self.0.aggregation_failures,
+            ),
+        ];
+
+        let mut out = String::new();
+        for (name, help, counter) in counters {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+        }
+        self.0.task_latency.render(
+            &mut out,
+            "parallel_exec_task_latency_seconds",
+            "End-to-end latency of tasks, from event to final status",
+        );
+        out
+    }
+}
+
+/// Serves `GET /metrics` on `listener` until `shutdown` resolves
+pub fn serve<S>(listener: TcpListener, metrics: Metrics, shutdown: S) -> JoinHandle<()>
+where
+    S: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        tokio::pin!(shutdown);
+        if let Ok(address) = listener.local_addr() {
+            info!("Metrics endpoint listening on {}{}", address, METRICS_PATH);
+        }
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let metrics = metrics.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_connection(stream, &metrics).await {
+                                debug!("Metrics connection failed: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => warn!("Failed to accept metrics connection: {}", e),
+                },
+                _ = &mut shutdown => break,
+            }
+        }
+        info!("Metrics endpoint stopped");
+    })
+}
+
+/// Binds `address` and serves the metrics endpoint on it, see [`serve`]
+pub async fn bind<S>(
+    address: SocketAddr,
+    metrics: Metrics,
+    shutdown: S,
+) -> std::io::Result<JoinHandle<()>>
+where
+    S: Future<Output = ()> + Send + 'static,
+{
+    let listener = TcpListener::bind(address).await?;
+    Ok(serve(listener, metrics, shutdown))
+}
+
+async fn handle_connection(mut stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    let Some((method, path)) = read_request_line(&mut stream).await? else {
+        return Ok(());
+    };
+
+    if method == "GET" && path == METRICS_PATH {
+        let body = metrics.render();
+        write_response(&mut stream, "200 OK", "text/plain; version=0.0.4", &body).await
+    } else {
+        write_response(&mut stream, "404 Not Found", "text/plain", "not found\n").await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the value of the sample `name` in a scrape
+    fn sample(scrape: &str, name: &str) -> Option<f64> {
+        scrape.lines().find_map(|line| {
+            let (series, value) = line.rsplit_once(' ')?;
+            (series == name).then(|| value.parse().ok())?
+        })
+    }
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::new();
+        metrics.observe_task_latency(Duration::from_millis(30));
+        metrics.observe_task_latency(Duration::from_millis(700));
+        metrics.observe_task_latency(Duration::from_secs(120));
+
+        let scrape = metrics.render();
+        let bucket = |le: &str| {
+            sample(
+                &scrape,
+                &format!("parallel_exec_task_latency_seconds_bucket{{le=\"{}\"}}", le),
+            )
+        };
+        assert_eq!(bucket("0.05"), Some(1.0));
+        assert_eq!(bucket("0.5"), Some(1.0));
+        assert_eq!(bucket("1"), Some(2.0));
+        assert_eq!(bucket("60"), Some(2.0));
+        assert_eq!(bucket("+Inf"), Some(3.0));
+        assert_eq!(
+            sample(&scrape, "parallel_exec_task_latency_seconds_count"),
+            Some(3.0)
+        );
+        assert_eq!(
+            sample(&scrape, "parallel_exec_task_latency_seconds_sum"),
+            Some(120.73)
+        );
+    }
+}
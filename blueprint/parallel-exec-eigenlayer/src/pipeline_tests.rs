@@ -0,0 +1,224 @@
+//! End-to-end test of the operator's task pipeline against a mock task manager.
+//!
+//! [`MockChain`] is an in-memory JSON-RPC node holding the `NewTaskCreated` logs of a task
+//! manager. Tasks emitted on it go through the same path as on a real chain: the
+//! [`RpcEventSource`] polls them, the [`ReconnectingListener`] checks them against the chain
+//! and queues them, and [`process_task_event`] computes and signs them, with the helper API
+//! and the aggregator replaced by mocks.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use alloy_primitives::{Address, Bytes, B256};
+use alloy_sol_types::SolEvent;
+use blueprint_sdk::config::GadgetConfiguration;
+use futures::StreamExt;
+use serde_json::{json, Value};
+
+use crate::api_client::ApiClient;
+use crate::config::{Secret, SignerSettings};
+use crate::constants::PROCESSED_TASKS_WINDOW;
+use crate::contexts::processed_tasks::ProcessedTasks;
+use crate::contexts::task_limiter::TaskLimiter;
+use crate::contexts::x_square::{EigenSquareContext, OperatorIdCache};
+use crate::jobs::compute_x_square::{
+    preload_task_signer, process_task_event, ResultHashMode, TaskStatus,
+};
+use crate::listener::{ReconnectingListener, RpcEventSource, TaskQueue};
+use crate::scheduler::ParallelSchedule;
+use crate::signer::{SignatureScheme, SignedTaskPayload, SignerBackend, SigningDomain};
+use crate::test_support::{serve_json_rpc, MockAggregator, MockApi};
+use crate::IIncredibleSquaringTaskManager::Task;
+use crate::IncredibleSquaringTaskManager::NewTaskCreated;
+
+const TASK_MANAGER: Address = Address::repeat_byte(0x42);
+const CHAIN_ID: u64 = 31337;
+const BLOCK_HASH: &str = "0x0a885e8a3a5cb03e0540e7ef65734c46189f17a65596009570a227d3abb6d466";
+const BLS_SECRET: &str = "12345";
+
+/// Hash of the canonical block `block_number` of the mock chain
+fn block_hash(block_number: u64) -> B256 {
+    B256::left_padding_from(&block_number.to_be_bytes())
+}
+
+#[derive(Debug, Default)]
+struct ChainState {
+    /// Every `NewTaskCreated` log emitted so far, as returned by `eth_getLogs`
+    logs: Vec<Value>,
+    /// Logs emitted since the filter was installed or last polled
+    filter_changes: VecDeque<Value>,
+    filter_installed: bool,
+}
+
+/// JSON-RPC node serving the `NewTaskCreated` logs of a task manager at [`TASK_MANAGER`]
+#[derive(Debug, Clone)]
+struct MockChain {
+    endpoint: String,
+    state: Arc<Mutex<ChainState>>,
+}
+
+impl MockChain {
+    async fn start() -> Self {
+        let state = Arc::<Mutex<ChainState>>::default();
+        let answered = state.clone();
+        let endpoint =
+            serve_json_rpc(move |method, params| answer(&answered, method, params)).await;
+        Self { endpoint, state }
+    }
+
+    /// Emits `NewTaskCreated` for `task` in block `block_number`, as the task manager does
+    /// when a task is created
+    fn emit_task(&self, block_number: u64, task_index: u32, task: Task) {
+        let event = NewTaskCreated {
+            taskIndex: task_index,
+            task,
+        };
+        let data = event.encode_log_data();
+        let log = json!({
+            "address": TASK_MANAGER,
+            "topics": data.topics(),
+            "data": data.data,
+            "blockNumber": format!("{:#x}", block_number),
+            "blockHash": block_hash(block_number),
+            "transactionHash": B256::repeat_byte(task_index as u8),
+            "transactionIndex": "0x0",
+            "logIndex": "0x0",
+            "removed": false,
+        });
+
+        let mut state = self.state.lock().unwrap();
+        state.logs.push(log.clone());
+        if state.filter_installed {
+            state.filter_changes.push_back(log);
+        }
+    }
+}
+
+fn answer(state: &Mutex<ChainState>, method: &str, params: &Value) -> Value {
+    let mut state = state.lock().unwrap();
+    match method {
+        "eth_newFilter" => {
+            state.filter_installed = true;
+            json!("0x1")
+        }
+        "eth_getFilterChanges" => Value::Array(state.filter_changes.drain(..).collect()),
+        "eth_getLogs" => Value::Array(state.logs.clone()),
+        "eth_getBlockByNumber" => {
+            let number = params[0].as_str().unwrap_or("0x0");
+            let number = u64::from_str_radix(number.trim_start_matches("0x"), 16).unwrap_or(0);
+            json!({ "number": format!("{:#x}", number), "hash": block_hash(number) })
+        }
+        "eth_chainId" => json!(format!("{:#x}", CHAIN_ID)),
+        _ => json!("0x1"),
+    }
+}
+
+/// Block 42 with transactions writing the same slot of accounts 1, 2 and 1, which are
+/// scheduled as `[[0, 1], [2]]`
+fn helper_api_body() -> String {
+    let write = |address: Address| json!({ "access_list": [{ "address": address, "storage_keys": [B256::ZERO] }] });
+    json!({
+        "status": "success",
+        "message": "ok",
+        "data": [{
+            "hash": BLOCK_HASH,
+            "number": "0x2a",
+            "timestamp": "0x67c2a1b0",
+            "transactions_root": B256::ZERO.to_string(),
+            "parent_hash": B256::ZERO.to_string(),
+            "transactions": [
+                write(Address::repeat_byte(1)),
+                write(Address::repeat_byte(2)),
+                write(Address::repeat_byte(1)),
+            ],
+        }],
+    })
+    .to_string()
+}
+
+fn operator_context(aggregator: Arc<MockAggregator>) -> EigenSquareContext {
+    EigenSquareContext {
+        client: aggregator,
+        api_client: ApiClient::new().with_transport(Arc::new(MockApi {
+            body: helper_api_body(),
+        })),
+        response_queue: None,
+        schedule_artifacts: None,
+        api_recordings: None,
+        processed_tasks: ProcessedTasks::new(PROCESSED_TASKS_WINDOW),
+        task_limiter: TaskLimiter::new(16, 256),
+        operator_id: OperatorIdCache::default(),
+        signature_scheme: SignatureScheme::Bls,
+        task_signer: None,
+        operator_quorums: None,
+        signing_domain: SigningDomain::new(CHAIN_ID, TASK_MANAGER),
+        result_hash_mode: ResultHashMode::default(),
+        dry_run: false,
+        task_deadline: None,
+        #[cfg(feature = "operator-id-override")]
+        operator_id_override: None,
+        #[cfg(feature = "metrics")]
+        metrics: crate::metrics::Metrics::new(),
+        std_config: GadgetConfiguration::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_emitted_task_is_signed_and_sent_to_the_aggregator() {
+    let chain = MockChain::start().await;
+    let aggregator = Arc::new(MockAggregator::default());
+    let mut ctx = operator_context(aggregator.clone());
+    // The operator's key handed to it directly and loaded as in `main`
+    let settings = SignerSettings {
+        backend: SignerBackend::Env,
+        bls_secret: Some(Secret::new(BLS_SECRET)),
+        ..Default::default()
+    };
+    ctx.task_signer = preload_task_signer(&ctx, &settings).await.unwrap();
+
+    // The listener and queue wired up as in `main`
+    let source = RpcEventSource::new(&chain.endpoint, chain.endpoint.clone(), TASK_MANAGER)
+        .unwrap()
+        .with_poll_interval(Duration::from_millis(20));
+    let (task_queue, mut queued_tasks) = TaskQueue::new(8);
+    tokio::spawn(async move {
+        let listener = ReconnectingListener::new(source, 0);
+        let handle = |event| {
+            let task_queue = task_queue.clone();
+            async move { task_queue.push(event).await }
+        };
+        listener.run(handle, std::future::pending()).await;
+    });
+
+    chain.emit_task(
+        43,
+        7,
+        Task {
+            taskCreatedBlock: 42,
+            quorumNumbers: Bytes::from(vec![0]),
+            quorumThresholdPercentage: 67,
+        },
+    );
+    let event = tokio::time::timeout(Duration::from_secs(5), queued_tasks.next())
+        .await
+        .expect("the emitted task reaches the queue")
+        .unwrap();
+    assert_eq!(event.block_number, 43);
+    assert_eq!(event.event.taskIndex, 7);
+
+    let status = process_task_event(&ctx, &event).await;
+    assert_eq!(TaskStatus::from_code(status), Some(TaskStatus::Ok));
+
+    // The aggregator received the schedule hash of the task's block, signed by the operator
+    let received = aggregator.received();
+    assert_eq!(received.len(), 1);
+    let SignedTaskPayload::Bls(signed) = &received[0] else {
+        panic!("expected a BLS signed task response");
+    };
+    let expected = ParallelSchedule::new(BLOCK_HASH.parse().unwrap(), vec![vec![0, 1], vec![2]]);
+    assert_eq!(signed.task_response.referenceTaskIndex, 7);
+    assert_eq!(signed.task_response.resultHash, expected.hash());
+    let signer = ctx.task_signer.as_ref().unwrap();
+    assert!(signer.verify(&received[0], &ctx.signing_domain));
+}
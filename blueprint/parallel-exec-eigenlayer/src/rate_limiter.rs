@@ -0,0 +1,152 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tokio::time::sleep;
+
+/// How many requests a [`RateLimiter`] lets through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Time between two requests once the burst is used up
+    pub interval: Duration,
+    /// Requests allowed back to back after a quiet period
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Allows `requests` requests per minute, one at a time
+    pub fn per_minute(requests: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(60) / requests.max(1),
+            burst: 1,
+        }
+    }
+
+    /// Allows `requests` requests per second, one at a time
+    pub fn per_second(requests: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / requests.max(1),
+            burst: 1,
+        }
+    }
+
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+        self
+    }
+
+    /// How far ahead of the schedule a burst may run
+    fn tolerance(&self) -> Duration {
+        self.interval * self.burst.saturating_sub(1)
+    }
+}
+
+/// Token bucket shared between clones, spacing requests out so an upstream rate limit is
+/// never hit.
+///
+/// Tracks when the bucket would be full again rather than counting tokens, so a request
+/// reserves its slot before it waits and concurrent callers are served in order.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    /// Time at which the bucket is full again
+    full_at: Arc<Mutex<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            full_at: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Waits until a request may be sent, returning how long it waited
+    pub async fn acquire(&self) -> Duration {
+        let wait = self.reserve(Instant::now());
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+        wait
+    }
+
+    /// Holds back every request until `delay` from now, e.g. as told by a `Retry-After` header
+    pub fn pause_for(&self, delay: Duration) {
+        let resume_at = Instant::now() + delay;
+        let mut full_at = self.full_at.lock();
+        *full_at = (*full_at).max(resume_at + self.limit.tolerance());
+    }
+
+    /// Takes the next slot and returns how long after `now` it opens
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut full_at = self.full_at.lock();
+        let start = (*full_at).max(now);
+        let opens_at = start
+            .checked_sub(self.limit.tolerance())
+            .unwrap_or(now)
+            .max(now);
+        *full_at = start + self.limit.interval;
+        opens_at - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_intervals() {
+        assert_eq!(RateLimit::per_minute(30).interval, Duration::from_secs(2));
+        assert_eq!(
+            RateLimit::per_second(4).interval,
+            Duration::from_millis(250)
+        );
+        assert_eq!(RateLimit::per_second(0).interval, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_burst_is_allowed_then_requests_are_spaced() {
+        let limiter = RateLimiter::new(RateLimit::per_second(10).with_burst(3));
+        let now = Instant::now();
+
+        let waits: Vec<_> = (0..5).map(|_| limiter.reserve(now)).collect();
+        assert_eq!(
+            waits,
+            vec![
+                Duration::ZERO,
+                Duration::ZERO,
+                Duration::ZERO,
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+            ]
+        );
+
+        // A quiet period refills the bucket
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.reserve(later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_pause_holds_back_the_next_request() {
+        let limiter = RateLimiter::new(RateLimit::per_second(10).with_burst(3));
+        limiter.pause_for(Duration::from_secs(2));
+
+        let wait = limiter.reserve(Instant::now());
+        assert!(wait > Duration::from_millis(1900) && wait <= Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_spaces_requests_by_the_interval() {
+        let limiter = RateLimiter::new(RateLimit::per_second(20));
+        let started = Instant::now();
+        for _ in 0..4 {
+            limiter.acquire().await;
+        }
+        // The first request goes out right away, the other three 50ms apart
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+}
@@ -0,0 +1,226 @@
+use std::fmt::Debug;
+
+use alloy_primitives::aliases::U192;
+use alloy_primitives::Address;
+use alloy_sol_types::sol;
+use async_trait::async_trait;
+use blueprint_sdk::utils::evm::get_provider_http;
+use eigensdk::crypto_bls::OperatorId;
+use thiserror::Error;
+
+use crate::contexts::task_config::MAX_QUORUM_COUNT;
+use crate::IncredibleSquaringTaskManager;
+
+sol! {
+    /// The part of the EigenLayer registry coordinator read at startup
+    #[sol(rpc)]
+    interface IRegistryCoordinator {
+        function getCurrentQuorumBitmap(bytes32 operatorId) external view returns (uint192);
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RegistrationError {
+    #[error("Failed to read the registry coordinator: {0}")]
+    Registry(String),
+    #[error(
+        "Operator {operator_id} of the configured BLS key is not registered in any quorum of \
+         registry coordinator {registry_coordinator}, so none of its task responses would be \
+         counted. Register the operator with the AVS, or point the keystore at the BLS key of \
+         a registered operator"
+    )]
+    NotRegistered {
+        operator_id: OperatorId,
+        registry_coordinator: Address,
+    },
+}
+
+/// Answers which quorums an operator is registered in, so the startup check can run against
+/// the registry coordinator or a mock in tests
+#[async_trait]
+pub trait OperatorRegistry: Debug + Send + Sync {
+    /// Address of the registry, named in errors
+    fn address(&self) -> Address;
+
+    /// Returns the quorums `operator_id` is currently registered in, in ascending order and
+    /// empty if the operator is not registered
+    async fn registered_quorums(
+        &self,
+        operator_id: OperatorId,
+    ) -> Result<Vec<u8>, RegistrationError>;
+}
+
+/// The registry coordinator of the AVS, as read over RPC
+#[derive(Debug, Clone)]
+pub struct RegistryCoordinator {
+    address: Address,
+    http_endpoint: String,
+}
+
+impl RegistryCoordinator {
+    pub fn new(address: Address, http_endpoint: impl Into<String>) -> Self {
+        Self {
+            address,
+            http_endpoint: http_endpoint.into(),
+        }
+    }
+
+    /// Looks up the registry coordinator the task manager at `task_manager_address` checks
+    /// signatures against
+    pub async fn for_task_manager(
+        task_manager_address: Address,
+        http_endpoint: impl Into<String>,
+    ) -> Result<Self, RegistrationError> {
+        let http_endpoint = http_endpoint.into();
+        let task_manager = IncredibleSquaringTaskManager::new(
+            task_manager_address,
+            get_provider_http(&http_endpoint),
+        );
+        let address = task_manager
+            .registryCoordinator()
+            .call()
+            .await
+            .map_err(|e| RegistrationError::Registry(e.to_string()))?
+            ._0;
+        Ok(Self::new(address, http_endpoint))
+    }
+}
+
+#[async_trait]
+impl OperatorRegistry for RegistryCoordinator {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn registered_quorums(
+        &self,
+        operator_id: OperatorId,
+    ) -> Result<Vec<u8>, RegistrationError> {
+        let registry =
+            IRegistryCoordinator::new(self.address, get_provider_http(&self.http_endpoint));
+        let bitmap = registry
+            .getCurrentQuorumBitmap(operator_id)
+            .call()
+            .await
+            .map_err(|e| RegistrationError::Registry(e.to_string()))?
+            ._0;
+        Ok(quorums_of_bitmap(bitmap))
+    }
+}
+
+/// Lists the quorums set in a quorum bitmap of the registry coordinator
+pub fn quorums_of_bitmap(bitmap: U192) -> Vec<u8> {
+    (0..MAX_QUORUM_COUNT)
+        .filter(|&quorum| bitmap.bit(quorum as usize))
+        .collect()
+}
+
+/// Checks that `operator_id` is registered in `registry`, returning the quorums it is
+/// registered in.
+///
+/// An unregistered operator can sign every task, but the aggregator never counts its
+/// signatures, so this is checked once at startup instead of discovered task by task.
+pub async fn check_operator_registered(
+    registry: &dyn OperatorRegistry,
+    operator_id: OperatorId,
+) -> Result<Vec<u8>, RegistrationError> {
+    let quorums = registry.registered_quorums(operator_id).await?;
+    if quorums.is_empty() {
+        return Err(RegistrationError::NotRegistered {
+            operator_id,
+            registry_coordinator: registry.address(),
+        });
+    }
+    Ok(quorums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Registry holding the quorums of a fixed set of operators
+    #[derive(Debug, Default)]
+    struct MockRegistry {
+        operators: HashMap<OperatorId, Vec<u8>>,
+        unreachable: bool,
+    }
+
+    #[async_trait]
+    impl OperatorRegistry for MockRegistry {
+        fn address(&self) -> Address {
+            Address::repeat_byte(0x42)
+        }
+
+        async fn registered_quorums(
+            &self,
+            operator_id: OperatorId,
+        ) -> Result<Vec<u8>, RegistrationError> {
+            if self.unreachable {
+                return Err(RegistrationError::Registry(
+                    "connection refused".to_string(),
+                ));
+            }
+            Ok(self
+                .operators
+                .get(&operator_id)
+                .cloned()
+                .unwrap_or_default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_operator_passes_the_check() {
+        let operator_id = OperatorId::repeat_byte(0x11);
+        let registry = MockRegistry {
+            operators: HashMap::from([(operator_id, vec![0, 2])]),
+            ..Default::default()
+        };
+
+        let quorums = check_operator_registered(&registry, operator_id)
+            .await
+            .unwrap();
+        assert_eq!(quorums, vec![0, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_unregistered_operator_fails_the_check() {
+        let registry = MockRegistry {
+            operators: HashMap::from([(OperatorId::repeat_byte(0x11), vec![0])]),
+            ..Default::default()
+        };
+
+        let unregistered = OperatorId::repeat_byte(0x22);
+        let error = check_operator_registered(&registry, unregistered)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            RegistrationError::NotRegistered { operator_id, registry_coordinator }
+                if operator_id == unregistered && registry_coordinator == registry.address()
+        ));
+        // The error names the operator and what to do about it
+        let message = error.to_string();
+        assert!(message.contains(&unregistered.to_string()));
+        assert!(message.contains("Register the operator"));
+
+        let unreachable = MockRegistry {
+            unreachable: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            check_operator_registered(&unreachable, unregistered).await,
+            Err(RegistrationError::Registry(_))
+        ));
+    }
+
+    #[test]
+    fn test_quorums_of_bitmap() {
+        assert!(quorums_of_bitmap(U192::ZERO).is_empty());
+        assert_eq!(quorums_of_bitmap(U192::from(0b101u64)), vec![0, 2]);
+        assert_eq!(
+            quorums_of_bitmap(U192::from(1u64) << 191),
+            vec![MAX_QUORUM_COUNT - 1]
+        );
+    }
+}
@@ -0,0 +1,1246 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use alloy_primitives::{keccak256, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::api_client::{parse_block_hash, ApiClientError, Block};
+use crate::deps::AccessKey;
+
+/// Identifies a transaction by its position in the block
+pub type TxId = u32;
+
+/// The state a transaction reads and writes.
+///
+/// Storage is tracked per `(account, slot)` pair, so transactions touching different slots of
+/// the same contract stay independent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxAccess {
+    pub id: TxId,
+    pub reads: BTreeSet<AccessKey>,
+    pub writes: BTreeSet<AccessKey>,
+    /// Estimated gas used, only considered by [`schedule_balanced`]
+    pub gas: u64,
+    /// The accessed state is unknown, so the transaction conflicts with every other one
+    pub unknown_access: bool,
+}
+
+impl TxAccess {
+    pub fn new(id: TxId) -> Self {
+        Self {
+            id,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a transaction whose accessed state is unknown, which is scheduled serially
+    pub fn unknown(id: TxId) -> Self {
+        Self {
+            id,
+            unknown_access: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_reads<K: Into<AccessKey>>(mut self, reads: impl IntoIterator<Item = K>) -> Self {
+        self.reads.extend(reads.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_writes<K: Into<AccessKey>>(mut self, writes: impl IntoIterator<Item = K>) -> Self {
+        self.writes.extend(writes.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_gas(mut self, gas: u64) -> Self {
+        self.gas = gas;
+        self
+    }
+
+    /// Returns true if the two transactions cannot run concurrently, i.e. one of them writes
+    /// an account or slot the other reads or writes, or the access of either one is unknown
+    pub fn conflicts_with(&self, other: &TxAccess) -> bool {
+        self.unknown_access
+            || other.unknown_access
+            || !self.writes.is_disjoint(&other.writes)
+            || !self.writes.is_disjoint(&other.reads)
+            || !self.reads.is_disjoint(&other.writes)
+    }
+}
+
+/// Partitions `txs` into batches that are safe to execute concurrently.
+///
+/// Transactions are taken in block order, i.e. by id regardless of the order they are given
+/// in: a transaction is placed in the batch right after the last batch holding an earlier
+/// transaction it conflicts with, so running the batches one after another preserves the
+/// result of serial execution. A transaction with unknown access gets a batch of its own.
+pub fn schedule_parallel(txs: &[TxAccess]) -> Vec<Vec<TxId>> {
+    schedule_indexed(txs).0
+}
+
+/// Returns the gas of the longest chain of dependent transactions in `txs`, i.e. the gas a
+/// block takes at the least however many transactions run concurrently.
+///
+/// A transaction depends on every earlier one it conflicts with, as in [`schedule_parallel`].
+/// Transactions without an estimate weigh one gas.
+pub fn critical_path_gas(txs: &[TxAccess]) -> u64 {
+    schedule_indexed(txs).1.critical_path
+}
+
+/// Like [`schedule_parallel`], also returning the index left after placing every transaction
+fn schedule_indexed(txs: &[TxAccess]) -> (Vec<Vec<TxId>>, BatchIndex) {
+    let mut batches: Vec<Vec<TxId>> = Vec::new();
+    let mut index = BatchIndex::default();
+    for tx in in_block_order(txs) {
+        let batch = index.place(tx, batches.len());
+        if batch == batches.len() {
+            batches.push(Vec::new());
+        }
+        batches[batch].push(tx.id);
+    }
+    (batches, index)
+}
+
+/// The batches the transactions scheduled so far left each key in, which is all
+/// [`schedule_parallel`] needs to place the next transaction in block order
+#[derive(Debug, Clone, Default)]
+struct BatchIndex {
+    /// Highest batch that writes, respectively reads, each key so far
+    last_write: HashMap<AccessKey, usize>,
+    last_read: HashMap<AccessKey, usize>,
+    /// Batch of the last transaction with unknown access, nothing may run before it
+    barrier: Option<usize>,
+    /// Gas after which the writers, respectively readers, of each key so far are done, when
+    /// every transaction starts as soon as the ones it depends on are
+    write_done: HashMap<AccessKey, u64>,
+    read_done: HashMap<AccessKey, u64>,
+    /// Gas after which the last transaction with unknown access is done
+    barrier_done: u64,
+    /// Gas of the longest chain of dependent transactions so far
+    critical_path: u64,
+    /// Id of the last transaction placed
+    last_id: Option<TxId>,
+    num_txs: usize,
+}
+
+impl BatchIndex {
+    /// Returns the batch `tx` goes in, given the number of batches so far, and records the
+    /// keys it touches
+    fn place(&mut self, tx: &TxAccess, num_batches: usize) -> usize {
+        let batch = if tx.unknown_access {
+            num_batches
+        } else {
+            let after_writes = tx
+                .reads
+                .iter()
+                .chain(&tx.writes)
+                .filter_map(|key| self.last_write.get(key));
+            let after_reads = tx.writes.iter().filter_map(|key| self.last_read.get(key));
+            after_writes
+                .chain(after_reads)
+                .chain(&self.barrier)
+                .max()
+                .map_or(0, |&batch| batch + 1)
+        };
+        let start = if tx.unknown_access {
+            self.critical_path
+        } else {
+            let after_writes = tx
+                .reads
+                .iter()
+                .chain(&tx.writes)
+                .filter_map(|key| self.write_done.get(key));
+            let after_reads = tx.writes.iter().filter_map(|key| self.read_done.get(key));
+            after_writes
+                .chain(after_reads)
+                .fold(self.barrier_done, |start, &done| start.max(done))
+        };
+        let done = start + tx.gas.max(1);
+        self.critical_path = self.critical_path.max(done);
+        if tx.unknown_access {
+            self.barrier = Some(batch);
+            self.barrier_done = done;
+        }
+
+        for key in &tx.writes {
+            let entry = self.last_write.entry(*key).or_default();
+            *entry = (*entry).max(batch);
+            let entry = self.write_done.entry(*key).or_default();
+            *entry = (*entry).max(done);
+        }
+        for key in &tx.reads {
+            let entry = self.last_read.entry(*key).or_default();
+            *entry = (*entry).max(batch);
+            let entry = self.read_done.entry(*key).or_default();
+            *entry = (*entry).max(done);
+        }
+        self.last_id = Some(tx.id);
+        self.num_txs += 1;
+        batch
+    }
+}
+
+/// Assigns `txs` to `num_lanes` lanes that run concurrently, balancing their total gas.
+///
+/// Conflicting transactions, directly or through other transactions, always share a lane and
+/// keep their relative order within it, so lanes never need to synchronise. These groups are
+/// then placed heaviest first on the currently lightest lane, which keeps the heaviest lane
+/// within 4/3 of the optimum. A `num_lanes` of zero is treated as one.
+pub fn schedule_balanced(txs: &[TxAccess], num_lanes: usize) -> Vec<Vec<TxId>> {
+    let num_lanes = num_lanes.max(1);
+    let txs = in_block_order(txs);
+
+    // Union every transaction with the earlier ones it conflicts with
+    let mut parent: Vec<usize> = (0..txs.len()).collect();
+    let mut first_toucher: HashMap<AccessKey, usize> = HashMap::new();
+    let mut first_writer: HashMap<AccessKey, usize> = HashMap::new();
+    let mut readers: HashMap<AccessKey, Vec<usize>> = HashMap::new();
+    for (position, tx) in txs.iter().enumerate() {
+        for key in &tx.reads {
+            match first_writer.get(key) {
+                Some(&writer) => union(&mut parent, writer, position),
+                None => readers.entry(*key).or_default().push(position),
+            }
+            first_toucher.entry(*key).or_insert(position);
+        }
+        for key in &tx.writes {
+            if let Some(&toucher) = first_toucher.get(key) {
+                union(&mut parent, toucher, position);
+            }
+            // Readers seen before the first write were never joined, do it now
+            for reader in readers.remove(key).unwrap_or_default() {
+                union(&mut parent, reader, position);
+            }
+            first_writer.entry(*key).or_insert(position);
+            first_toucher.entry(*key).or_insert(position);
+        }
+    }
+    // A transaction with unknown access conflicts with everything, serialising the whole set
+    if txs.iter().any(|tx| tx.unknown_access) {
+        for position in 1..txs.len() {
+            union(&mut parent, 0, position);
+        }
+    }
+
+    // Collect the groups in order of their first transaction, with their total gas
+    let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+    let mut groups: Vec<(u64, Vec<usize>)> = Vec::new();
+    for (position, tx) in txs.iter().enumerate() {
+        let root = find(&mut parent, position);
+        let group = *group_of_root.entry(root).or_insert_with(|| {
+            groups.push((0, Vec::new()));
+            groups.len() - 1
+        });
+        groups[group].0 += tx.gas;
+        groups[group].1.push(position);
+    }
+
+    // Heaviest group first, ties broken by block order to keep the result deterministic
+    let mut order: Vec<usize> = (0..groups.len()).collect();
+    order.sort_by_key(|&group| std::cmp::Reverse(groups[group].0));
+
+    let mut lane_gas = vec![0u64; num_lanes];
+    let mut lanes: Vec<Vec<usize>> = vec![Vec::new(); num_lanes];
+    for group in order {
+        let lane = (0..num_lanes)
+            .min_by_key(|&lane| lane_gas[lane])
+            .unwrap_or_default();
+        lane_gas[lane] += groups[group].0;
+        lanes[lane].extend(&groups[group].1);
+    }
+
+    lanes
+        .into_iter()
+        .map(|mut positions| {
+            positions.sort_unstable();
+            positions
+                .into_iter()
+                .map(|position| txs[position].id)
+                .collect()
+        })
+        .collect()
+}
+
+/// Which transactions [`schedule_with_reorder`] may move past each other.
+///
+/// Transactions are order-sensitive unless marked commutative. Two conflicting commutative
+/// transactions, e.g. two increments of the same counter, give the same result in either
+/// order, so they only need to run in different batches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReorderConstraints {
+    commutative: BTreeSet<TxId>,
+}
+
+impl ReorderConstraints {
+    /// Keeps every transaction in block order
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_commutative(mut self, ids: impl IntoIterator<Item = TxId>) -> Self {
+        self.commutative.extend(ids);
+        self
+    }
+
+    pub fn is_commutative(&self, id: TxId) -> bool {
+        self.commutative.contains(&id)
+    }
+
+    /// Returns true if `a` and `b` may run in either order. A transaction with unknown access
+    /// never moves, whatever it is marked as.
+    fn may_swap(&self, a: &TxAccess, b: &TxAccess) -> bool {
+        !a.unknown_access
+            && !b.unknown_access
+            && self.is_commutative(a.id)
+            && self.is_commutative(b.id)
+    }
+}
+
+/// Like [`schedule_parallel`], but lets conflicting commutative transactions run in any
+/// order, which can shorten the chain of batches.
+///
+/// A transaction runs after every earlier transaction it conflicts with, unless both are
+/// commutative, in which case they only have to be in different batches. Every batch is
+/// filled in block order with the transactions whose predecessors all ran before it. With no
+/// commutative transactions this gives the same schedule as [`schedule_parallel`]. Compares
+/// every pair of transactions, so it is quadratic in their number.
+pub fn schedule_with_reorder(txs: &[TxAccess], constraints: &ReorderConstraints) -> Vec<Vec<TxId>> {
+    let txs = in_block_order(txs);
+
+    // Earlier transactions each one has to run after, and the later ones waiting on it
+    let mut pending = vec![0usize; txs.len()];
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); txs.len()];
+    for later in 0..txs.len() {
+        for earlier in 0..later {
+            if txs[earlier].conflicts_with(txs[later])
+                && !constraints.may_swap(txs[earlier], txs[later])
+            {
+                successors[earlier].push(later);
+                pending[later] += 1;
+            }
+        }
+    }
+
+    let mut placed = vec![false; txs.len()];
+    let mut remaining = txs.len();
+    let mut batches: Vec<Vec<TxId>> = Vec::new();
+    while remaining > 0 {
+        // The earliest ready transaction always fits, so every round places at least one
+        let mut batch: Vec<usize> = Vec::new();
+        for position in (0..txs.len()).filter(|&position| !placed[position]) {
+            if pending[position] == 0
+                && batch
+                    .iter()
+                    .all(|&other| !txs[other].conflicts_with(txs[position]))
+            {
+                batch.push(position);
+            }
+        }
+
+        for &position in &batch {
+            placed[position] = true;
+            for &successor in &successors[position] {
+                pending[successor] -= 1;
+            }
+        }
+        remaining -= batch.len();
+        batches.push(batch.into_iter().map(|position| txs[position].id).collect());
+    }
+
+    batches
+}
+
+/// Summary of how parallelisable a set of transactions is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScheduleStats {
+    pub num_txs: usize,
+    pub num_batches: usize,
+    pub max_batch_size: usize,
+    /// Serial gas divided by the gas of the critical path, i.e. the longest chain of dependent
+    /// transactions. 1.0 means nothing runs in parallel.
+    pub estimated_speedup: f64,
+}
+
+impl ScheduleStats {
+    /// Computes the stats of `batches`, as scheduled from `txs`.
+    ///
+    /// Every transaction weighs at least one gas, so transactions without an estimate count
+    /// as equally expensive. The critical path follows the dependencies between `txs`, see
+    /// [`critical_path_gas`].
+    pub fn new(txs: &[TxAccess], batches: &[Vec<TxId>]) -> Self {
+        let gas: HashMap<TxId, u64> = txs.iter().map(|tx| (tx.id, tx.gas.max(1))).collect();
+        let gas_of = |id: &TxId| gas.get(id).copied().unwrap_or(1);
+
+        let serial_gas: u64 = batches.iter().flatten().map(gas_of).sum();
+        let critical_path_gas = critical_path_gas(txs);
+        let estimated_speedup = if critical_path_gas == 0 {
+            1.0
+        } else {
+            serial_gas as f64 / critical_path_gas as f64
+        };
+
+        Self {
+            num_txs: batches.iter().map(Vec::len).sum(),
+            num_batches: batches.len(),
+            max_batch_size: batches.iter().map(Vec::len).max().unwrap_or(0),
+            estimated_speedup,
+        }
+    }
+}
+
+/// Like [`schedule_parallel`], also returning the stats of the schedule
+pub fn schedule_parallel_with_stats(txs: &[TxAccess]) -> (Vec<Vec<TxId>>, ScheduleStats) {
+    let batches = schedule_parallel(txs);
+    let stats = ScheduleStats::new(txs, &batches);
+    (batches, stats)
+}
+
+/// Returns the transactions sorted by id, so the input order never changes a schedule
+fn in_block_order(txs: &[TxAccess]) -> Vec<&TxAccess> {
+    let mut ordered: Vec<&TxAccess> = txs.iter().collect();
+    ordered.sort_by_key(|tx| tx.id);
+    ordered
+}
+
+fn find(parent: &mut [usize], mut node: usize) -> usize {
+    while parent[node] != node {
+        parent[node] = parent[parent[node]];
+        node = parent[node];
+    }
+    node
+}
+
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parent, a), find(parent, b));
+    // Keep the earliest transaction as the root
+    if a < b {
+        parent[b] = a;
+    } else {
+        parent[a] = b;
+    }
+}
+
+/// The parallel execution plan for a block, which is what operators attest to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParallelSchedule {
+    pub block_hash: B256,
+    /// Batches in execution order, the transactions within a batch can run concurrently
+    pub batches: Vec<Vec<TxId>>,
+    /// Estimated gas of the transactions that have an estimate
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub gas: BTreeMap<TxId, u64>,
+    /// Gas of the longest chain of dependent transactions, known when the schedule was built
+    /// from the transactions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    critical_path: Option<u64>,
+    /// Hash of the block data the schedule was computed from, attested to along with the
+    /// batches when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_data_hash: Option<B256>,
+    /// Keys touched by the transactions added with [`ParallelSchedule::add_tx`]
+    #[serde(skip)]
+    index: BatchIndex,
+}
+
+/// Schedules are equal when they attest to the same plan, however they were built
+impl PartialEq for ParallelSchedule {
+    fn eq(&self, other: &Self) -> bool {
+        self.block_hash == other.block_hash
+            && self.batches == other.batches
+            && self.gas == other.gas
+            && self.critical_path() == other.critical_path()
+            && self.block_data_hash == other.block_data_hash
+    }
+}
+
+impl Eq for ParallelSchedule {}
+
+impl ParallelSchedule {
+    pub fn new(block_hash: B256, batches: Vec<Vec<TxId>>) -> Self {
+        Self {
+            block_hash,
+            batches,
+            gas: BTreeMap::new(),
+            critical_path: None,
+            block_data_hash: None,
+            index: BatchIndex::default(),
+        }
+    }
+
+    /// Weighs the scheduled transactions by `gas`, estimates of zero are left out
+    pub fn with_gas(mut self, gas: impl IntoIterator<Item = (TxId, u64)>) -> Self {
+        self.gas.extend(gas.into_iter().filter(|&(_, gas)| gas > 0));
+        self
+    }
+
+    /// Binds the schedule to `hash` of the block data it was computed from, so operators that
+    /// hashed different data never sign the same schedule
+    pub fn with_block_data_hash(mut self, hash: B256) -> Self {
+        self.block_data_hash = Some(hash);
+        self
+    }
+
+    /// Schedules `txs` like [`schedule_parallel`], weighing them by their gas and keeping the
+    /// [critical path](ParallelSchedule::critical_path) of their dependencies
+    pub fn from_txs(block_hash: B256, txs: &[TxAccess]) -> Self {
+        let (batches, index) = schedule_indexed(txs);
+        let mut schedule =
+            Self::new(block_hash, batches).with_gas(txs.iter().map(|tx| (tx.id, tx.gas)));
+        schedule.critical_path = Some(index.critical_path);
+        schedule
+    }
+
+    /// Schedules the transactions of `block` by their access lists
+    pub fn from_block(block: &Block) -> Result<Self, ApiClientError> {
+        Self::from_block_with_stats(block).map(|(schedule, _)| schedule)
+    }
+
+    /// Like [`ParallelSchedule::from_block`], also returning the stats of the schedule
+    pub fn from_block_with_stats(block: &Block) -> Result<(Self, ScheduleStats), ApiClientError> {
+        let block_hash = parse_block_hash(&block.hash)?;
+        let txs = block.tx_accesses();
+        let mut schedule = Self::from_txs(block_hash, &txs);
+        schedule.canonicalize();
+        let stats = ScheduleStats::new(&txs, &schedule.batches);
+        Ok((schedule, stats))
+    }
+
+    /// Appends `tx` to the schedule without rescheduling the transactions before it, and
+    /// returns the batch it was placed in.
+    ///
+    /// Adding a block's transactions one by one, starting from a schedule without batches,
+    /// gives the same schedule as [`schedule_parallel`] over all of them, already in canonical
+    /// form. Returns `None` and leaves the schedule unchanged if `tx` does not come after every
+    /// transaction added so far, or if the schedule holds batches that were not built by
+    /// `add_tx`, e.g. ones parsed from JSON.
+    pub fn add_tx(&mut self, tx: &TxAccess) -> Option<usize> {
+        if self.index.num_txs != self.num_txs()
+            || self.index.last_id.is_some_and(|last| tx.id <= last)
+        {
+            return None;
+        }
+
+        let batch = self.index.place(tx, self.batches.len());
+        if batch == self.batches.len() {
+            self.batches.push(Vec::new());
+        }
+        self.batches[batch].push(tx.id);
+        if tx.gas > 0 {
+            self.gas.insert(tx.id, tx.gas);
+        }
+        self.critical_path = Some(self.index.critical_path);
+        Some(batch)
+    }
+
+    /// Puts the schedule in its canonical form, which every operator encodes byte for byte
+    /// the same: transaction ids ascending within each batch, empty batches dropped and the
+    /// batches ordered by their lowest id.
+    ///
+    /// For batches built from dependency layers this keeps the execution order, since every
+    /// transaction depends on a lower id in the previous batch.
+    pub fn canonicalize(&mut self) {
+        for batch in &mut self.batches {
+            batch.sort_unstable();
+        }
+        self.batches.retain(|batch| !batch.is_empty());
+        self.batches.sort_by_key(|batch| batch[0]);
+    }
+
+    /// Returns the number of scheduled transactions
+    pub fn num_txs(&self) -> usize {
+        self.batches.iter().map(Vec::len).sum()
+    }
+
+    /// Returns the gas of the critical path, i.e. the longest chain of dependent transactions.
+    ///
+    /// For a chain of dependent transactions this is their total gas, for independent ones the
+    /// gas of the heaviest. Transactions without an estimate weigh one gas, as in
+    /// [`ScheduleStats`]. A schedule not built from its transactions, e.g. with
+    /// [`ParallelSchedule::new`], does not know their dependencies and takes every batch to
+    /// depend on the one before, i.e. sums the heaviest transaction of every batch.
+    pub fn critical_path(&self) -> u64 {
+        if let Some(critical_path) = self.critical_path {
+            return critical_path;
+        }
+        self.batches
+            .iter()
+            .map(|batch| {
+                batch
+                    .iter()
+                    .map(|id| self.gas.get(id).copied().unwrap_or(1))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    /// Encodes the schedule as the block hash, the number of batches as a big-endian `u32`,
+    /// then every batch as its length followed by its transaction ids, all big-endian `u32`s,
+    /// then the [critical path](ParallelSchedule::critical_path) as a big-endian `u64`, so
+    /// operators attest to it along with the batches, and finally the block data hash if set.
+    ///
+    /// The schedule is encoded as is, see [`ParallelSchedule::canonicalize`].
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(40 + 4 * (1 + self.batches.len() + self.num_txs()));
+        out.extend_from_slice(self.block_hash.as_slice());
+        out.extend_from_slice(&(self.batches.len() as u32).to_be_bytes());
+        for batch in &self.batches {
+            out.extend_from_slice(&(batch.len() as u32).to_be_bytes());
+            for id in batch {
+                out.extend_from_slice(&id.to_be_bytes());
+            }
+        }
+        out.extend_from_slice(&self.critical_path().to_be_bytes());
+        if let Some(block_data_hash) = self.block_data_hash {
+            out.extend_from_slice(block_data_hash.as_slice());
+        }
+        out
+    }
+
+    /// Returns the keccak256 hash of the canonical encoding, signed as the task result
+    pub fn hash(&self) -> B256 {
+        let mut canonical = self.clone();
+        canonical.canonicalize();
+        keccak256(canonical.encode())
+    }
+
+    /// Serializes the schedule as JSON, with the block hash as a hex string and the batches as
+    /// arrays of transaction ids.
+    ///
+    /// The schedule is serialized as is, so two schedules give the same JSON exactly when they
+    /// are equal.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Parses a schedule serialized with [`ParallelSchedule::to_json`]
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Address;
+
+    fn account(byte: u8) -> Address {
+        Address::repeat_byte(byte)
+    }
+
+    fn slot(address: u8, slot: u8) -> (Address, B256) {
+        (Address::repeat_byte(address), B256::repeat_byte(slot))
+    }
+
+    /// Checks that no two transactions in the same batch conflict
+    fn assert_batches_independent(txs: &[TxAccess], batches: &[Vec<TxId>]) {
+        for batch in batches {
+            for (i, a_id) in batch.iter().enumerate() {
+                for b_id in &batch[i + 1..] {
+                    let a = txs.iter().find(|tx| tx.id == *a_id).unwrap();
+                    let b = txs.iter().find(|tx| tx.id == *b_id).unwrap();
+                    assert!(!a.conflicts_with(b), "{} and {} share a batch", a.id, b.id);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_disjoint_transactions_share_one_batch() {
+        let txs: Vec<_> = (0..4)
+            .map(|i| {
+                TxAccess::new(i)
+                    .with_reads([account(0xaa)])
+                    .with_writes([account(i as u8)])
+            })
+            .collect();
+
+        let batches = schedule_parallel(&txs);
+        assert_eq!(batches, vec![vec![0, 1, 2, 3]]);
+        assert_batches_independent(&txs, &batches);
+    }
+
+    #[test]
+    fn test_conflicting_transactions_are_split() {
+        let txs = vec![
+            TxAccess::new(0).with_writes([account(1)]),
+            TxAccess::new(1).with_writes([account(1)]),
+            TxAccess::new(2).with_reads([account(1)]),
+            TxAccess::new(3).with_writes([account(2)]),
+        ];
+
+        let batches = schedule_parallel(&txs);
+        assert_eq!(batches, vec![vec![0, 3], vec![1], vec![2]]);
+        assert_batches_independent(&txs, &batches);
+    }
+
+    #[test]
+    fn test_read_then_write_runs_after_the_read() {
+        let txs = vec![
+            TxAccess::new(0).with_reads([account(1)]),
+            TxAccess::new(1).with_reads([account(1)]),
+            TxAccess::new(2).with_writes([account(1)]),
+        ];
+
+        assert_eq!(schedule_parallel(&txs), vec![vec![0, 1], vec![2]]);
+    }
+
+    #[test]
+    fn test_chained_dependencies_are_serialised() {
+        // 0 -> 1 -> 2 through different accounts, 3 is independent
+        let txs = vec![
+            TxAccess::new(0).with_writes([account(1)]),
+            TxAccess::new(1)
+                .with_reads([account(1)])
+                .with_writes([account(2)]),
+            TxAccess::new(2).with_reads([account(2)]),
+            TxAccess::new(3).with_writes([account(3)]),
+        ];
+
+        let batches = schedule_parallel(&txs);
+        assert_eq!(batches, vec![vec![0, 3], vec![1], vec![2]]);
+        assert_batches_independent(&txs, &batches);
+    }
+
+    #[test]
+    fn test_different_slots_of_one_contract_share_a_batch() {
+        let txs = vec![
+            TxAccess::new(0).with_writes([slot(1, 1)]),
+            TxAccess::new(1).with_writes([slot(1, 2)]),
+            TxAccess::new(2)
+                .with_reads([slot(1, 3)])
+                .with_writes([slot(1, 4)]),
+            TxAccess::new(3).with_reads([slot(1, 2)]),
+        ];
+
+        let batches = schedule_parallel(&txs);
+        assert_eq!(batches, vec![vec![0, 1, 2], vec![3]]);
+        assert_batches_independent(&txs, &batches);
+        assert!(!txs[0].conflicts_with(&txs[1]));
+        assert!(txs[1].conflicts_with(&txs[3]));
+
+        let txs: Vec<_> = txs.into_iter().map(|tx| tx.with_gas(21_000)).collect();
+        assert_eq!(
+            schedule_balanced(&txs, 3),
+            vec![vec![1, 3], vec![0], vec![2]]
+        );
+    }
+
+    #[test]
+    fn test_account_and_slot_keys_are_distinct() {
+        // A balance transfer to a contract does not conflict with its storage
+        let txs = vec![
+            TxAccess::new(0).with_writes([account(1)]),
+            TxAccess::new(1).with_writes([slot(1, 1)]),
+        ];
+        assert_eq!(schedule_parallel(&txs), vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_unknown_access_is_scheduled_serially() {
+        let txs = vec![
+            TxAccess::new(0).with_writes([account(1)]),
+            TxAccess::new(1).with_writes([account(2)]),
+            TxAccess::unknown(2),
+            TxAccess::new(3).with_writes([account(3)]),
+            TxAccess::new(4).with_writes([account(4)]),
+        ];
+
+        assert!(txs[2].conflicts_with(&txs[3]));
+        assert!(txs[3].conflicts_with(&txs[2]));
+        assert_eq!(
+            schedule_parallel(&txs),
+            vec![vec![0, 1], vec![2], vec![3, 4]]
+        );
+        assert_eq!(
+            schedule_balanced(&txs, 2),
+            vec![vec![0, 1, 2, 3, 4], vec![]]
+        );
+    }
+
+    #[test]
+    fn test_stats_for_fully_parallel_set() {
+        let txs: Vec<_> = (0..8)
+            .map(|i| {
+                TxAccess::new(i)
+                    .with_writes([account(i as u8)])
+                    .with_gas(21_000)
+            })
+            .collect();
+
+        let (batches, stats) = schedule_parallel_with_stats(&txs);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(stats.num_txs, 8);
+        assert_eq!(stats.num_batches, 1);
+        assert_eq!(stats.max_batch_size, 8);
+        assert!((stats.estimated_speedup - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stats_for_fully_serial_set() {
+        let txs: Vec<_> = (0..5)
+            .map(|i| {
+                TxAccess::new(i)
+                    .with_writes([account(1)])
+                    .with_gas(21_000 * (i as u64 + 1))
+            })
+            .collect();
+
+        let (_, stats) = schedule_parallel_with_stats(&txs);
+        assert_eq!(stats.num_txs, 5);
+        assert_eq!(stats.num_batches, 5);
+        assert_eq!(stats.max_batch_size, 1);
+        assert!((stats.estimated_speedup - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stats_weigh_the_longest_dependency_chain() {
+        // Critical path is 100 + 40, out of 190 serial gas
+        let txs = vec![
+            TxAccess::new(0).with_writes([account(1)]).with_gas(100),
+            TxAccess::new(1).with_writes([account(2)]).with_gas(50),
+            TxAccess::new(2).with_reads([account(1)]).with_gas(40),
+        ];
+
+        let (_, stats) = schedule_parallel_with_stats(&txs);
+        assert!((stats.estimated_speedup - 190.0 / 140.0).abs() < 1e-9);
+
+        // Batch maxima would give 100 + 100, but 3 only waits for the light transaction 1
+        let txs = vec![
+            TxAccess::new(0).with_writes([account(1)]).with_gas(100),
+            TxAccess::new(1).with_writes([account(2)]).with_gas(10),
+            TxAccess::new(2).with_reads([account(1)]).with_gas(10),
+            TxAccess::new(3).with_reads([account(2)]).with_gas(100),
+        ];
+        let (_, stats) = schedule_parallel_with_stats(&txs);
+        assert!((stats.estimated_speedup - 220.0 / 110.0).abs() < 1e-9);
+
+        let (_, empty) = schedule_parallel_with_stats(&[]);
+        assert_eq!(empty.num_batches, 0);
+        assert_eq!(empty.estimated_speedup, 1.0);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert!(schedule_parallel(&[]).is_empty());
+    }
+
+    /// Transaction 1 has to wait for 0, and 2 for 1, unless 1 and 2 commute
+    fn commuting_chain() -> Vec<TxAccess> {
+        vec![
+            TxAccess::new(0).with_writes([account(1)]),
+            TxAccess::new(1)
+                .with_reads([account(1)])
+                .with_writes([account(2)]),
+            TxAccess::new(2).with_writes([account(2)]),
+        ]
+    }
+
+    #[test]
+    fn test_reorder_keeps_order_sensitive_transactions_ordered() {
+        let txs = commuting_chain();
+        let batches = schedule_with_reorder(&txs, &ReorderConstraints::new());
+        assert_eq!(batches, vec![vec![0], vec![1], vec![2]]);
+        assert_eq!(batches, schedule_parallel(&txs));
+
+        // Only one side of the conflict commutes, so the order stays
+        let constraints = ReorderConstraints::new().with_commutative([2]);
+        assert_eq!(
+            schedule_with_reorder(&txs, &constraints),
+            vec![vec![0], vec![1], vec![2]]
+        );
+    }
+
+    #[test]
+    fn test_reorder_moves_commutative_transactions_forward() {
+        let txs = commuting_chain();
+        let constraints = ReorderConstraints::new().with_commutative([1, 2]);
+
+        let batches = schedule_with_reorder(&txs, &constraints);
+        assert_eq!(batches, vec![vec![0, 2], vec![1]]);
+        assert_batches_independent(&txs, &batches);
+        assert!(
+            ScheduleStats::new(&txs, &batches).max_batch_size
+                > ScheduleStats::new(&txs, &schedule_parallel(&txs)).max_batch_size
+        );
+    }
+
+    #[test]
+    fn test_reorder_never_moves_unknown_access() {
+        let txs = vec![
+            TxAccess::new(0).with_writes([account(1)]),
+            TxAccess::unknown(1),
+            TxAccess::new(2).with_writes([account(2)]),
+        ];
+        let constraints = ReorderConstraints::new().with_commutative([0, 1, 2]);
+
+        assert_eq!(
+            schedule_with_reorder(&txs, &constraints),
+            vec![vec![0], vec![1], vec![2]]
+        );
+    }
+
+    fn lane_gas(txs: &[TxAccess], lanes: &[Vec<TxId>]) -> Vec<u64> {
+        lanes
+            .iter()
+            .map(|lane| {
+                lane.iter()
+                    .map(|id| txs.iter().find(|tx| tx.id == *id).unwrap().gas)
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Smallest possible heaviest lane, found by trying every assignment
+    fn optimal_max_lane_gas(gas: &[u64], num_lanes: usize) -> u64 {
+        let mut best = u64::MAX;
+        let mut assignment = vec![0usize; gas.len()];
+        loop {
+            let mut lanes = vec![0u64; num_lanes];
+            for (tx, lane) in assignment.iter().enumerate() {
+                lanes[*lane] += gas[tx];
+            }
+            best = best.min(*lanes.iter().max().unwrap());
+
+            // Advance to the next assignment, counting in base `num_lanes`
+            let Some(position) = assignment.iter().position(|lane| *lane + 1 < num_lanes) else {
+                return best;
+            };
+            assignment[position] += 1;
+            assignment[..position].fill(0);
+        }
+    }
+
+    #[test]
+    fn test_balanced_schedule_is_close_to_optimal() {
+        let gas = [
+            70_000, 21_000, 45_000, 120_000, 33_000, 90_000, 21_000, 60_000,
+        ];
+        let txs: Vec<_> = gas
+            .iter()
+            .enumerate()
+            .map(|(i, gas)| {
+                TxAccess::new(i as u32)
+                    .with_writes([account(i as u8)])
+                    .with_gas(*gas)
+            })
+            .collect();
+
+        for num_lanes in 1..=4 {
+            let lanes = schedule_balanced(&txs, num_lanes);
+            assert_eq!(lanes.len(), num_lanes);
+            assert_eq!(lanes.iter().map(Vec::len).sum::<usize>(), txs.len());
+
+            let max_lane = *lane_gas(&txs, &lanes).iter().max().unwrap();
+            let optimal = optimal_max_lane_gas(&gas, num_lanes);
+            assert!(
+                max_lane * 3 <= optimal * 4,
+                "{} lanes: heaviest lane {} vs optimal {}",
+                num_lanes,
+                max_lane,
+                optimal
+            );
+        }
+    }
+
+    #[test]
+    fn test_balanced_schedule_keeps_conflicts_on_one_lane() {
+        let txs = vec![
+            TxAccess::new(0).with_reads([account(1)]).with_gas(10),
+            TxAccess::new(1).with_writes([account(2)]).with_gas(50),
+            TxAccess::new(2).with_writes([account(1)]).with_gas(10),
+            TxAccess::new(3)
+                .with_reads([account(2)])
+                .with_writes([account(3)])
+                .with_gas(10),
+            TxAccess::new(4).with_reads([account(3)]).with_gas(10),
+            TxAccess::new(5).with_reads([account(9)]).with_gas(20),
+            TxAccess::new(6).with_reads([account(9)]).with_gas(20),
+        ];
+
+        let lanes = schedule_balanced(&txs, 3);
+        assert_eq!(lanes, vec![vec![1, 3, 4], vec![0, 2, 6], vec![5]]);
+        for lane in &lanes {
+            assert!(lane.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_balanced_schedule_with_zero_lanes() {
+        let txs = vec![TxAccess::new(0), TxAccess::new(1)];
+        assert_eq!(schedule_balanced(&txs, 0), vec![vec![0, 1]]);
+        assert_eq!(schedule_balanced(&[], 2), vec![Vec::<TxId>::new(), vec![]]);
+    }
+
+    #[test]
+    fn test_schedule_encoding() {
+        let schedule = ParallelSchedule::new(B256::repeat_byte(0xab), vec![vec![0, 2], vec![1]]);
+
+        let mut expected = vec![0xab; 32];
+        expected.extend_from_slice(&[0, 0, 0, 2]);
+        expected.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 2]);
+        expected.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 1]);
+        expected.extend_from_slice(&2u64.to_be_bytes());
+        assert_eq!(schedule.encode(), expected);
+
+        // The hash only depends on the canonical form
+        let reordered =
+            ParallelSchedule::new(B256::repeat_byte(0xab), vec![vec![1], vec![], vec![2, 0]]);
+        assert_ne!(reordered.encode(), schedule.encode());
+        assert_eq!(reordered.hash(), schedule.hash());
+        let regrouped = ParallelSchedule::new(B256::repeat_byte(0xab), vec![vec![0], vec![1, 2]]);
+        assert_ne!(regrouped.hash(), schedule.hash());
+
+        // Operators disagreeing on the gas of a transaction disagree on the critical path
+        let weighed = schedule.clone().with_gas([(1, 21_000)]);
+        assert_ne!(weighed.hash(), schedule.hash());
+
+        // A bound block data hash follows the critical path
+        let bound = schedule
+            .clone()
+            .with_block_data_hash(B256::repeat_byte(0xcd));
+        expected.extend_from_slice(&[0xcd; 32]);
+        assert_eq!(bound.encode(), expected);
+        assert_ne!(bound.hash(), schedule.hash());
+        assert_ne!(
+            bound.hash(),
+            schedule
+                .clone()
+                .with_block_data_hash(B256::repeat_byte(0xce))
+                .hash()
+        );
+    }
+
+    #[test]
+    fn test_critical_path_of_a_chain_is_its_total_gas() {
+        let txs: Vec<_> = (0..4)
+            .map(|i| {
+                TxAccess::new(i)
+                    .with_writes([account(1)])
+                    .with_gas(1_000 * (i as u64 + 1))
+            })
+            .collect();
+
+        let schedule = ParallelSchedule::from_txs(B256::ZERO, &txs);
+        assert_eq!(schedule.batches.len(), 4);
+        assert_eq!(schedule.critical_path(), 10_000);
+        assert_eq!(critical_path_gas(&txs), 10_000);
+    }
+
+    #[test]
+    fn test_critical_path_follows_dependencies_across_batches() {
+        // 0 -> 2 and 1 -> 3 are the only dependencies, the heavy transactions 0 and 3 never
+        // wait on each other
+        let txs = vec![
+            TxAccess::new(0).with_writes([account(1)]).with_gas(100),
+            TxAccess::new(1).with_writes([account(2)]).with_gas(1),
+            TxAccess::new(2).with_reads([account(1)]).with_gas(1),
+            TxAccess::new(3).with_reads([account(2)]).with_gas(100),
+        ];
+
+        let schedule = ParallelSchedule::from_txs(B256::ZERO, &txs);
+        assert_eq!(schedule.batches, vec![vec![0, 1], vec![2, 3]]);
+        assert_eq!(schedule.critical_path(), 101);
+        assert_eq!(critical_path_gas(&txs), 101);
+
+        // Without the dependencies every batch waits for the heaviest of the one before
+        let unknown_deps = ParallelSchedule::new(B256::ZERO, schedule.batches.clone())
+            .with_gas(txs.iter().map(|tx| (tx.id, tx.gas)));
+        assert_eq!(unknown_deps.critical_path(), 200);
+        assert_ne!(unknown_deps.hash(), schedule.hash());
+
+        // The critical path survives a round trip through JSON
+        let parsed = ParallelSchedule::from_json(&schedule.to_json().unwrap()).unwrap();
+        assert_eq!(parsed.critical_path(), 101);
+        assert_eq!(parsed.hash(), schedule.hash());
+
+        // A transaction with unknown access waits for everything before it
+        let mut barrier = txs.clone();
+        barrier.push(TxAccess::unknown(4).with_gas(10));
+        barrier.push(TxAccess::new(5).with_writes([account(3)]).with_gas(5));
+        assert_eq!(critical_path_gas(&barrier), 116);
+    }
+
+    #[test]
+    fn test_critical_path_of_independent_transactions_is_the_heaviest() {
+        let txs: Vec<_> = (0..4)
+            .map(|i| {
+                TxAccess::new(i)
+                    .with_writes([account(i as u8)])
+                    .with_gas(1_000 * (i as u64 + 1))
+            })
+            .collect();
+
+        let schedule = ParallelSchedule::from_txs(B256::ZERO, &txs);
+        assert_eq!(schedule.batches, vec![vec![0, 1, 2, 3]]);
+        assert_eq!(schedule.critical_path(), 4_000);
+
+        // Without estimates every transaction weighs one gas
+        let unweighed = ParallelSchedule::new(B256::ZERO, schedule.batches.clone());
+        assert_eq!(unweighed.critical_path(), 1);
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let mut schedule =
+            ParallelSchedule::new(B256::ZERO, vec![vec![5, 3], vec![], vec![4, 1], vec![2, 0]]);
+        schedule.canonicalize();
+        assert_eq!(schedule.batches, vec![vec![0, 2], vec![1, 4], vec![3, 5]]);
+    }
+
+    #[test]
+    fn test_shuffled_input_gives_identical_canonical_schedule() {
+        let txs = vec![
+            TxAccess::new(0).with_writes([slot(1, 1)]).with_gas(30),
+            TxAccess::new(1).with_writes([slot(2, 1)]).with_gas(20),
+            TxAccess::new(2)
+                .with_reads([slot(1, 1)])
+                .with_writes([slot(3, 1)])
+                .with_gas(10),
+            TxAccess::new(3).with_writes([slot(2, 1)]).with_gas(40),
+            TxAccess::new(4).with_reads([slot(3, 1)]).with_gas(10),
+            TxAccess::new(5).with_writes([account(9)]).with_gas(50),
+        ];
+        let mut shuffled = txs.clone();
+        shuffled.reverse();
+        shuffled.swap(0, 3);
+
+        let schedule = ParallelSchedule::new(B256::ZERO, schedule_parallel(&txs));
+        let from_shuffled = ParallelSchedule::new(B256::ZERO, schedule_parallel(&shuffled));
+        assert_eq!(schedule.batches, vec![vec![0, 1, 5], vec![2, 3], vec![4]]);
+        assert_eq!(from_shuffled.batches, schedule.batches);
+        assert_eq!(from_shuffled.hash(), schedule.hash());
+
+        assert_eq!(schedule_balanced(&shuffled, 2), schedule_balanced(&txs, 2));
+    }
+
+    /// Adds `txs` one by one, checking the schedule against a full rebuild after every step
+    fn assert_incremental_matches_rebuild(txs: &[TxAccess]) {
+        let mut schedule = ParallelSchedule::new(B256::ZERO, vec![]);
+        for (i, tx) in txs.iter().enumerate() {
+            let batch = schedule.add_tx(tx).unwrap();
+            assert!(schedule.batches[batch].contains(&tx.id));
+
+            let mut rebuilt = ParallelSchedule::from_txs(B256::ZERO, &txs[..=i]);
+            rebuilt.canonicalize();
+            assert_eq!(schedule, rebuilt, "after transaction {}", tx.id);
+            assert_eq!(schedule.hash(), rebuilt.hash());
+        }
+    }
+
+    #[test]
+    fn test_incremental_schedule_matches_rebuild() {
+        // Independent transactions
+        let independent: Vec<_> = (0..4)
+            .map(|i| TxAccess::new(i).with_writes([account(i as u8)]))
+            .collect();
+        assert_incremental_matches_rebuild(&independent);
+
+        // A chain, where every transaction opens a new batch
+        let chain: Vec<_> = (0..4)
+            .map(|i| {
+                TxAccess::new(i)
+                    .with_writes([account(1)])
+                    .with_gas(1_000 * (i as u64 + 1))
+            })
+            .collect();
+        assert_incremental_matches_rebuild(&chain);
+
+        // Later transactions filling gaps in earlier batches
+        let txs = vec![
+            TxAccess::new(0).with_writes([slot(1, 1)]).with_gas(30),
+            TxAccess::new(1)
+                .with_reads([slot(1, 1)])
+                .with_writes([slot(2, 1)]),
+            TxAccess::new(2).with_reads([slot(2, 1)]).with_gas(10),
+            TxAccess::new(3).with_writes([slot(3, 1)]),
+            TxAccess::new(4).with_reads([slot(1, 1)]),
+            TxAccess::new(5).with_writes([slot(3, 1)]).with_gas(40),
+        ];
+        assert_incremental_matches_rebuild(&txs);
+
+        // Unknown access in the middle of the block acts as a barrier
+        let barrier = vec![
+            TxAccess::new(0).with_writes([account(1)]),
+            TxAccess::new(1).with_writes([account(2)]),
+            TxAccess::unknown(2),
+            TxAccess::new(3).with_writes([account(3)]),
+            TxAccess::new(4).with_reads([account(1)]),
+        ];
+        assert_incremental_matches_rebuild(&barrier);
+
+        // Gaps in the ids, as when transactions without access lists are left out
+        let sparse = vec![
+            TxAccess::new(2).with_writes([account(1)]),
+            TxAccess::new(5).with_reads([account(1)]),
+            TxAccess::new(9).with_writes([account(2)]),
+        ];
+        assert_incremental_matches_rebuild(&sparse);
+    }
+
+    #[test]
+    fn test_add_tx_rejects_transactions_out_of_order() {
+        let mut schedule = ParallelSchedule::new(B256::ZERO, vec![]);
+        assert_eq!(schedule.add_tx(&TxAccess::new(1)), Some(0));
+        let before = schedule.clone();
+        assert_eq!(schedule.add_tx(&TxAccess::new(1)), None);
+        assert_eq!(schedule.add_tx(&TxAccess::new(0)), None);
+        assert_eq!(schedule, before);
+        assert_eq!(schedule.add_tx(&TxAccess::new(2)), Some(0));
+
+        // Batches not built by add_tx give nothing to place the transaction against
+        let mut parsed = ParallelSchedule::from_json(&schedule.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, schedule);
+        assert_eq!(parsed.add_tx(&TxAccess::new(3)), None);
+        let mut built = ParallelSchedule::new(B256::ZERO, vec![vec![0]]);
+        assert_eq!(built.add_tx(&TxAccess::new(1)), None);
+    }
+
+    #[test]
+    fn test_schedule_json_round_trip() {
+        let schedule = ParallelSchedule::new(B256::repeat_byte(0xab), vec![vec![0, 2], vec![1]]);
+
+        let json = schedule.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value["block_hash"],
+            format!("0x{}", "ab".repeat(32)).as_str()
+        );
+        assert_eq!(value["batches"], serde_json::json!([[0, 2], [1]]));
+        assert!(value.get("gas").is_none());
+
+        let parsed = ParallelSchedule::from_json(&json).unwrap();
+        assert_eq!(parsed, schedule);
+        assert_eq!(parsed.hash(), schedule.hash());
+        assert!(ParallelSchedule::from_json("{\"batches\": []}").is_err());
+
+        let weighed = schedule.with_gas([(0, 21_000), (2, 50_000)]);
+        let parsed = ParallelSchedule::from_json(&weighed.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, weighed);
+        assert_eq!(parsed.critical_path(), weighed.critical_path());
+
+        let bound = weighed.with_block_data_hash(B256::repeat_byte(0xcd));
+        let parsed = ParallelSchedule::from_json(&bound.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, bound);
+        assert_eq!(parsed.hash(), bound.hash());
+    }
+
+    #[test]
+    fn test_schedule_json_is_deterministic() {
+        let txs = vec![
+            TxAccess::new(0).with_writes([slot(1, 1)]),
+            TxAccess::new(1).with_reads([slot(1, 1)]),
+            TxAccess::new(2).with_writes([slot(2, 1)]),
+        ];
+        let mut shuffled = txs.clone();
+        shuffled.reverse();
+
+        let mut schedule = ParallelSchedule::new(B256::ZERO, schedule_parallel(&txs));
+        let mut from_shuffled = ParallelSchedule::new(B256::ZERO, schedule_parallel(&shuffled));
+        schedule.canonicalize();
+        from_shuffled.canonicalize();
+
+        let json = schedule.to_json().unwrap();
+        assert_eq!(schedule.to_json().unwrap(), json);
+        assert_eq!(from_shuffled.to_json().unwrap(), json);
+    }
+}
@@ -0,0 +1,1138 @@
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy_primitives::{keccak256, Address, B256, U256};
+use alloy_signer::{Signature as EcdsaSignature, SignerSync};
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{SolType, SolValue};
+use ark_bn254::{Bn254, G1Affine, G2Affine};
+use ark_ec::pairing::Pairing;
+use ark_ec::AffineRepr;
+use async_trait::async_trait;
+use blueprint_sdk::crypto::k256::K256Ecdsa;
+use blueprint_sdk::keystore::backends::Backend;
+use blueprint_sdk::keystore::Keystore;
+use eigensdk::crypto_bls::{
+    verify_message, BlsG1Point, BlsG2Point, BlsKeyPair, OperatorId, Signature,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::bls_keys::KeystoreError;
+use crate::contexts::client::SignedTaskResponse;
+use crate::jobs::compute_x_square::TaskStatus;
+use crate::IIncredibleSquaringTaskManager::TaskResponse;
+
+#[derive(Debug, Error)]
+pub enum SignerError {
+    #[error("Unknown signature scheme: {0}")]
+    UnknownScheme(String),
+    #[error(transparent)]
+    Bls(#[from] KeystoreError),
+    #[error("No local ECDSA key found: {0}")]
+    NoEcdsaKey(String),
+    #[error("Failed to sign with ECDSA key: {0}")]
+    EcdsaSigning(String),
+    #[error("Unknown signer backend: {0}")]
+    UnknownBackend(String),
+    #[error("Invalid ECDSA key: {0}")]
+    InvalidEcdsaKey(String),
+    #[error("Remote signer failed: {0}")]
+    Remote(String),
+}
+
+impl SignerError {
+    /// Returns the task status reported when a task fails with this error
+    pub fn status(&self) -> TaskStatus {
+        match self {
+            SignerError::Bls(e) => e.status(),
+            SignerError::NoEcdsaKey(_) | SignerError::InvalidEcdsaKey(_) => TaskStatus::NoKey,
+            SignerError::UnknownScheme(_)
+            | SignerError::UnknownBackend(_)
+            | SignerError::EcdsaSigning(_)
+            | SignerError::Remote(_) => TaskStatus::SigningFailed,
+        }
+    }
+}
+
+/// Signature scheme operators attest to task responses with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureScheme {
+    /// BN254 BLS signatures, aggregated by the BLS aggregator
+    #[default]
+    Bls,
+    /// secp256k1 ECDSA signatures, for AVSs with ECDSA quorums
+    Ecdsa,
+}
+
+impl FromStr for SignatureScheme {
+    type Err = SignerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "bls" => Ok(SignatureScheme::Bls),
+            "ecdsa" => Ok(SignatureScheme::Ecdsa),
+            _ => Err(SignerError::UnknownScheme(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for SignatureScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureScheme::Bls => f.write_str("bls"),
+            SignatureScheme::Ecdsa => f.write_str("ecdsa"),
+        }
+    }
+}
+
+/// Where the operator's key lives and task responses are signed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignerBackend {
+    /// The key of the signature scheme in the blueprint's keystore
+    #[default]
+    Keystore,
+    /// A key handed to the operator through the environment
+    Env,
+    /// A signing service holding the key, see [`RemoteTaskSigner`]
+    Remote,
+}
+
+impl FromStr for SignerBackend {
+    type Err = SignerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "keystore" | "file" => Ok(SignerBackend::Keystore),
+            "env" => Ok(SignerBackend::Env),
+            "remote" => Ok(SignerBackend::Remote),
+            _ => Err(SignerError::UnknownBackend(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for SignerBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerBackend::Keystore => f.write_str("keystore"),
+            SignerBackend::Env => f.write_str("env"),
+            SignerBackend::Remote => f.write_str("remote"),
+        }
+    }
+}
+
+/// Type string of the domain task responses are signed in, see
+/// `IncredibleSquaringTaskManager.TASK_RESPONSE_DOMAIN_TYPEHASH`
+pub const TASK_RESPONSE_DOMAIN_TYPE: &str =
+    "ParallelExecTaskResponse(uint256 chainId,address taskManager)";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Unknown task response encoding {0}, expected `standard` or `packed`")]
+pub struct UnknownEncoding(pub String);
+
+/// How a task response is encoded before it is hashed and signed.
+///
+/// Must match what the task manager hashes in `taskResponseSigningHash`, otherwise every
+/// signature fails to verify on-chain. The bundled task manager uses the standard encoding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TaskResponseEncoding {
+    /// `abi.encode(taskResponse)`, every field padded to 32 bytes
+    #[default]
+    Standard,
+    /// `abi.encodePacked(taskResponse)`, every field at its own width
+    Packed,
+}
+
+impl FromStr for TaskResponseEncoding {
+    type Err = UnknownEncoding;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "standard" => Ok(TaskResponseEncoding::Standard),
+            "packed" => Ok(TaskResponseEncoding::Packed),
+            _ => Err(UnknownEncoding(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for TaskResponseEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskResponseEncoding::Standard => f.write_str("standard"),
+            TaskResponseEncoding::Packed => f.write_str("packed"),
+        }
+    }
+}
+
+/// Returns the bytes a task response is hashed from with `encoding`
+pub fn encode_task_response(
+    task_response: &TaskResponse,
+    encoding: TaskResponseEncoding,
+) -> Vec<u8> {
+    match encoding {
+        TaskResponseEncoding::Standard => <TaskResponse as SolType>::abi_encode(task_response),
+        TaskResponseEncoding::Packed => <TaskResponse as SolType>::abi_encode_packed(task_response),
+    }
+}
+
+/// Returns the ABI encoded hash of a task response, before it is bound to a [`SigningDomain`]
+pub fn task_response_digest(task_response: &TaskResponse) -> B256 {
+    keccak256(encode_task_response(
+        task_response,
+        TaskResponseEncoding::Standard,
+    ))
+}
+
+/// The task manager deployment signed task responses are valid for.
+///
+/// Operators sign [`SigningDomain::signing_hash`] rather than the bare
+/// [`task_response_digest`], so a signature for one task manager cannot be replayed against
+/// another deployment, on the same or another chain, that accepts the same task responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SigningDomain {
+    pub chain_id: u64,
+    pub task_manager: Address,
+    /// Encoding task responses are hashed with
+    pub encoding: TaskResponseEncoding,
+}
+
+impl SigningDomain {
+    pub fn new(chain_id: u64, task_manager: Address) -> Self {
+        Self {
+            chain_id,
+            task_manager,
+            encoding: TaskResponseEncoding::default(),
+        }
+    }
+
+    pub fn with_encoding(mut self, encoding: TaskResponseEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Returns the domain separator, matching `IncredibleSquaringTaskManager.domainSeparator`
+    pub fn separator(&self) -> B256 {
+        let type_hash = keccak256(TASK_RESPONSE_DOMAIN_TYPE);
+        keccak256((type_hash, U256::from(self.chain_id), self.task_manager).abi_encode())
+    }
+
+    /// Returns the hash operators sign for `task_response` in this domain
+    pub fn signing_hash(&self, task_response: &TaskResponse) -> B256 {
+        let mut message = Vec::with_capacity(66);
+        message.extend_from_slice(b"\x19\x01");
+        message.extend_from_slice(self.separator().as_slice());
+        let digest = keccak256(encode_task_response(task_response, self.encoding));
+        message.extend_from_slice(digest.as_slice());
+        keccak256(message)
+    }
+}
+
+/// Returns the hash operators sign for `task_response`, bound to the task manager deployed at
+/// `task_manager` on `chain_id`
+pub fn task_response_signing_hash(
+    task_response: &TaskResponse,
+    chain_id: u64,
+    task_manager: Address,
+) -> B256 {
+    SigningDomain::new(chain_id, task_manager).signing_hash(task_response)
+}
+
+/// A task response signed by an ECDSA operator, identified by its address
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EcdsaSignedTaskResponse {
+    pub task_response: TaskResponse,
+    pub signature: EcdsaSignature,
+    pub operator: Address,
+    /// Quorum the response is sent for, see [`SignedTaskResponse::quorum_number`]
+    #[serde(default)]
+    pub quorum_number: Option<u8>,
+}
+
+impl EcdsaSignedTaskResponse {
+    /// Recovers the address that signed the task response in `domain`
+    pub fn recover_operator(&self, domain: &SigningDomain) -> Option<Address> {
+        self.signature
+            .recover_address_from_prehash(&domain.signing_hash(&self.task_response))
+            .ok()
+    }
+}
+
+/// A signed task response of either scheme, ready to be sent to the aggregator
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SignedTaskPayload {
+    Bls(SignedTaskResponse),
+    Ecdsa(EcdsaSignedTaskResponse),
+}
+
+impl SignedTaskPayload {
+    pub fn scheme(&self) -> SignatureScheme {
+        match self {
+            SignedTaskPayload::Bls(_) => SignatureScheme::Bls,
+            SignedTaskPayload::Ecdsa(_) => SignatureScheme::Ecdsa,
+        }
+    }
+
+    /// Returns the id of the signing operator, which is its address for ECDSA operators
+    pub fn operator(&self) -> String {
+        match self {
+            SignedTaskPayload::Bls(response) => response.operator_id.to_string(),
+            SignedTaskPayload::Ecdsa(response) => response.operator.to_string(),
+        }
+    }
+
+    pub fn task_response(&self) -> &TaskResponse {
+        match self {
+            SignedTaskPayload::Bls(response) => &response.task_response,
+            SignedTaskPayload::Ecdsa(response) => &response.task_response,
+        }
+    }
+
+    pub fn quorum_number(&self) -> Option<u8> {
+        match self {
+            SignedTaskPayload::Bls(response) => response.quorum_number,
+            SignedTaskPayload::Ecdsa(response) => response.quorum_number,
+        }
+    }
+
+    /// Addresses the payload to `quorum_number`.
+    ///
+    /// The quorum is not part of the signed digest, the same signature counts towards every
+    /// quorum the operator belongs to.
+    pub fn with_quorum_number(mut self, quorum_number: u8) -> Self {
+        match &mut self {
+            SignedTaskPayload::Bls(response) => response.quorum_number = Some(quorum_number),
+            SignedTaskPayload::Ecdsa(response) => response.quorum_number = Some(quorum_number),
+        }
+        self
+    }
+}
+
+/// Summarises the payload on one line for logs, e.g.
+/// `task_index=7 quorum=0 scheme=bls operator_id=0x01.. result_hash=0xab..`
+impl fmt::Display for SignedTaskPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let task_response = self.task_response();
+        write!(f, "task_index={}", task_response.referenceTaskIndex)?;
+        if let Some(quorum_number) = self.quorum_number() {
+            write!(f, " quorum={}", quorum_number)?;
+        }
+        write!(
+            f,
+            " scheme={} operator_id={} result_hash={}",
+            self.scheme(),
+            self.operator(),
+            task_response.resultHash
+        )
+    }
+}
+
+/// Signs the digest of task responses on behalf of the operator
+#[async_trait]
+pub trait TaskSigner: Send + Sync {
+    fn scheme(&self) -> SignatureScheme;
+
+    /// Returns the BLS operator id responses are signed as, `None` for ECDSA signers
+    fn bls_operator_id(&self) -> Option<OperatorId>;
+
+    /// Signs [`SigningDomain::signing_hash`] of `task_response`
+    async fn sign_task_response(
+        &self,
+        task_response: TaskResponse,
+        domain: &SigningDomain,
+    ) -> Result<SignedTaskPayload, SignerError>;
+
+    /// Checks that `payload` carries a valid signature of this signer's key over
+    /// [`SigningDomain::signing_hash`] of its task response
+    fn verify(&self, payload: &SignedTaskPayload, domain: &SigningDomain) -> bool;
+}
+
+/// A signer loaded once and shared between tasks
+#[async_trait]
+impl<S: TaskSigner + ?Sized> TaskSigner for Arc<S> {
+    fn scheme(&self) -> SignatureScheme {
+        (**self).scheme()
+    }
+
+    fn bls_operator_id(&self) -> Option<OperatorId> {
+        (**self).bls_operator_id()
+    }
+
+    async fn sign_task_response(
+        &self,
+        task_response: TaskResponse,
+        domain: &SigningDomain,
+    ) -> Result<SignedTaskPayload, SignerError> {
+        (**self).sign_task_response(task_response, domain).await
+    }
+
+    fn verify(&self, payload: &SignedTaskPayload, domain: &SigningDomain) -> bool {
+        (**self).verify(payload, domain)
+    }
+}
+
+/// Signs task responses with the operator's BLS key
+#[derive(Debug, Clone)]
+pub struct BlsTaskSigner {
+    key_pair: BlsKeyPair,
+    operator_id: OperatorId,
+}
+
+impl BlsTaskSigner {
+    pub fn new(key_pair: BlsKeyPair, operator_id: OperatorId) -> Self {
+        Self {
+            key_pair,
+            operator_id,
+        }
+    }
+
+    /// Returns the operator id responses are signed as
+    pub fn operator_id(&self) -> OperatorId {
+        self.operator_id
+    }
+}
+
+#[async_trait]
+impl TaskSigner for BlsTaskSigner {
+    fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::Bls
+    }
+
+    fn bls_operator_id(&self) -> Option<OperatorId> {
+        Some(self.operator_id)
+    }
+
+    async fn sign_task_response(
+        &self,
+        task_response: TaskResponse,
+        domain: &SigningDomain,
+    ) -> Result<SignedTaskPayload, SignerError> {
+        let digest = domain.signing_hash(&task_response);
+        Ok(SignedTaskPayload::Bls(SignedTaskResponse {
+            task_response,
+            signature: self.key_pair.sign_message(digest.as_ref()),
+            operator_id: self.operator_id,
+            quorum_number: None,
+        }))
+    }
+
+    fn verify(&self, payload: &SignedTaskPayload, domain: &SigningDomain) -> bool {
+        verify_bls(
+            payload,
+            domain,
+            self.operator_id,
+            &self.key_pair.public_key_g2(),
+        )
+    }
+}
+
+/// Signs task responses with the operator's ECDSA key
+#[derive(Debug, Clone)]
+pub struct EcdsaTaskSigner {
+    signer: PrivateKeySigner,
+}
+
+impl EcdsaTaskSigner {
+    pub fn new(signer: PrivateKeySigner) -> Self {
+        Self { signer }
+    }
+
+    /// Returns the operator address task responses are signed with
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+}
+
+#[async_trait]
+impl TaskSigner for EcdsaTaskSigner {
+    fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::Ecdsa
+    }
+
+    fn bls_operator_id(&self) -> Option<OperatorId> {
+        None
+    }
+
+    async fn sign_task_response(
+        &self,
+        task_response: TaskResponse,
+        domain: &SigningDomain,
+    ) -> Result<SignedTaskPayload, SignerError> {
+        let digest = domain.signing_hash(&task_response);
+        let signature = self
+            .signer
+            .sign_hash_sync(&digest)
+            .map_err(|e| SignerError::EcdsaSigning(e.to_string()))?;
+        Ok(SignedTaskPayload::Ecdsa(EcdsaSignedTaskResponse {
+            task_response,
+            signature,
+            operator: self.signer.address(),
+            quorum_number: None,
+        }))
+    }
+
+    fn verify(&self, payload: &SignedTaskPayload, domain: &SigningDomain) -> bool {
+        verify_ecdsa(payload, domain, self.address())
+    }
+}
+
+/// Checks that `payload` is a BLS signature of `operator_id` with `public_key` in `domain`
+fn verify_bls(
+    payload: &SignedTaskPayload,
+    domain: &SigningDomain,
+    operator_id: OperatorId,
+    public_key: &BlsG2Point,
+) -> bool {
+    let SignedTaskPayload::Bls(signed) = payload else {
+        return false;
+    };
+    let digest = domain.signing_hash(&signed.task_response);
+    signed.operator_id == operator_id
+        && verify_message(public_key.g2(), &digest.0, signed.signature.g1_point().g1())
+}
+
+/// Derives the operator id the registry coordinator assigns to the BLS `public_key`
+pub fn operator_id_from_public_key(public_key: &BlsG1Point) -> OperatorId {
+    let point = public_key.g1();
+    let x: num_bigint::BigUint = point.x.into();
+    let y: num_bigint::BigUint = point.y.into();
+    keccak256([x.to_bytes_be(), y.to_bytes_be()].concat())
+}
+
+/// Checks that `g1` and `g2` are the same BLS key, i.e. that `e(g1, G2) == e(G1, g2)`
+fn bls_keys_match(g1: &BlsG1Point, g2: &BlsG2Point) -> bool {
+    Bn254::pairing(g1.g1(), G2Affine::generator()) == Bn254::pairing(G1Affine::generator(), g2.g2())
+}
+
+/// Checks that `payload` is an ECDSA signature of `address` in `domain`
+fn verify_ecdsa(payload: &SignedTaskPayload, domain: &SigningDomain, address: Address) -> bool {
+    let SignedTaskPayload::Ecdsa(signed) = payload else {
+        return false;
+    };
+    signed.operator == address && signed.recover_operator(domain) == Some(address)
+}
+
+/// Loads an ECDSA signer from a hex encoded private key, as passed through the environment
+pub fn ecdsa_signer_from_key(key: &str) -> Result<EcdsaTaskSigner, SignerError> {
+    key.trim()
+        .parse::<PrivateKeySigner>()
+        .map(EcdsaTaskSigner::new)
+        .map_err(|e| SignerError::InvalidEcdsaKey(e.to_string()))
+}
+
+/// Exchanges JSON with a remote signing service, so tests can stand in for the network
+#[async_trait]
+pub trait SigningTransport: Debug + Send + Sync {
+    /// Sends `body` to `path` of the signing service and returns its JSON reply
+    async fn post(&self, path: &str, body: Value) -> Result<Value, SignerError>;
+}
+
+/// [`SigningTransport`] POSTing to a signing service over HTTP
+#[derive(Debug, Clone)]
+pub struct HttpSigningTransport {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl HttpSigningTransport {
+    pub fn new(base_url: impl Into<String>, timeout: Duration) -> Result<Self, SignerError> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| SignerError::Remote(e.to_string()))?;
+        Ok(Self {
+            client,
+            base_url: base_url.into(),
+        })
+    }
+}
+
+#[async_trait]
+impl SigningTransport for HttpSigningTransport {
+    async fn post(&self, path: &str, body: Value) -> Result<Value, SignerError> {
+        let url = format!("{}{}", self.base_url.trim_end_matches('/'), path);
+        let remote = |e: reqwest::Error| SignerError::Remote(format!("{}: {}", url, e));
+        let response = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(remote)?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(SignerError::Remote(format!(
+                "{} answered HTTP {}: {}",
+                url, status, body
+            )));
+        }
+        response.json().await.map_err(remote)
+    }
+}
+
+/// The key a remote signing service signs with, as returned by its `/key` endpoint
+///
+/// A BLS key is announced on both curves: the operator id is derived from the G1 key, and
+/// signatures are verified with the G2 key, which must be the same key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "scheme", rename_all = "lowercase")]
+pub enum RemoteKey {
+    Bls {
+        public_key: BlsG1Point,
+        public_key_g2: BlsG2Point,
+    },
+    Ecdsa {
+        address: Address,
+    },
+}
+
+impl RemoteKey {
+    pub fn scheme(&self) -> SignatureScheme {
+        match self {
+            RemoteKey::Bls { .. } => SignatureScheme::Bls,
+            RemoteKey::Ecdsa { .. } => SignatureScheme::Ecdsa,
+        }
+    }
+}
+
+/// Body of a request to the `/key` and `/sign` endpoints of a remote signing service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteSignRequest {
+    pub scheme: SignatureScheme,
+    /// [`SigningDomain::signing_hash`] of the task response, absent when asking for the key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_hash: Option<B256>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RemoteSignature<S> {
+    signature: S,
+}
+
+/// Signs task responses through a signing service holding the operator's key, e.g. in front
+/// of an HSM, so the key never reaches the operator.
+///
+/// The service is sent `{"scheme": "bls"}` at `/key` and answers with the [`RemoteKey`] it
+/// signs with, which is fetched once by [`RemoteTaskSigner::connect`]. The operator id is
+/// derived from that key rather than taken from the service. Every task response
+/// is then signed by sending `{"scheme": "bls", "message_hash": "0x.."}` to `/sign`, which
+/// answers with `{"signature": ..}`. Signatures are checked against the key like local ones.
+#[derive(Debug, Clone)]
+pub struct RemoteTaskSigner {
+    transport: Arc<dyn SigningTransport>,
+    key: RemoteKey,
+}
+
+impl RemoteTaskSigner {
+    /// Asks the service behind `transport` for its `scheme` key, rejecting a BLS key whose G1
+    /// and G2 halves differ
+    pub async fn connect(
+        transport: Arc<dyn SigningTransport>,
+        scheme: SignatureScheme,
+    ) -> Result<Self, SignerError> {
+        let request = RemoteSignRequest {
+            scheme,
+            message_hash: None,
+        };
+        let key: RemoteKey = post_json(transport.as_ref(), "/key", &request).await?;
+        if key.scheme() != scheme {
+            return Err(SignerError::Remote(format!(
+                "asked for a {} key, got a {} key",
+                scheme,
+                key.scheme()
+            )));
+        }
+        if let RemoteKey::Bls {
+            public_key,
+            public_key_g2,
+        } = &key
+        {
+            if !bls_keys_match(public_key, public_key_g2) {
+                return Err(SignerError::Remote(
+                    "announced G2 key does not match the G1 key".to_string(),
+                ));
+            }
+        }
+        Ok(Self { transport, key })
+    }
+
+    pub fn key(&self) -> &RemoteKey {
+        &self.key
+    }
+}
+
+async fn post_json<T: DeserializeOwned>(
+    transport: &dyn SigningTransport,
+    path: &str,
+    request: &RemoteSignRequest,
+) -> Result<T, SignerError> {
+    let body = serde_json::to_value(request).map_err(|e| SignerError::Remote(e.to_string()))?;
+    let reply = transport.post(path, body).await?;
+    serde_json::from_value(reply)
+        .map_err(|e| SignerError::Remote(format!("unexpected reply from {}: {}", path, e)))
+}
+
+#[async_trait]
+impl TaskSigner for RemoteTaskSigner {
+    fn scheme(&self) -> SignatureScheme {
+        self.key.scheme()
+    }
+
+    fn bls_operator_id(&self) -> Option<OperatorId> {
+        match &self.key {
+            RemoteKey::Bls { public_key, .. } => Some(operator_id_from_public_key(public_key)),
+            RemoteKey::Ecdsa { .. } => None,
+        }
+    }
+
+    async fn sign_task_response(
+        &self,
+        task_response: TaskResponse,
+        domain: &SigningDomain,
+    ) -> Result<SignedTaskPayload, SignerError> {
+        let request = RemoteSignRequest {
+            scheme: self.scheme(),
+            message_hash: Some(domain.signing_hash(&task_response)),
+        };
+        let transport = self.transport.as_ref();
+        Ok(match &self.key {
+            RemoteKey::Bls { public_key, .. } => {
+                let reply: RemoteSignature<Signature> =
+                    post_json(transport, "/sign", &request).await?;
+                SignedTaskPayload::Bls(SignedTaskResponse {
+                    task_response,
+                    signature: reply.signature,
+                    operator_id: operator_id_from_public_key(public_key),
+                    quorum_number: None,
+                })
+            }
+            RemoteKey::Ecdsa { address } => {
+                let reply: RemoteSignature<EcdsaSignature> =
+                    post_json(transport, "/sign", &request).await?;
+                SignedTaskPayload::Ecdsa(EcdsaSignedTaskResponse {
+                    task_response,
+                    signature: reply.signature,
+                    operator: *address,
+                    quorum_number: None,
+                })
+            }
+        })
+    }
+
+    fn verify(&self, payload: &SignedTaskPayload, domain: &SigningDomain) -> bool {
+        match &self.key {
+            RemoteKey::Bls {
+                public_key,
+                public_key_g2,
+            } => verify_bls(
+                payload,
+                domain,
+                operator_id_from_public_key(public_key),
+                public_key_g2,
+            ),
+            RemoteKey::Ecdsa { address } => verify_ecdsa(payload, domain, *address),
+        }
+    }
+}
+
+/// Loads the operator's ECDSA key from the keystore
+pub fn load_ecdsa_signer(keystore: &Keystore) -> Result<EcdsaTaskSigner, SignerError> {
+    let public = keystore
+        .first_local::<K256Ecdsa>()
+        .map_err(|e| SignerError::NoEcdsaKey(e.to_string()))?;
+    let secret = keystore
+        .get_secret::<K256Ecdsa>(&public)
+        .map_err(|e| SignerError::NoEcdsaKey(e.to_string()))?;
+    Ok(EcdsaTaskSigner::new(PrivateKeySigner::from_signing_key(
+        secret.0,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn domain() -> SigningDomain {
+        SigningDomain::new(31337, Address::repeat_byte(0x42))
+    }
+
+    fn task_response() -> TaskResponse {
+        TaskResponse {
+            referenceTaskIndex: 7,
+            resultHash: B256::repeat_byte(0xab),
+        }
+    }
+
+    #[test]
+    fn test_signing_hash_depends_on_domain() {
+        let task_response = TaskResponse {
+            referenceTaskIndex: 7,
+            resultHash: B256::repeat_byte(0xab),
+        };
+        let task_manager = Address::repeat_byte(0x42);
+        let signing_hash = task_response_signing_hash(&task_response, 31337, task_manager);
+
+        // Identical responses hash differently on another chain or for another task manager
+        assert_ne!(
+            task_response_signing_hash(&task_response, 1, task_manager),
+            signing_hash
+        );
+        assert_ne!(
+            task_response_signing_hash(&task_response, 31337, Address::repeat_byte(0x43)),
+            signing_hash
+        );
+        assert_ne!(task_response_digest(&task_response), signing_hash);
+
+        // The hash is `keccak256("\x19\x01" || domainSeparator || keccak256(abi.encode(response)))`
+        let domain = SigningDomain::new(31337, task_manager);
+        let separator = keccak256(
+            [
+                keccak256(TASK_RESPONSE_DOMAIN_TYPE).as_slice(),
+                U256::from(31337).to_be_bytes::<32>().as_slice(),
+                B256::left_padding_from(task_manager.as_slice()).as_slice(),
+            ]
+            .concat(),
+        );
+        assert_eq!(domain.separator(), separator);
+        let digest = task_response_digest(&task_response);
+        assert_eq!(
+            signing_hash,
+            keccak256([&[0x19u8, 0x01][..], separator.as_slice(), digest.as_slice()].concat())
+        );
+    }
+
+    #[test]
+    fn test_packed_and_standard_encodings_differ() {
+        let task_response = TaskResponse {
+            referenceTaskIndex: 7,
+            resultHash: B256::repeat_byte(0xab),
+        };
+        let standard = encode_task_response(&task_response, TaskResponseEncoding::Standard);
+        let packed = encode_task_response(&task_response, TaskResponseEncoding::Packed);
+
+        // `uint32` is padded to a full word only in the standard encoding
+        assert_eq!(standard.len(), 64);
+        assert_eq!(packed.len(), 36);
+        assert_eq!(&packed[..4], &7u32.to_be_bytes());
+        assert_eq!(&packed[4..], task_response.resultHash.as_slice());
+        assert_ne!(standard, packed);
+
+        // The default is the standard encoding the task manager verifies against
+        let domain = SigningDomain::new(31337, Address::repeat_byte(0x42));
+        assert_eq!(domain.encoding, TaskResponseEncoding::Standard);
+        assert_eq!(keccak256(&standard), task_response_digest(&task_response));
+        assert_ne!(
+            domain
+                .with_encoding(TaskResponseEncoding::Packed)
+                .signing_hash(&task_response),
+            domain.signing_hash(&task_response)
+        );
+    }
+
+    #[test]
+    fn test_parse_task_response_encoding() {
+        assert_eq!("standard".parse(), Ok(TaskResponseEncoding::Standard));
+        assert_eq!(" Packed ".parse(), Ok(TaskResponseEncoding::Packed));
+        assert_eq!(
+            "rlp".parse::<TaskResponseEncoding>(),
+            Err(UnknownEncoding("rlp".to_string()))
+        );
+        assert_eq!(TaskResponseEncoding::Packed.to_string(), "packed");
+    }
+
+    #[test]
+    fn test_signature_scheme_from_str() {
+        assert_eq!(
+            "bls".parse::<SignatureScheme>().unwrap(),
+            SignatureScheme::Bls
+        );
+        assert_eq!(
+            " ECDSA ".parse::<SignatureScheme>().unwrap(),
+            SignatureScheme::Ecdsa
+        );
+        assert!(matches!(
+            "schnorr".parse::<SignatureScheme>(),
+            Err(SignerError::UnknownScheme(scheme)) if scheme == "schnorr"
+        ));
+        assert_eq!(SignatureScheme::default(), SignatureScheme::Bls);
+    }
+
+    #[tokio::test]
+    async fn test_bls_signer_signs_task_response_digest() {
+        let key_pair = BlsKeyPair::new("12345".to_string()).unwrap();
+        let signer = BlsTaskSigner::new(key_pair.clone(), OperatorId::repeat_byte(1));
+
+        let payload = signer
+            .sign_task_response(task_response(), &domain())
+            .await
+            .unwrap();
+        assert_eq!(payload.scheme(), SignatureScheme::Bls);
+        let SignedTaskPayload::Bls(signed) = payload else {
+            panic!("expected a BLS payload");
+        };
+        assert_eq!(signed.operator_id, OperatorId::repeat_byte(1));
+
+        let digest = domain().signing_hash(&task_response());
+        let public_key = key_pair.public_key_g2().g2();
+        assert!(verify_message(
+            public_key,
+            &digest.0,
+            signed.signature.g1_point().g1()
+        ));
+        // The bare digest is never signed, so the signature is useless outside its domain
+        assert!(!verify_message(
+            public_key,
+            &task_response_digest(&task_response()).0,
+            signed.signature.g1_point().g1()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_payload_display_is_one_line() {
+        let key_pair = BlsKeyPair::new("12345".to_string()).unwrap();
+        let signer = BlsTaskSigner::new(key_pair, OperatorId::repeat_byte(1));
+        let payload = signer
+            .sign_task_response(task_response(), &domain())
+            .await
+            .unwrap();
+
+        let operator_id = format!("0x{}", "01".repeat(32));
+        let result_hash = format!("0x{}", "ab".repeat(32));
+        assert_eq!(
+            payload.to_string(),
+            format!(
+                "task_index=7 scheme=bls operator_id={} result_hash={}",
+                operator_id, result_hash
+            )
+        );
+        assert_eq!(
+            payload.with_quorum_number(1).to_string(),
+            format!(
+                "task_index=7 quorum=1 scheme=bls operator_id={} result_hash={}",
+                operator_id, result_hash
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ecdsa_signer_signs_task_response_digest() {
+        let signer = EcdsaTaskSigner::new(PrivateKeySigner::random());
+
+        let payload = signer
+            .sign_task_response(task_response(), &domain())
+            .await
+            .unwrap();
+        assert_eq!(payload.scheme(), SignatureScheme::Ecdsa);
+        assert_eq!(payload.task_response().resultHash, B256::repeat_byte(0xab));
+        let SignedTaskPayload::Ecdsa(signed) = payload else {
+            panic!("expected an ECDSA payload");
+        };
+        assert_eq!(signed.operator, signer.address());
+        assert_eq!(signed.recover_operator(&domain()), Some(signer.address()));
+        let other_chain = SigningDomain::new(1, domain().task_manager);
+        assert_ne!(
+            signed.recover_operator(&other_chain),
+            Some(signer.address())
+        );
+
+        // A response for another task does not verify against the same signature
+        let tampered = EcdsaSignedTaskResponse {
+            task_response: TaskResponse {
+                referenceTaskIndex: 8,
+                ..task_response()
+            },
+            ..signed
+        };
+        assert_ne!(tampered.recover_operator(&domain()), Some(signer.address()));
+    }
+
+    #[tokio::test]
+    async fn test_signers_verify_only_their_own_signatures() {
+        let signer = BlsTaskSigner::new(
+            BlsKeyPair::new("12345".to_string()).unwrap(),
+            OperatorId::repeat_byte(1),
+        );
+        let other = BlsTaskSigner::new(
+            BlsKeyPair::new("67890".to_string()).unwrap(),
+            OperatorId::repeat_byte(1),
+        );
+        let payload = signer
+            .sign_task_response(task_response(), &domain())
+            .await
+            .unwrap();
+        assert!(signer.verify(&payload, &domain()));
+        assert!(!signer.verify(&payload, &SigningDomain::new(1, domain().task_manager)));
+        assert!(!other.verify(&payload, &domain()));
+
+        let ecdsa_signer = EcdsaTaskSigner::new(PrivateKeySigner::random());
+        let ecdsa_payload = ecdsa_signer
+            .sign_task_response(task_response(), &domain())
+            .await
+            .unwrap();
+        assert!(ecdsa_signer.verify(&ecdsa_payload, &domain()));
+        assert!(!EcdsaTaskSigner::new(PrivateKeySigner::random()).verify(&ecdsa_payload, &domain()));
+        // A payload of the other scheme never verifies
+        assert!(!ecdsa_signer.verify(&payload, &domain()));
+        assert!(!signer.verify(&ecdsa_payload, &domain()));
+    }
+
+    #[test]
+    fn test_signer_backend_from_str() {
+        assert_eq!(
+            " Remote ".parse::<SignerBackend>().unwrap(),
+            SignerBackend::Remote
+        );
+        assert_eq!(
+            "file".parse::<SignerBackend>().unwrap(),
+            SignerBackend::Keystore
+        );
+        assert_eq!(SignerBackend::Env.to_string(), "env");
+        assert!(matches!(
+            "hsm".parse::<SignerBackend>(),
+            Err(SignerError::UnknownBackend(backend)) if backend == "hsm"
+        ));
+    }
+
+    /// Signing service holding a BLS and an ECDSA key, answering like a remote signer would
+    #[derive(Debug)]
+    struct MockSigningService {
+        bls: BlsKeyPair,
+        ecdsa: PrivateKeySigner,
+        /// Signs with this key instead, to impersonate a misconfigured service
+        wrong_bls: Option<BlsKeyPair>,
+        /// Announces the G2 key of this key instead, to impersonate a misconfigured service
+        wrong_g2: Option<BlsKeyPair>,
+        unavailable: bool,
+        requests: Mutex<Vec<(String, Value)>>,
+    }
+
+    impl MockSigningService {
+        fn new() -> Self {
+            Self {
+                bls: BlsKeyPair::new("12345".to_string()).unwrap(),
+                ecdsa: PrivateKeySigner::random(),
+                wrong_bls: None,
+                wrong_g2: None,
+                unavailable: false,
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl SigningTransport for MockSigningService {
+        async fn post(&self, path: &str, body: Value) -> Result<Value, SignerError> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push((path.to_string(), body.clone()));
+            if self.unavailable {
+                return Err(SignerError::Remote("connection refused".to_string()));
+            }
+            let request: RemoteSignRequest = serde_json::from_value(body).unwrap();
+            let reply = match (path, request.scheme, request.message_hash) {
+                ("/key", SignatureScheme::Bls, _) => serde_json::to_value(RemoteKey::Bls {
+                    public_key: self.bls.public_key(),
+                    public_key_g2: self.wrong_g2.as_ref().unwrap_or(&self.bls).public_key_g2(),
+                }),
+                ("/key", SignatureScheme::Ecdsa, _) => serde_json::to_value(RemoteKey::Ecdsa {
+                    address: self.ecdsa.address(),
+                }),
+                ("/sign", SignatureScheme::Bls, Some(hash)) => {
+                    let key_pair = self.wrong_bls.as_ref().unwrap_or(&self.bls);
+                    let signature = key_pair.sign_message(hash.as_ref());
+                    Ok(serde_json::json!({ "signature": signature }))
+                }
+                ("/sign", SignatureScheme::Ecdsa, Some(hash)) => {
+                    let signature = self.ecdsa.sign_hash_sync(&hash).unwrap();
+                    Ok(serde_json::json!({ "signature": signature }))
+                }
+                _ => panic!("unexpected request to {}", path),
+            };
+            Ok(reply.unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_signs_like_the_local_key() {
+        let service = Arc::new(MockSigningService::new());
+        let operator_id = operator_id_from_public_key(&service.bls.public_key());
+        let local_bls = BlsTaskSigner::new(service.bls.clone(), operator_id);
+        let local_ecdsa = EcdsaTaskSigner::new(service.ecdsa.clone());
+
+        let remote = RemoteTaskSigner::connect(service.clone(), SignatureScheme::Bls)
+            .await
+            .unwrap();
+        assert_eq!(remote.scheme(), SignatureScheme::Bls);
+        assert_eq!(remote.bls_operator_id(), Some(operator_id));
+        let payload = remote
+            .sign_task_response(task_response(), &domain())
+            .await
+            .unwrap();
+        assert!(remote.verify(&payload, &domain()));
+        assert!(local_bls.verify(&payload, &domain()));
+        // Only the signing hash left the operator
+        let requests = service.requests.lock().unwrap().clone();
+        assert_eq!(requests[1].0, "/sign");
+        assert_eq!(
+            requests[1].1["message_hash"],
+            domain().signing_hash(&task_response()).to_string()
+        );
+
+        let remote = RemoteTaskSigner::connect(service.clone(), SignatureScheme::Ecdsa)
+            .await
+            .unwrap();
+        assert_eq!(remote.bls_operator_id(), None);
+        let payload = remote
+            .sign_task_response(task_response(), &domain())
+            .await
+            .unwrap();
+        assert_eq!(payload.operator(), local_ecdsa.address().to_string());
+        assert!(remote.verify(&payload, &domain()));
+        assert!(local_ecdsa.verify(&payload, &domain()));
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_failures() {
+        // Signatures of another key than the service announced never verify
+        let service = Arc::new(MockSigningService {
+            wrong_bls: Some(BlsKeyPair::new("67890".to_string()).unwrap()),
+            ..MockSigningService::new()
+        });
+        let remote = RemoteTaskSigner::connect(service, SignatureScheme::Bls)
+            .await
+            .unwrap();
+        let payload = remote
+            .sign_task_response(task_response(), &domain())
+            .await
+            .unwrap();
+        assert!(!remote.verify(&payload, &domain()));
+
+        // Nor does a service announcing the G2 key of another key than its G1 key
+        let service = Arc::new(MockSigningService {
+            wrong_g2: Some(BlsKeyPair::new("67890".to_string()).unwrap()),
+            ..MockSigningService::new()
+        });
+        let err = RemoteTaskSigner::connect(service, SignatureScheme::Bls)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SignerError::Remote(_)));
+
+        let unavailable = Arc::new(MockSigningService {
+            unavailable: true,
+            ..MockSigningService::new()
+        });
+        let err = RemoteTaskSigner::connect(unavailable, SignatureScheme::Ecdsa)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SignerError::Remote(_)));
+        assert_eq!(err.status(), TaskStatus::SigningFailed);
+    }
+}
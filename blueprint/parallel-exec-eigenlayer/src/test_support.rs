@@ -0,0 +1,261 @@
+//! Mocks shared by the tests of several modules: an HTTP server answering with canned
+//! responses, a JSON-RPC node, and in-process stand-ins for the keystore, the helper API and
+//! the aggregator.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::time::sleep;
+
+use crate::api_client::{ApiClientError, HttpRequest, HttpResponse, HttpTransport};
+use crate::bls_keys::BlsKeystore;
+use crate::contexts::client::{AggregatorClientError, AggregatorTransport, SignedTaskResponse};
+use crate::signer::{EcdsaSignedTaskResponse, SignedTaskPayload};
+
+/// Reads the next HTTP request from `stream`, returning its head and body, or `None` once the
+/// connection is closed
+pub async fn read_request<S: AsyncRead + Unpin>(stream: &mut S) -> Option<(String, Vec<u8>)> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&data[..end]).into_owned();
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            if data.len() >= end + 4 + length {
+                return Some((head, data[end + 4..end + 4 + length].to_vec()));
+            }
+        }
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+/// Formats an HTTP response with a JSON `body`, sending the raw header lines `headers` along
+pub fn json_response(status: u16, headers: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}\r\n{}",
+        status,
+        body.len(),
+        headers,
+        body
+    )
+}
+
+/// Minimal HTTP server answering each connection with the next canned response
+/// (the last one is repeated) and recording the request head it received.
+pub struct MockServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub async fn start(responses: Vec<(u16, String)>) -> Self {
+        Self::start_delayed(responses, Duration::ZERO).await
+    }
+
+    /// Like [`MockServer::start`], but waits `delay` before answering each request
+    pub async fn start_delayed(responses: Vec<(u16, String)>, delay: Duration) -> Self {
+        let responses = responses
+            .into_iter()
+            .map(|(status, body)| (status, String::new(), body))
+            .collect();
+        Self::start_with_headers(responses, delay).await
+    }
+
+    /// Like [`MockServer::start_delayed`], sending the raw header lines given along with
+    /// every response
+    pub async fn start_with_headers(
+        responses: Vec<(u16, String, String)>,
+        delay: Duration,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            let mut served = 0;
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let Some((head, _)) = read_request(&mut stream).await else {
+                    continue;
+                };
+                recorded.lock().unwrap().push(head);
+
+                sleep(delay).await;
+                let (status, headers, body) = &responses[served.min(responses.len() - 1)];
+                served += 1;
+                let headers = format!("{}Connection: close\r\n", headers);
+                let _ = stream
+                    .write_all(json_response(*status, &headers, body).as_bytes())
+                    .await;
+            }
+        });
+
+        Self { addr, requests }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Reads the next JSON-RPC request from `stream`
+pub async fn read_rpc_request<S: AsyncRead + Unpin>(stream: &mut S) -> Option<Value> {
+    let (_, body) = read_request(stream).await?;
+    serde_json::from_slice(&body).ok()
+}
+
+/// Starts a JSON-RPC node answering every call with `answer` of the called method and its
+/// params, keeping connections open between calls, and returns its URL
+pub async fn serve_json_rpc(
+    answer: impl Fn(&str, &Value) -> Value + Send + Sync + 'static,
+) -> String {
+    let answer = Arc::new(answer);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let answer = Arc::clone(&answer);
+            tokio::spawn(async move {
+                while let Some(request) = read_rpc_request(&mut stream).await {
+                    let result = answer(
+                        request["method"].as_str().unwrap_or_default(),
+                        &request["params"],
+                    );
+                    let reply = json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "result": result,
+                    })
+                    .to_string();
+                    let response = json_response(200, "", &reply);
+                    if stream.write_all(response.as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    url
+}
+
+/// Keystore stub returning canned results for each step. Keys that load are better held in a
+/// [`BlsSecret`](crate::bls_keys::BlsSecret)
+pub struct StubKeystore {
+    pub public: Result<(), String>,
+    pub secret: Result<Option<String>, String>,
+}
+
+impl StubKeystore {
+    /// Keystore without any BLS key
+    pub fn empty() -> Self {
+        Self {
+            public: Err("keystore is empty".to_string()),
+            secret: Ok(None),
+        }
+    }
+}
+
+impl BlsKeystore for StubKeystore {
+    type Public = ();
+
+    fn first_local_bls(&self) -> Result<(), String> {
+        self.public.clone()
+    }
+
+    fn expose_bls_secret(&self, _public: &()) -> Result<Option<String>, String> {
+        self.secret.clone()
+    }
+}
+
+/// Helper API answering every request with the same body
+#[derive(Debug)]
+pub struct MockApi {
+    pub body: String,
+}
+
+#[async_trait]
+impl HttpTransport for MockApi {
+    async fn send(&self, _request: HttpRequest) -> Result<HttpResponse, ApiClientError> {
+        // Give concurrently running tasks a chance to interleave
+        tokio::task::yield_now().await;
+        Ok(HttpResponse {
+            status: 200,
+            body: self.body.clone(),
+            retry_after: None,
+        })
+    }
+}
+
+/// Aggregator recording every signed task response it receives
+#[derive(Debug, Default)]
+pub struct MockAggregator {
+    received: Mutex<Vec<SignedTaskPayload>>,
+    /// Never answers once it received this many responses
+    hang_after: Option<usize>,
+}
+
+impl MockAggregator {
+    /// Aggregator that never answers once it received `hang_after` responses
+    pub fn hanging_after(hang_after: usize) -> Self {
+        Self {
+            hang_after: Some(hang_after),
+            ..Default::default()
+        }
+    }
+
+    pub fn received(&self) -> Vec<SignedTaskPayload> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl AggregatorTransport for MockAggregator {
+    async fn send_signed_task_response(
+        &self,
+        response: SignedTaskResponse,
+    ) -> Result<(), AggregatorClientError> {
+        tokio::task::yield_now().await;
+        if self
+            .hang_after
+            .is_some_and(|hang_after| self.received().len() >= hang_after)
+        {
+            std::future::pending::<()>().await;
+        }
+        self.received
+            .lock()
+            .unwrap()
+            .push(SignedTaskPayload::Bls(response));
+        Ok(())
+    }
+
+    async fn send_ecdsa_signed_task_response(
+        &self,
+        response: EcdsaSignedTaskResponse,
+    ) -> Result<(), AggregatorClientError> {
+        tokio::task::yield_now().await;
+        self.received
+            .lock()
+            .unwrap()
+            .push(SignedTaskPayload::Ecdsa(response));
+        Ok(())
+    }
+}